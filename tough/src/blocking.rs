@@ -0,0 +1,635 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A blocking client API, for programs that do not otherwise use an async runtime.
+//!
+//! The types in this module wrap their async counterparts and run them to completion on a
+//! runtime that they own, so metadata and targets are verified exactly as they are by the async
+//! API. Targets are read through [`std::io::Read`].
+//!
+//! These types must not be used from within an async runtime; doing so panics, because they
+//! block the thread to drive their own runtime. This module is only available with the `blocking`
+//! feature.
+//!
+//! ```no_run
+//! # use std::io::Read;
+//! # use tough::blocking::RepositoryLoader;
+//! # use tough::TargetName;
+//! # use url::Url;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let root = std::fs::read("root.json")?;
+//! let repository = RepositoryLoader::new(
+//!     &root,
+//!     Url::parse("https://example.com/metadata/")?,
+//!     Url::parse("https://example.com/targets/")?,
+//! )
+//! .load()?;
+//!
+//! let mut contents = Vec::new();
+//! if let Some(mut reader) = repository.read_target(&TargetName::new("file.txt")?)? {
+//!     reader.read_to_end(&mut contents)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::clock::{Clock, TrustedTimePolicy};
+use crate::datastore::Datastore;
+use crate::editor::signed::{PathExists, SignedRepository as AsyncSignedRepository};
+use crate::editor::RepositoryEditor as AsyncRepositoryEditor;
+use crate::error::{self, Result};
+use crate::key_source::KeySource;
+use crate::observer::Observer;
+use crate::schema::{
+    DelegatedRole, HashAlgorithm, PathSet, Root, Signed, Snapshot, Target, Targets, Timestamp,
+};
+use crate::{
+    ExpirationEnforcement, Limits, Mirror, Prefix, RateLimit, RoleExpiration, TargetMatch,
+    TargetName, TargetQuery, Transport,
+};
+use bytes::{Buf, Bytes};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::StreamExt;
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+use snafu::ResultExt;
+use std::convert::TryInto;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Read};
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use url::Url;
+
+/// Builds the runtime that a blocking [`Repository`] or [`RepositoryEditor`] runs on.
+fn runtime() -> Result<Arc<Runtime>> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map(Arc::new)
+        .context(error::BlockingRuntimeSnafu)
+}
+
+/// A blocking version of [`crate::RepositoryLoader`].
+#[derive(Debug, Clone)]
+pub struct RepositoryLoader<'a> {
+    inner: crate::RepositoryLoader<'a>,
+}
+
+impl<'a> RepositoryLoader<'a> {
+    /// Create a new `RepositoryLoader`. See [`crate::RepositoryLoader::new`].
+    pub fn new(root: &'a impl AsRef<[u8]>, metadata_base_url: Url, targets_base_url: Url) -> Self {
+        Self {
+            inner: crate::RepositoryLoader::new(root, metadata_base_url, targets_base_url),
+        }
+    }
+
+    /// Create a new `RepositoryLoader` for an offline bundle. See
+    /// [`crate::RepositoryLoader::from_bundle`].
+    #[cfg(feature = "archive")]
+    pub fn from_bundle<P: AsRef<Path>>(root: &'a impl AsRef<[u8]>, bundle: P) -> Result<Self> {
+        crate::RepositoryLoader::from_bundle(root, bundle).map(Self::from)
+    }
+
+    /// Load and verify TUF repository metadata, on a new runtime that the returned [`Repository`]
+    /// keeps.
+    pub fn load(self) -> Result<Repository> {
+        let runtime = runtime()?;
+        let inner = runtime.block_on(self.inner.load())?;
+        Ok(Repository { inner, runtime })
+    }
+
+    /// Set the transport. See [`crate::RepositoryLoader::transport`].
+    #[must_use]
+    pub fn transport<T: Transport + Send + Sync + 'static>(self, transport: T) -> Self {
+        self.inner.transport(transport).into()
+    }
+
+    /// Add a [`Mirror`] to fall back to. See [`crate::RepositoryLoader::mirror`].
+    #[must_use]
+    pub fn mirror(self, mirror: Mirror) -> Self {
+        self.inner.mirror(mirror).into()
+    }
+
+    /// Add an [`Observer`]. See [`crate::RepositoryLoader::observer`].
+    #[must_use]
+    pub fn observer<O: Observer + 'static>(self, observer: O) -> Self {
+        self.inner.observer(observer).into()
+    }
+
+    /// Set the repository [`Limits`]. See [`crate::RepositoryLoader::limits`].
+    #[must_use]
+    pub fn limits(self, limits: Limits) -> Self {
+        self.inner.limits(limits).into()
+    }
+
+    /// Limit the download rate to a [`RateLimit`]. See [`crate::RepositoryLoader::rate_limit`].
+    #[must_use]
+    pub fn rate_limit(self, rate_limit: RateLimit) -> Self {
+        self.inner.rate_limit(rate_limit).into()
+    }
+
+    /// Set a `datastore` directory path. See [`crate::RepositoryLoader::datastore`].
+    #[must_use]
+    pub fn datastore<P: Into<PathBuf>>(self, datastore: P) -> Self {
+        self.inner.datastore(datastore).into()
+    }
+
+    /// Set the [`Datastore`]. See [`crate::RepositoryLoader::datastore_backend`].
+    #[must_use]
+    pub fn datastore_backend<D: Datastore + 'static>(self, datastore: D) -> Self {
+        self.inner.datastore_backend(datastore).into()
+    }
+
+    /// Set the [`Clock`]. See [`crate::RepositoryLoader::clock`].
+    #[must_use]
+    pub fn clock<C: Clock + 'static>(self, clock: C) -> Self {
+        self.inner.clock(clock).into()
+    }
+
+    /// Set the [`TrustedTimePolicy`]. See [`crate::RepositoryLoader::trusted_time_policy`].
+    #[must_use]
+    pub fn trusted_time_policy(self, policy: TrustedTimePolicy) -> Self {
+        self.inner.trusted_time_policy(policy).into()
+    }
+
+    /// Set the [`ExpirationEnforcement`]. See
+    /// [`crate::RepositoryLoader::expiration_enforcement`].
+    #[must_use]
+    pub fn expiration_enforcement(self, exp: ExpirationEnforcement) -> Self {
+        self.inner.expiration_enforcement(exp).into()
+    }
+}
+
+impl<'a> From<crate::RepositoryLoader<'a>> for RepositoryLoader<'a> {
+    fn from(inner: crate::RepositoryLoader<'a>) -> Self {
+        Self { inner }
+    }
+}
+
+/// A blocking version of [`crate::Repository`], created by [`RepositoryLoader::load`].
+#[derive(Debug, Clone)]
+pub struct Repository {
+    inner: crate::Repository,
+    runtime: Arc<Runtime>,
+}
+
+impl Repository {
+    /// Update the repository metadata. See [`crate::Repository::refresh`].
+    pub fn refresh(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.refresh())
+    }
+
+    /// Returns the list of targets present in the repository. See
+    /// [`crate::Repository::targets`].
+    pub fn targets(&self) -> &Signed<Targets> {
+        self.inner.targets()
+    }
+
+    /// Returns a reference to the signed root.
+    pub fn root(&self) -> &Signed<Root> {
+        self.inner.root()
+    }
+
+    /// Returns a reference to the signed snapshot.
+    pub fn snapshot(&self) -> &Signed<Snapshot> {
+        self.inner.snapshot()
+    }
+
+    /// Returns a reference to the signed timestamp.
+    pub fn timestamp(&self) -> &Signed<Timestamp> {
+        self.inner.timestamp()
+    }
+
+    /// Returns all targets, including all target files delegated by targets. See
+    /// [`crate::Repository::all_targets`].
+    pub fn all_targets(&mut self) -> Result<impl Iterator<Item = (&TargetName, &Target)> + '_> {
+        self.runtime.block_on(self.inner.all_targets())
+    }
+
+    /// Fetches and verifies every delegated targets role that has not been loaded yet. See
+    /// [`crate::Repository::load_delegated_targets`].
+    pub fn load_delegated_targets(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.load_delegated_targets())
+    }
+
+    /// Searches the repository metadata for the target `name`. See
+    /// [`crate::Repository::find_target`].
+    pub fn find_target(&self, name: &TargetName) -> Result<Option<Target>> {
+        self.runtime.block_on(self.inner.find_target(name))
+    }
+
+    /// Returns the targets that match `query`. See [`crate::Repository::query_targets`].
+    pub fn query_targets<T: DeserializeOwned>(
+        &mut self,
+        query: &TargetQuery<T>,
+    ) -> Result<Vec<TargetMatch<T>>> {
+        self.runtime.block_on(self.inner.query_targets(query))
+    }
+
+    /// Fetches a target from the repository, returning `Ok(None)` if the target is not listed in
+    /// the repository metadata. See [`crate::Repository::read_target`].
+    ///
+    /// The returned reader provides access to the target contents before its checksum is
+    /// validated. If the maximum size is reached or there is a checksum mismatch, reading fails
+    /// with an error whose source is an [`error::Error`]. **Consumers of this library must not use
+    /// data from the reader if it returns an error.**
+    pub fn read_target(&self, name: &TargetName) -> Result<Option<TargetReader>> {
+        let stream = self.runtime.block_on(self.inner.read_target(name))?;
+        Ok(stream.map(|stream| TargetReader {
+            stream: stream.boxed(),
+            chunk: Bytes::new(),
+            runtime: Arc::clone(&self.runtime),
+        }))
+    }
+
+    /// Fetches a target from the repository and saves it to `outdir`. See
+    /// [`crate::Repository::save_target`].
+    pub fn save_target<P>(&self, name: &TargetName, outdir: P, prepend: Prefix) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.save_target(name, outdir, prepend))
+    }
+
+    /// Fetches a target from the repository and saves it to `outdir`, resuming an earlier
+    /// interrupted download. See [`crate::Repository::save_target_resumable`].
+    pub fn save_target_resumable<P>(
+        &self,
+        name: &TargetName,
+        outdir: P,
+        prepend: Prefix,
+    ) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.save_target_resumable(name, outdir, prepend))
+    }
+
+    /// Fetches the targets `names` and saves them to `outdir`. See
+    /// [`crate::Repository::save_targets`].
+    pub fn save_targets<P>(
+        &self,
+        names: &[TargetName],
+        outdir: P,
+        prepend: Prefix,
+        jobs: NonZeroUsize,
+    ) -> Vec<(TargetName, Result<()>)>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.save_targets(names, outdir, prepend, jobs))
+    }
+
+    /// Cache an entire or partial repository to disk. See [`crate::Repository::cache`].
+    pub fn cache<P1, P2, S>(
+        &self,
+        metadata_outdir: P1,
+        targets_outdir: P2,
+        targets_subset: Option<&[S]>,
+        cache_root_chain: bool,
+    ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        self.runtime.block_on(self.inner.cache(
+            metadata_outdir,
+            targets_outdir,
+            targets_subset,
+            cache_root_chain,
+        ))
+    }
+
+    /// Cache the repository metadata to disk. See [`crate::Repository::cache_metadata`].
+    pub fn cache_metadata<P>(&self, metadata_outdir: P, cache_root_chain: bool) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.cache_metadata(metadata_outdir, cache_root_chain))
+    }
+
+    /// Writes an offline bundle of the repository to `path`. See
+    /// [`crate::Repository::export_bundle`].
+    #[cfg(feature = "archive")]
+    pub fn export_bundle<P, S>(&self, path: P, targets_subset: Option<&[S]>) -> Result<()>
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        self.runtime
+            .block_on(self.inner.export_bundle(path, targets_subset))
+    }
+
+    /// Returns when the metadata of each loaded role expires. See
+    /// [`crate::Repository::expirations`].
+    pub fn expirations(&self) -> Vec<RoleExpiration> {
+        self.inner.expirations()
+    }
+
+    /// Returns the roles whose metadata has expired or expires before `time`. See
+    /// [`crate::Repository::expiring_before`].
+    pub fn expiring_before(&self, time: DateTime<Utc>) -> Vec<RoleExpiration> {
+        self.inner.expiring_before(time)
+    }
+
+    /// Returns the roles whose metadata has expired or expires within `window`. See
+    /// [`crate::Repository::expiring_within`].
+    pub fn expiring_within(&self, window: TimeDelta) -> Result<Vec<RoleExpiration>> {
+        self.runtime.block_on(self.inner.expiring_within(window))
+    }
+
+    /// Returns the base URL of the mirror that most recently served the metadata file at `path`.
+    /// See [`crate::Repository::metadata_mirror`].
+    pub fn metadata_mirror(&self, path: &str) -> Option<Url> {
+        self.inner.metadata_mirror(path)
+    }
+
+    /// Returns the base URL of the mirror that most recently served the target `name`. See
+    /// [`crate::Repository::target_mirror`].
+    pub fn target_mirror(&self, name: &TargetName) -> Option<Url> {
+        self.inner.target_mirror(name)
+    }
+
+    /// Return the named `DelegatedRole` if found. See [`crate::Repository::delegated_role`].
+    pub fn delegated_role(&self, name: &str) -> Option<&DelegatedRole> {
+        self.inner.delegated_role(name)
+    }
+}
+
+/// Reads the contents of a target, as returned by [`Repository::read_target`].
+pub struct TargetReader {
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send>>,
+    /// The rest of the last chunk read from `stream`.
+    chunk: Bytes,
+    runtime: Arc<Runtime>,
+}
+
+impl Read for TargetReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.runtime.block_on(self.stream.next()) {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(e)) => return Err(io::Error::other(e)),
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        self.chunk.copy_to_slice(&mut buf[..len]);
+        Ok(len)
+    }
+}
+
+impl Debug for TargetReader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TargetReader")
+            .field("buffered", &self.chunk.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A blocking version of [`crate::editor::RepositoryEditor`].
+#[derive(Debug)]
+pub struct RepositoryEditor {
+    inner: AsyncRepositoryEditor,
+    runtime: Arc<Runtime>,
+}
+
+impl RepositoryEditor {
+    /// Create a new, bare `RepositoryEditor`. See [`crate::editor::RepositoryEditor::new`].
+    pub fn new<P>(root_path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let runtime = runtime()?;
+        let inner = runtime.block_on(AsyncRepositoryEditor::new(root_path))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Create a `RepositoryEditor` from an existing repository. See
+    /// [`crate::editor::RepositoryEditor::from_repo`].
+    pub fn from_repo<P>(root_path: P, repo: Repository) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let Repository { inner, runtime } = repo;
+        let inner = runtime.block_on(AsyncRepositoryEditor::from_repo(root_path, inner))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Builds and signs each required role. See [`crate::editor::RepositoryEditor::sign`].
+    pub fn sign(self, keys: &[Box<dyn KeySource>]) -> Result<SignedRepository> {
+        let inner = self.runtime.block_on(self.inner.sign(keys))?;
+        Ok(SignedRepository {
+            inner,
+            runtime: self.runtime,
+        })
+    }
+
+    /// Add an existing `Targets` struct to the repository. See
+    /// [`crate::editor::RepositoryEditor::targets`].
+    pub fn targets(&mut self, targets: Signed<Targets>) -> Result<&mut Self> {
+        self.inner.targets(targets)?;
+        Ok(self)
+    }
+
+    /// Add an existing `Snapshot` to the repository. See
+    /// [`crate::editor::RepositoryEditor::snapshot`].
+    pub fn snapshot(&mut self, snapshot: Snapshot) -> Result<&mut Self> {
+        self.inner.snapshot(snapshot)?;
+        Ok(self)
+    }
+
+    /// Add an existing `Timestamp` to the repository. See
+    /// [`crate::editor::RepositoryEditor::timestamp`].
+    pub fn timestamp(&mut self, timestamp: Timestamp) -> Result<&mut Self> {
+        self.inner.timestamp(timestamp)?;
+        Ok(self)
+    }
+
+    /// Add a `Target` to the repository.
+    pub fn add_target<T, E>(&mut self, name: T, target: Target) -> Result<&mut Self>
+    where
+        T: TryInto<TargetName, Error = E>,
+        E: Display,
+    {
+        self.inner.add_target(name, target)?;
+        Ok(self)
+    }
+
+    /// Remove a `Target` from the repository.
+    pub fn remove_target(&mut self, name: &TargetName) -> Result<&mut Self> {
+        self.inner.remove_target(name)?;
+        Ok(self)
+    }
+
+    /// Set the hash algorithms listed for targets added by path. See
+    /// [`crate::editor::RepositoryEditor::target_hash_algorithms`].
+    pub fn target_hash_algorithms(&mut self, algorithms: &[HashAlgorithm]) -> &mut Self {
+        self.inner.target_hash_algorithms(algorithms);
+        self
+    }
+
+    /// Add a target to the repository using its path. See
+    /// [`crate::editor::RepositoryEditor::add_target_path`].
+    pub fn add_target_path<P>(&mut self, target_path: P) -> Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.add_target_path(target_path))?;
+        Ok(self)
+    }
+
+    /// Add a list of target paths to the repository. See
+    /// [`crate::editor::RepositoryEditor::add_target_paths`].
+    pub fn add_target_paths<P>(&mut self, targets: Vec<P>) -> Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.add_target_paths(targets))?;
+        Ok(self)
+    }
+
+    /// Remove all targets from this repo.
+    pub fn clear_targets(&mut self) -> Result<&mut Self> {
+        self.inner.clear_targets()?;
+        Ok(self)
+    }
+
+    /// Delegate target with name as a `DelegatedRole`. See
+    /// [`crate::editor::RepositoryEditor::delegate_role`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn delegate_role(
+        &mut self,
+        name: &str,
+        key_source: &[Box<dyn KeySource>],
+        paths: PathSet,
+        threshold: NonZeroU64,
+        expiration: DateTime<Utc>,
+        version: NonZeroU64,
+    ) -> Result<&mut Self> {
+        self.runtime.block_on(
+            self.inner
+                .delegate_role(name, key_source, paths, threshold, expiration, version),
+        )?;
+        Ok(self)
+    }
+
+    /// Set whether the delegation to `role` is terminating. See
+    /// [`crate::editor::RepositoryEditor::terminating`].
+    pub fn terminating(&mut self, role: &str, terminating: bool) -> Result<&mut Self> {
+        self.inner.terminating(role, terminating)?;
+        Ok(self)
+    }
+
+    /// Set the `Snapshot` version.
+    pub fn snapshot_version(&mut self, snapshot_version: NonZeroU64) -> &mut Self {
+        self.inner.snapshot_version(snapshot_version);
+        self
+    }
+
+    /// Set the `Snapshot` expiration.
+    pub fn snapshot_expires(&mut self, snapshot_expires: DateTime<Utc>) -> &mut Self {
+        self.inner.snapshot_expires(snapshot_expires);
+        self
+    }
+
+    /// Set the `Targets` version.
+    pub fn targets_version(&mut self, targets_version: NonZeroU64) -> Result<&mut Self> {
+        self.inner.targets_version(targets_version)?;
+        Ok(self)
+    }
+
+    /// Set the `Targets` expiration.
+    pub fn targets_expires(&mut self, targets_expires: DateTime<Utc>) -> Result<&mut Self> {
+        self.inner.targets_expires(targets_expires)?;
+        Ok(self)
+    }
+
+    /// Set the `Timestamp` version.
+    pub fn timestamp_version(&mut self, timestamp_version: NonZeroU64) -> &mut Self {
+        self.inner.timestamp_version(timestamp_version);
+        self
+    }
+
+    /// Set the `Timestamp` expiration.
+    pub fn timestamp_expires(&mut self, timestamp_expires: DateTime<Utc>) -> &mut Self {
+        self.inner.timestamp_expires(timestamp_expires);
+        self
+    }
+
+    /// Signs the role being edited. See
+    /// [`crate::editor::RepositoryEditor::sign_targets_editor`].
+    pub fn sign_targets_editor(&mut self, keys: &[Box<dyn KeySource>]) -> Result<&mut Self> {
+        self.runtime
+            .block_on(self.inner.sign_targets_editor(keys))?;
+        Ok(self)
+    }
+
+    /// Changes the role being edited. See
+    /// [`crate::editor::RepositoryEditor::change_delegated_targets`].
+    pub fn change_delegated_targets(&mut self, role: &str) -> Result<&mut Self> {
+        self.inner.change_delegated_targets(role)?;
+        Ok(self)
+    }
+}
+
+/// A blocking version of [`crate::editor::signed::SignedRepository`], created by
+/// [`RepositoryEditor::sign`].
+#[derive(Debug)]
+pub struct SignedRepository {
+    inner: AsyncSignedRepository,
+    runtime: Arc<Runtime>,
+}
+
+impl SignedRepository {
+    /// Writes the metadata to the given directory. See
+    /// [`crate::editor::signed::SignedRepository::write`].
+    pub fn write<P>(&self, outdir: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime.block_on(self.inner.write(outdir))
+    }
+
+    /// Symlinks the targets found in `indir` to `outdir`. See
+    /// [`crate::editor::signed::SignedRepository::link_targets`].
+    pub fn link_targets<P1, P2>(
+        &self,
+        indir: P1,
+        outdir: P2,
+        replace_behavior: PathExists,
+    ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.link_targets(indir, outdir, replace_behavior))
+    }
+
+    /// Copies the targets found in `indir` to `outdir`. See
+    /// [`crate::editor::signed::SignedRepository::copy_targets`].
+    pub fn copy_targets<P1, P2>(
+        &self,
+        indir: P1,
+        outdir: P2,
+        replace_behavior: PathExists,
+    ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.copy_targets(indir, outdir, replace_behavior))
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Offline repository bundles: a single archive holding the metadata of a repository, and
+//! optionally some of its targets, which can be carried to a host without network access.
+//!
+//! A bundle is an uncompressed tar archive with a `metadata` directory and a `targets` directory,
+//! laid out like the output of [`Repository::cache`]. It is written with
+//! [`Repository::export_bundle`] and loaded with [`RepositoryLoader::from_bundle`].
+
+use crate::error::{self, Result};
+use crate::{ArchiveTransport, Repository, RepositoryLoader};
+use snafu::ResultExt;
+use std::path::Path;
+use tempfile::TempDir;
+use url::Url;
+
+impl Repository {
+    /// Writes an offline bundle of the repository to `path`, which can be loaded with
+    /// [`RepositoryLoader::from_bundle`].
+    ///
+    /// The bundle holds every version of the root metadata, the timestamp, snapshot and targets
+    /// metadata, and the metadata of every delegated targets role. `targets_subset` is the list of
+    /// targets to include. If no subset is specified (`None`), then *all* targets are included.
+    pub async fn export_bundle<P, S>(&self, path: P, targets_subset: Option<&[S]>) -> Result<()>
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        let path = path.as_ref();
+        let staging = TempDir::new().context(error::BundleStagingSnafu)?;
+        let metadata_dir = staging.path().join("metadata");
+        let targets_dir = staging.path().join("targets");
+        self.cache(&metadata_dir, &targets_dir, targets_subset, true)
+            .await?;
+
+        let bundle_path = path.to_owned();
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            let file = std::fs::File::create(&bundle_path)?;
+            let mut builder = tar::Builder::new(file);
+            builder.append_dir_all("metadata", &metadata_dir)?;
+            builder.append_dir_all("targets", &targets_dir)?;
+            builder.into_inner()?.sync_all()
+        })
+        .await
+        // We do not cancel the task nor do we expect it to panic
+        .unwrap_or_else(|_| unreachable!())
+        .context(error::BundleWriteSnafu { path })
+    }
+}
+
+impl<'a> RepositoryLoader<'a> {
+    /// Create a new `RepositoryLoader` for an offline bundle written by
+    /// [`Repository::export_bundle`].
+    ///
+    /// `root` is the content of a trusted root metadata file, as in [`RepositoryLoader::new`]. The
+    /// metadata in the bundle is verified the same way as metadata fetched from a repository, and
+    /// targets are read out of the bundle with an [`ArchiveTransport`].
+    pub fn from_bundle<P: AsRef<Path>>(root: &'a impl AsRef<[u8]>, bundle: P) -> Result<Self> {
+        let transport = ArchiveTransport::open(bundle)?;
+        Ok(Self::new(root, bundle_url("metadata/")?, bundle_url("targets/")?).transport(transport))
+    }
+}
+
+fn bundle_url(dir: &str) -> Result<Url> {
+    let url = format!("{}:///{dir}", ArchiveTransport::SCHEME);
+    Url::parse(&url).context(error::ParseUrlSnafu { url })
+}
@@ -1,12 +1,13 @@
 use crate::error::{self, Result};
-use crate::fetch::{fetch_max_size, fetch_sha256};
-use crate::schema::{RoleType, Target};
-use crate::transport::IntoVec;
+use crate::io::Digests;
+use crate::observer::{Event, Observer};
+use crate::schema::{RoleType, Signed, Target, Targets};
 use crate::{encode_filename, Prefix, Repository, TargetName};
 use bytes::Bytes;
 use futures::StreamExt;
 use futures_core::stream::BoxStream;
 use snafu::{futures::TryStreamExt, OptionExt, ResultExt};
+use std::num::NonZeroUsize;
 use std::path::Path;
 use tokio::io::AsyncWriteExt;
 
@@ -26,6 +27,34 @@ impl Repository {
         targets_subset: Option<&[S]>,
         cache_root_chain: bool,
     ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        self.cache_with_jobs(
+            metadata_outdir,
+            targets_outdir,
+            targets_subset,
+            cache_root_chain,
+            NonZeroUsize::MIN,
+        )
+        .await
+    }
+
+    /// Cache an entire or partial repository to disk like [`Repository::cache`], downloading up to
+    /// `jobs` targets at once.
+    ///
+    /// Every target is attempted even if some fail. If any target fails, the error for the first
+    /// failed target (in the order they are listed) is returned, and metadata is not cached.
+    pub async fn cache_with_jobs<P1, P2, S>(
+        &self,
+        metadata_outdir: P1,
+        targets_outdir: P2,
+        targets_subset: Option<&[S]>,
+        cache_root_chain: bool,
+        jobs: NonZeroUsize,
+    ) -> Result<()>
     where
         P1: AsRef<Path>,
         P2: AsRef<Path>,
@@ -43,21 +72,33 @@ impl Repository {
                 path: targets_outdir.as_ref(),
             })?;
 
+        // Load the entire delegation tree, since all of its metadata will be cached.
+        let targets = self.targets_with_delegations().await?;
+
         // Fetch targets and save them to the outdir
-        if let Some(target_list) = targets_subset {
-            for raw_name in target_list {
-                let target_name = TargetName::new(raw_name.as_ref())?;
-                self.cache_target(&targets_outdir, &target_name).await?;
-            }
+        let target_names = if let Some(target_list) = targets_subset {
+            target_list
+                .iter()
+                .map(|raw_name| TargetName::new(raw_name.as_ref()))
+                .collect::<Result<Vec<_>>>()?
         } else {
-            let targets = &self.targets.signed.targets_map();
-            for target_name in targets.keys() {
-                self.cache_target(&targets_outdir, target_name).await?;
-            }
+            targets.signed.targets_map().into_keys().collect()
+        };
+        // Retain the digest-prepended filename if consistent snapshots are used.
+        let prepend = if self.consistent_snapshot {
+            Prefix::Digest
+        } else {
+            Prefix::None
+        };
+        for (_, result) in self
+            .save_targets(&target_names, &targets_outdir, prepend, jobs)
+            .await
+        {
+            result?;
         }
 
         // Cache all metadata
-        self.cache_metadata_impl(&metadata_outdir).await?;
+        self.cache_metadata_impl(&targets, &metadata_outdir).await?;
 
         if cache_root_chain {
             self.cache_root_chain(&metadata_outdir).await?;
@@ -81,7 +122,8 @@ impl Repository {
                 path: metadata_outdir.as_ref(),
             })?;
 
-        self.cache_metadata_impl(&metadata_outdir).await?;
+        let targets = self.targets_with_delegations().await?;
+        self.cache_metadata_impl(&targets, &metadata_outdir).await?;
 
         if cache_root_chain {
             self.cache_root_chain(metadata_outdir).await?;
@@ -89,8 +131,13 @@ impl Repository {
         Ok(())
     }
 
-    /// Cache repository metadata files, including delegated targets metadata
-    async fn cache_metadata_impl<P>(&self, metadata_outdir: P) -> Result<()>
+    /// Cache repository metadata files, including the delegated targets metadata of every role
+    /// in `targets`
+    async fn cache_metadata_impl<P>(
+        &self,
+        targets: &Signed<Targets>,
+        metadata_outdir: P,
+    ) -> Result<()>
     where
         P: AsRef<Path>,
     {
@@ -117,7 +164,7 @@ impl Repository {
         )
         .await?;
 
-        for name in self.targets.signed.role_names() {
+        for name in targets.signed.role_names() {
             if let Some(filename) = self.delegated_filename(name) {
                 self.cache_file_from_transport(
                     filename.as_str(),
@@ -193,50 +240,27 @@ impl Repository {
         max_size_specifier: &'static str,
         outdir: P,
     ) -> Result<()> {
-        let url = self
-            .metadata_base_url
-            .join(filename)
-            .with_context(|_| error::JoinUrlSnafu {
-                path: filename,
-                url: self.metadata_base_url.clone(),
-            })?;
-        let stream = fetch_max_size(
-            self.transport.as_ref(),
-            url.clone(),
-            max_size,
-            max_size_specifier,
-        )
-        .await?;
+        let root_file_data = self
+            .mirrors
+            .fetch_metadata(
+                self.transport.as_ref(),
+                filename,
+                max_size,
+                max_size_specifier,
+                None,
+            )
+            .await?;
         let outpath = outdir.as_ref().join(filename);
         let mut file = tokio::fs::File::create(&outpath).await.with_context(|_| {
             error::CacheFileWriteSnafu {
                 path: outpath.clone(),
             }
         })?;
-        let root_file_data = stream
-            .into_vec()
-            .await
-            .context(error::TransportSnafu { url })?;
         file.write_all(&root_file_data)
             .await
             .context(error::CacheFileWriteSnafu { path: outpath })
     }
 
-    /// Saves a signed target to the specified `outdir`. Retains the digest-prepended filename if
-    /// consistent snapshots are used.
-    async fn cache_target<P: AsRef<Path>>(&self, outdir: P, name: &TargetName) -> Result<()> {
-        self.save_target(
-            name,
-            outdir,
-            if self.consistent_snapshot {
-                Prefix::Digest
-            } else {
-                Prefix::None
-            },
-        )
-        .await
-    }
-
     /// Gets the max size of the snapshot.json file as specified by the timestamp file.
     fn max_snapshot_size(&self) -> Result<Option<u64>> {
         let snapshot_meta =
@@ -251,48 +275,65 @@ impl Repository {
         Ok(snapshot_meta.length)
     }
 
-    /// Prepends the target digest to the name if using consistent snapshots. Returns both the
-    /// digest and the filename.
-    pub(crate) fn target_digest_and_filename(
-        &self,
-        target: &Target,
-        name: &TargetName,
-    ) -> (Vec<u8>, String) {
-        let sha256 = &target.hashes.sha256.clone().into_vec();
+    /// Returns the filenames the target may be stored under. If using consistent snapshots, the
+    /// target digest is prepended to the name, and the target may be stored under any of its
+    /// listed digests.
+    pub(crate) fn target_filenames(&self, target: &Target, name: &TargetName) -> Vec<String> {
         if self.consistent_snapshot {
-            (
-                sha256.clone(),
-                format!("{}.{}", hex::encode(sha256), name.resolved()),
-            )
+            target
+                .hashes
+                .iter()
+                .map(|(_, digest)| format!("{}.{}", hex::encode(digest), name.resolved()))
+                .collect()
         } else {
-            (sha256.clone(), name.resolved().to_owned())
+            vec![name.resolved().to_owned()]
         }
     }
 
-    /// Fetches the signed target using `Transport`. Aborts with error if the fetched target is
-    /// larger than its signed size.
+    /// Fetches the signed target using `Transport`, starting with the mirror at index `skip`.
+    /// Aborts with error if the fetched target is larger than its signed size. If `digests` cover
+    /// the leading bytes of the target, only the rest of the target is fetched.
+    ///
+    /// Observers are notified of the bytes received, and of hash or length mismatches.
+    ///
+    /// Also returns the index of the next mirror to try if the stream fails.
     pub(crate) async fn fetch_target(
         &self,
+        name: &TargetName,
         target: &Target,
-        digest: &[u8],
-        filename: &str,
-    ) -> Result<BoxStream<'static, Result<Bytes>>> {
-        let url = self
-            .targets_base_url
-            .join(filename)
-            .with_context(|_| error::JoinUrlSnafu {
-                path: filename,
-                url: self.targets_base_url.clone(),
-            })?;
-        Ok(fetch_sha256(
-            self.transport.as_ref(),
-            url.clone(),
-            target.length,
-            "targets.json",
-            digest,
-        )
-        .await?
-        .context(error::TransportSnafu { url })
-        .boxed())
+        filenames: &[String],
+        digests: &Digests,
+        skip: usize,
+    ) -> Result<(BoxStream<'static, Result<Bytes>>, usize)> {
+        let (stream, url, next) = self
+            .mirrors
+            .fetch_target(
+                self.transport.as_ref(),
+                name,
+                filenames,
+                target.length,
+                digests,
+                skip,
+            )
+            .await?;
+        let observers = self.observers.clone();
+        let name = name.clone();
+        let total = target.length;
+        let mut received = digests.len();
+        let stream = stream
+            .context(error::TransportSnafu { url })
+            .map(move |chunk| match chunk {
+                Ok(bytes) => {
+                    received = received.saturating_add(bytes.len() as u64);
+                    observers.notify(&Event::TargetProgress {
+                        name: &name,
+                        received,
+                        total,
+                    });
+                    Ok(bytes)
+                }
+                Err(e) => Err(observers.report(name.raw(), e)),
+            });
+        Ok((stream.boxed(), next))
     }
 }
@@ -0,0 +1,63 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Provides the [`Clock`] trait, which is the source of the current time for expiration checks,
+//! and the [`TrustedTimePolicy`], which decides how the clock is checked against the latest known
+//! time.
+//!
+//! Both are set on a [`RepositoryLoader`](crate::RepositoryLoader).
+
+use chrono::{DateTime, Duration, Utc};
+use dyn_clone::DynClone;
+use std::fmt::Debug;
+
+/// A source of the current time.
+///
+/// Inclusion of the `DynClone` trait means that you will need to implement `Clone` when
+/// implementing a `Clock`.
+pub trait Clock: Debug + DynClone + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+// Implements `Clone` for `Clock` trait objects (i.e. on `Box::<dyn Clone>`). To facilitate this,
+// `Clone` needs to be implemented for any `Clock`s.
+dyn_clone::clone_trait_object!(Clock);
+
+/// A [`Clock`] that reads the system time. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Decides what happens when the [`Clock`] is earlier than the latest known time.
+///
+/// Each time the clock is checked while metadata is verified, the later of the clock and the
+/// latest known time is stored in the [`Datastore`](crate::datastore::Datastore).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrustedTimePolicy {
+    /// Fail with [`Error::SystemTimeSteppedBackward`](crate::error::Error::SystemTimeSteppedBackward)
+    /// if the clock is earlier than the latest known time. This is the default.
+    #[default]
+    Strict,
+
+    /// Use the latest known time as a lower bound on the clock. This is meant for devices without
+    /// a real-time clock, which may start at the Unix epoch.
+    ///
+    /// TUF metadata does not record when it was signed, so the lower bound is derived from the
+    /// expiration of signed timestamp metadata: once its signature has been verified, timestamp
+    /// metadata that expires at `E` cannot have been signed before `E - timestamp_lifetime`, and
+    /// the latest known time is raised to that point. Replaying older timestamp metadata can only
+    /// lower this bound, so it is never raised past the actual time as long as
+    /// `timestamp_lifetime` is at least the period for which the repository signs timestamp
+    /// metadata. Metadata that expires between the lower bound and the actual time is not
+    /// detected as expired.
+    LowerBound {
+        /// The longest time between signing timestamp metadata and its expiration.
+        timestamp_lifetime: Duration,
+    },
+}
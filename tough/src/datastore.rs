@@ -1,121 +1,271 @@
 // Copyright 2019 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+//! Provides the [`Datastore`] trait, which abstracts over where a client persists the metadata it
+//! has trusted, along with [`FilesystemDatastore`] and [`MemoryDatastore`] implementations.
+//!
+//! A datastore is set with [`RepositoryLoader::datastore`](crate::RepositoryLoader::datastore) or
+//! [`RepositoryLoader::datastore_backend`](crate::RepositoryLoader::datastore_backend).
+
+use crate::clock::{Clock, TrustedTimePolicy};
 use crate::error::{self, Result};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use dyn_clone::DynClone;
+use fs2::FileExt;
 use log::debug;
 use serde::Serialize;
 use snafu::{ensure, ResultExt};
-use std::io::ErrorKind;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tempfile::TempDir;
-use tokio::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::sync::{Mutex, RwLock};
+
+/// The file used by the default [`Datastore::latest_known_time`] implementation.
+const LATEST_KNOWN_TIME_FILE: &str = "latest_known_time.json";
+
+/// The file that [`FilesystemDatastore`] locks when locking is enabled.
+const LOCK_FILE: &str = ".lock";
+
+/// A trait to abstract over the storage in which a client persists TUF metadata files.
+///
+/// The most recently trusted timestamp, snapshot and targets metadata files are kept in the
+/// datastore to detect version rollback attacks, along with the latest known system time, which is
+/// used to detect a system clock that steps backward.
+///
+/// Files are named by plain filenames, such as `timestamp.json`, without any directory
+/// components. Implementations must be safe to call concurrently.
+///
+/// Inclusion of the `DynClone` trait means that you will need to implement `Clone` when
+/// implementing a `Datastore`. Clones are expected to share their contents.
+#[async_trait]
+pub trait Datastore: Debug + DynClone + Send + Sync {
+    /// Returns the contents of `file`, or `None` if it does not exist.
+    async fn bytes(
+        &self,
+        file: &str,
+    ) -> std::result::Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Writes `bytes` to `file`, replacing its contents if it exists.
+    async fn write(
+        &self,
+        file: &str,
+        bytes: &[u8],
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Removes `file`. It is not an error if `file` does not exist.
+    async fn remove(
+        &self,
+        file: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Returns the latest system time that was stored with [`Datastore::set_latest_known_time`],
+    /// if any.
+    ///
+    /// The default implementation reads it from the `latest_known_time.json` file, and ignores
+    /// the file if it cannot be parsed.
+    async fn latest_known_time(
+        &self,
+    ) -> std::result::Result<
+        Option<DateTime<Utc>>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        Ok(self
+            .bytes(LATEST_KNOWN_TIME_FILE)
+            .await?
+            .and_then(|b| serde_json::from_slice(&b).ok()))
+    }
+
+    /// Acquires an exclusive lock on the datastore, which is held until the returned
+    /// [`DatastoreLock`] is dropped. A [`Repository`](crate::Repository) holds this lock while it
+    /// loads or refreshes metadata, so that processes sharing a datastore do not interleave their
+    /// reads and writes.
+    ///
+    /// The default implementation does not lock anything and returns `None`.
+    async fn lock(
+        &self,
+    ) -> std::result::Result<
+        Option<DatastoreLock>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        Ok(None)
+    }
+
+    /// Stores the latest known system time.
+    ///
+    /// The default implementation writes it to the `latest_known_time.json` file as an RFC 3339
+    /// string.
+    async fn set_latest_known_time(
+        &self,
+        time: DateTime<Utc>,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let bytes = serde_json::to_vec(&time)?;
+        self.write(LATEST_KNOWN_TIME_FILE, &bytes).await
+    }
+}
+
+// Implements `Clone` for `Datastore` trait objects (i.e. on `Box::<dyn Clone>`). To facilitate
+// this, `Clone` needs to be implemented for any `Datastore`s.
+dyn_clone::clone_trait_object!(Datastore);
+
+/// An exclusive lock on a [`Datastore`], see [`Datastore::lock`]. The lock is released when this
+/// is dropped.
+pub struct DatastoreLock {
+    _guard: Box<dyn Send + Sync>,
+}
+
+impl DatastoreLock {
+    /// Creates a `DatastoreLock` that releases the lock when `guard` is dropped.
+    pub fn new<G: Send + Sync + 'static>(guard: G) -> Self {
+        Self {
+            _guard: Box::new(guard),
+        }
+    }
+}
 
-/// `Datastore` persists TUF metadata files.
+impl Debug for DatastoreLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DatastoreLock").finish_non_exhaustive()
+    }
+}
+
+/// A [`Datastore`] that keeps files in a directory.
+///
+/// Files are written atomically, by writing to a temporary file in the directory and renaming it,
+/// so a crash never leaves a partially written file behind.
 #[derive(Debug, Clone)]
-pub(crate) struct Datastore {
+pub struct FilesystemDatastore {
     /// A lock around retrieving the datastore path.
     path_lock: Arc<RwLock<DatastorePath>>,
-    /// A lock to treat the `system_time` function as a critical section.
-    time_lock: Arc<Mutex<()>>,
+    /// Whether `lock` takes an advisory lock on a file in the directory.
+    locking: bool,
 }
 
-impl Datastore {
-    pub(crate) fn new(path: Option<PathBuf>) -> Result<Self> {
-        Ok(Self {
-            path_lock: Arc::new(RwLock::new(match path {
-                None => DatastorePath::TempDir(TempDir::new().context(error::DatastoreInitSnafu)?),
-                Some(p) => DatastorePath::Path(p),
-            })),
-            time_lock: Arc::new(Mutex::new(())),
-        })
+impl FilesystemDatastore {
+    /// Creates a `FilesystemDatastore` in `path`, a directory on a persistent filesystem. The
+    /// directory must exist prior to calling [`RepositoryLoader::load`](crate::RepositoryLoader::load).
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self::from_path(DatastorePath::Path(path.into()))
     }
 
-    async fn read(&self) -> RwLockReadGuard<'_, DatastorePath> {
-        self.path_lock.read().await
+    /// Creates a `FilesystemDatastore` in a temporary directory, which is removed when the last
+    /// clone of the datastore is dropped.
+    pub fn temporary() -> Result<Self> {
+        Ok(Self::from_path(DatastorePath::TempDir(
+            TempDir::new().context(error::DatastoreInitSnafu)?,
+        )))
     }
 
-    async fn write(&self) -> RwLockWriteGuard<'_, DatastorePath> {
-        self.path_lock.write().await
+    /// Enables advisory file locking, for a directory that is shared by several processes. While a
+    /// repository loads or refreshes metadata, it holds an exclusive lock on a `.lock` file in the
+    /// directory, and other processes that enable locking wait for it to be released.
+    ///
+    /// Locking is disabled by default.
+    #[must_use]
+    pub fn locking(mut self, locking: bool) -> Self {
+        self.locking = locking;
+        self
     }
 
-    /// Get contents of a file in the datastore. This function is thread safe.
-    ///
-    /// TODO: [provide a thread safe interface](https://github.com/awslabs/tough/issues/602)
-    ///
-    pub(crate) async fn bytes(&self, file: &str) -> Result<Option<Vec<u8>>> {
-        let lock = &self.read().await;
+    fn from_path(path: DatastorePath) -> Self {
+        Self {
+            path_lock: Arc::new(RwLock::new(path)),
+            locking: false,
+        }
+    }
+}
+
+#[async_trait]
+impl Datastore for FilesystemDatastore {
+    async fn bytes(
+        &self,
+        file: &str,
+    ) -> std::result::Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
+        let lock = self.path_lock.read().await;
         let path = lock.path().join(file);
         match tokio::fs::read(&path).await {
             Ok(file) => Ok(Some(file)),
             Err(err) => match err.kind() {
                 ErrorKind::NotFound => Ok(None),
-                _ => Err(err).context(error::DatastoreOpenSnafu { path: &path }),
+                _ => Err(err)
+                    .context(error::DatastoreOpenSnafu { path: &path })
+                    .map_err(Into::into),
             },
         }
     }
 
-    /// Writes a JSON metadata file in the datastore. This function is thread safe.
-    pub(crate) async fn create<T: Serialize>(&self, file: &str, value: &T) -> Result<()> {
-        let lock = &self.write().await;
-        let path = lock.path().join(file);
-        let bytes = serde_json::to_vec(value).with_context(|_| error::DatastoreSerializeSnafu {
-            what: format!("{file} in datastore"),
-            path: path.clone(),
-        })?;
-        tokio::fs::write(&path, bytes)
-            .await
-            .context(error::DatastoreCreateSnafu { path: &path })
+    async fn write(
+        &self,
+        file: &str,
+        bytes: &[u8],
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let lock = self.path_lock.write().await;
+        let dir = lock.path().to_owned();
+        let path = dir.join(file);
+        let persist_path = path.clone();
+        let bytes = bytes.to_vec();
+        tokio::task::spawn_blocking(move || {
+            let mut tmp = NamedTempFile::new_in(&dir)?;
+            tmp.write_all(&bytes)?;
+            tmp.as_file().sync_all()?;
+            tmp.persist(&persist_path).map_err(|e| e.error)?;
+            Ok(())
+        })
+        .await
+        // We do not cancel the task nor do we expect it to panic
+        .unwrap_or_else(|_| unreachable!())
+        .context(error::DatastoreCreateSnafu { path })
+        .map_err(Into::into)
     }
 
-    /// Deletes a file from the datastore. This function is thread safe.
-    pub(crate) async fn remove(&self, file: &str) -> Result<()> {
-        let lock = self.write().await;
+    async fn remove(
+        &self,
+        file: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let lock = self.path_lock.write().await;
         let path = lock.path().join(file);
         debug!("removing '{}'", path.display());
         match tokio::fs::remove_file(&path).await {
             Ok(()) => Ok(()),
             Err(err) => match err.kind() {
                 ErrorKind::NotFound => Ok(()),
-                _ => Err(err).context(error::DatastoreRemoveSnafu { path: &path }),
+                _ => Err(err)
+                    .context(error::DatastoreRemoveSnafu { path: &path })
+                    .map_err(Into::into),
             },
         }
     }
 
-    /// Ensures that system time has not stepped backward since it was last sampled. This function
-    /// is protected by a lock guard to ensure thread safety.
-    pub(crate) async fn system_time(&self) -> Result<DateTime<Utc>> {
-        // Treat this function as a critical section. This lock is not used for anything else.
-        let lock = self.time_lock.lock().await;
-
-        let file = "latest_known_time.json";
-        // Load the latest known system time, if it exists
-        let poss_latest_known_time = self
-            .bytes(file)
-            .await?
-            .map(|b| serde_json::from_slice::<DateTime<Utc>>(&b));
-
-        // Get 'current' system time
-        let sys_time = Utc::now();
-
-        if let Some(Ok(latest_known_time)) = poss_latest_known_time {
-            // Make sure the sampled system time did not go back in time
-            ensure!(
-                sys_time >= latest_known_time,
-                error::SystemTimeSteppedBackwardSnafu {
-                    sys_time,
-                    latest_known_time
-                }
-            );
+    async fn lock(
+        &self,
+    ) -> std::result::Result<
+        Option<DatastoreLock>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        if !self.locking {
+            return Ok(None);
         }
-        // Store the latest known time
-        // Serializes RFC3339 time string and store to datastore
-        self.create(file, &sys_time).await?;
-
-        // Explicitly drop the lock to avoid any compiler optimization.
-        drop(lock);
-        Ok(sys_time)
+        let path = self.path_lock.read().await.path().join(LOCK_FILE);
+        let lock_path = path.clone();
+        let file = tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)?;
+            file.lock_exclusive()?;
+            Ok(file)
+        })
+        .await
+        // We do not cancel the task nor do we expect it to panic
+        .unwrap_or_else(|_| unreachable!())
+        .context(error::DatastoreLockSnafu { path })?;
+        // Closing the file releases the lock.
+        Ok(Some(DatastoreLock::new(file)))
     }
 }
 
@@ -139,3 +289,214 @@ impl DatastorePath {
         }
     }
 }
+
+/// A [`Datastore`] that keeps files in memory. Clones share the same files, so a clone can be kept
+/// to load a repository again with the metadata that was trusted the first time.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDatastore {
+    files: Arc<RwLock<HashMap<String, Vec<u8>>>>,
+}
+
+impl MemoryDatastore {
+    /// Creates an empty `MemoryDatastore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Datastore for MemoryDatastore {
+    async fn bytes(
+        &self,
+        file: &str,
+    ) -> std::result::Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
+        Ok(self.files.read().await.get(file).cloned())
+    }
+
+    async fn write(
+        &self,
+        file: &str,
+        bytes: &[u8],
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.files
+            .write()
+            .await
+            .insert(file.to_owned(), bytes.to_vec());
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        file: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.files.write().await.remove(file);
+        Ok(())
+    }
+}
+
+/// The [`Datastore`] of a repository, which serializes metadata files and checks the time of its
+/// [`Clock`] on its behalf.
+#[derive(Debug, Clone)]
+pub(crate) struct Store {
+    datastore: Box<dyn Datastore>,
+    clock: Box<dyn Clock>,
+    policy: TrustedTimePolicy,
+    /// A lock to treat the `system_time` function as a critical section.
+    time_lock: Arc<Mutex<()>>,
+}
+
+impl Store {
+    pub(crate) fn new(
+        datastore: Box<dyn Datastore>,
+        clock: Box<dyn Clock>,
+        policy: TrustedTimePolicy,
+    ) -> Self {
+        Self {
+            datastore,
+            clock,
+            policy,
+            time_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Get contents of a file in the datastore.
+    pub(crate) async fn bytes(&self, file: &str) -> Result<Option<Vec<u8>>> {
+        self.datastore
+            .bytes(file)
+            .await
+            .context(error::DatastoreAccessSnafu {
+                action: "read",
+                file,
+            })
+    }
+
+    /// Writes a JSON metadata file in the datastore.
+    pub(crate) async fn create<T: Serialize>(&self, file: &str, value: &T) -> Result<()> {
+        let bytes = serde_json::to_vec(value).context(error::DatastoreSerializeSnafu { file })?;
+        self.datastore
+            .write(file, &bytes)
+            .await
+            .context(error::DatastoreAccessSnafu {
+                action: "write",
+                file,
+            })
+    }
+
+    /// Writes a file in the datastore as it is.
+    pub(crate) async fn write(&self, file: &str, bytes: &[u8]) -> Result<()> {
+        self.datastore
+            .write(file, bytes)
+            .await
+            .context(error::DatastoreAccessSnafu {
+                action: "write",
+                file,
+            })
+    }
+
+    /// Acquires the lock on the datastore, see [`Datastore::lock`].
+    pub(crate) async fn lock(&self) -> Result<Option<DatastoreLock>> {
+        self.datastore
+            .lock()
+            .await
+            .context(error::DatastoreAccessSnafu {
+                action: "lock",
+                file: LOCK_FILE,
+            })
+    }
+
+    /// Deletes a file from the datastore.
+    pub(crate) async fn remove(&self, file: &str) -> Result<()> {
+        self.datastore
+            .remove(file)
+            .await
+            .context(error::DatastoreAccessSnafu {
+                action: "remove",
+                file,
+            })
+    }
+
+    /// Raises the latest known time to the earliest time at which timestamp metadata expiring at
+    /// `expires` could have been signed, if the [`TrustedTimePolicy`] derives a lower bound from
+    /// it. The timestamp metadata must have been verified against the trusted root.
+    pub(crate) async fn observe_timestamp(&self, expires: DateTime<Utc>) -> Result<()> {
+        let TrustedTimePolicy::LowerBound { timestamp_lifetime } = self.policy else {
+            return Ok(());
+        };
+        let Some(signed_after) = expires.checked_sub_signed(timestamp_lifetime) else {
+            return Ok(());
+        };
+
+        // Shares the critical section of `system_time`, which reads and writes the same file.
+        let lock = self.time_lock.lock().await;
+        let latest_known_time =
+            self.datastore
+                .latest_known_time()
+                .await
+                .context(error::DatastoreAccessSnafu {
+                    action: "read",
+                    file: LATEST_KNOWN_TIME_FILE,
+                })?;
+        if latest_known_time < Some(signed_after) {
+            self.datastore
+                .set_latest_known_time(signed_after)
+                .await
+                .context(error::DatastoreAccessSnafu {
+                    action: "write",
+                    file: LATEST_KNOWN_TIME_FILE,
+                })?;
+        }
+        drop(lock);
+        Ok(())
+    }
+
+    /// Returns the time of the clock, after ensuring that it has not stepped backward since it was
+    /// last sampled, or raising it to the latest known time, according to the
+    /// [`TrustedTimePolicy`]. The latest known time includes the lower bound recorded by
+    /// [`Store::observe_timestamp`]. This function is protected by a lock guard to ensure thread safety.
+    pub(crate) async fn system_time(&self) -> Result<DateTime<Utc>> {
+        // Treat this function as a critical section. This lock is not used for anything else.
+        let lock = self.time_lock.lock().await;
+
+        // Load the latest known system time, if it exists
+        let latest_known_time =
+            self.datastore
+                .latest_known_time()
+                .await
+                .context(error::DatastoreAccessSnafu {
+                    action: "read",
+                    file: LATEST_KNOWN_TIME_FILE,
+                })?;
+
+        // Get 'current' system time
+        let mut sys_time = self.clock.now();
+
+        if let Some(latest_known_time) = latest_known_time {
+            match self.policy {
+                // Make sure the sampled system time did not go back in time
+                TrustedTimePolicy::Strict => ensure!(
+                    sys_time >= latest_known_time,
+                    error::SystemTimeSteppedBackwardSnafu {
+                        sys_time,
+                        latest_known_time
+                    }
+                ),
+                TrustedTimePolicy::LowerBound { .. } => {
+                    sys_time = sys_time.max(latest_known_time);
+                }
+            }
+        }
+        // Store the latest known time
+        self.datastore
+            .set_latest_known_time(sys_time)
+            .await
+            .context(error::DatastoreAccessSnafu {
+                action: "write",
+                file: LATEST_KNOWN_TIME_FILE,
+            })?;
+
+        // Explicitly drop the lock to avoid any compiler optimization.
+        drop(lock);
+        Ok(sys_time)
+    }
+}
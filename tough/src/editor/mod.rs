@@ -17,8 +17,8 @@ use crate::key_source::KeySource;
 use crate::schema::decoded::{Decoded, Hex};
 use crate::schema::key::Key;
 use crate::schema::{
-    Hashes, KeyHolder, Metafile, PathSet, Role, RoleType, Root, Signed, Snapshot, Target, Targets,
-    Timestamp,
+    HashAlgorithm, Hashes, KeyHolder, Metafile, PathSet, Role, RoleType, Root, Signed, Snapshot,
+    Target, Targets, Timestamp,
 };
 use crate::transport::{IntoVec, Transport};
 use crate::{encode_filename, Limits};
@@ -83,6 +83,9 @@ pub struct RepositoryEditor {
 
     transport: Option<Box<dyn Transport>>,
     limits: Option<Limits>,
+
+    /// The hash algorithms listed for targets added by path
+    target_hash_algorithms: Option<Vec<HashAlgorithm>>,
 }
 
 impl RepositoryEditor {
@@ -137,6 +140,7 @@ impl RepositoryEditor {
             signed_targets: None,
             transport: None,
             limits: None,
+            target_hash_algorithms: None,
         })
     }
 
@@ -149,7 +153,7 @@ impl RepositoryEditor {
         P: AsRef<Path>,
     {
         let mut editor = RepositoryEditor::new(root_path).await?;
-        editor.targets(repo.targets)?;
+        editor.targets(repo.targets_with_delegations().await?)?;
         editor.snapshot(repo.snapshot.signed)?;
         editor.timestamp(repo.timestamp.signed)?;
         editor.transport = Some(repo.transport.clone());
@@ -282,6 +286,13 @@ impl RepositoryEditor {
         Ok(self)
     }
 
+    /// Set the hash algorithms listed for targets added with `add_target_path()` or
+    /// `add_target_paths()`. Defaults to SHA 256 only.
+    pub fn target_hash_algorithms(&mut self, algorithms: &[HashAlgorithm]) -> &mut Self {
+        self.target_hash_algorithms = Some(algorithms.to_vec());
+        self
+    }
+
     /// Add a target to the repository using its path
     ///
     /// Note: This function builds a `Target` synchronously;
@@ -292,7 +303,8 @@ impl RepositoryEditor {
     where
         P: AsRef<Path>,
     {
-        let (target_name, target) = RepositoryEditor::build_target(target_path).await?;
+        let (target_name, target) =
+            RepositoryEditor::build_target_with_hashes(target_path, self.hash_algorithms()).await?;
         self.add_target(target_name, target)?;
         Ok(self)
     }
@@ -305,7 +317,8 @@ impl RepositoryEditor {
         P: AsRef<Path>,
     {
         for target in targets {
-            let (target_name, target) = RepositoryEditor::build_target(target).await?;
+            let (target_name, target) =
+                RepositoryEditor::build_target_with_hashes(target, self.hash_algorithms()).await?;
             self.add_target(target_name, target)?;
         }
 
@@ -314,6 +327,18 @@ impl RepositoryEditor {
 
     /// Builds a target struct for the given path
     pub async fn build_target<P>(target_path: P) -> Result<(TargetName, Target)>
+    where
+        P: AsRef<Path>,
+    {
+        RepositoryEditor::build_target_with_hashes(target_path, &[HashAlgorithm::Sha256]).await
+    }
+
+    /// Builds a target struct for the given path, listing the digest of the file for each of
+    /// `algorithms`
+    pub async fn build_target_with_hashes<P>(
+        target_path: P,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<(TargetName, Target)>
     where
         P: AsRef<Path>,
     {
@@ -329,13 +354,20 @@ impl RepositoryEditor {
         )?;
 
         // Build a Target from the path given. If it is not a file, this will fail
-        let target = Target::from_path(target_path)
+        let target = Target::from_path_with_hashes(target_path, algorithms)
             .await
             .context(error::TargetFromPathSnafu { path: target_path })?;
 
         Ok((target_name, target))
     }
 
+    /// Returns the hash algorithms listed for targets added by path.
+    fn hash_algorithms(&self) -> &[HashAlgorithm] {
+        self.target_hash_algorithms
+            .as_deref()
+            .unwrap_or(&[HashAlgorithm::Sha256])
+    }
+
     /// Remove all targets from this repo
     pub fn clear_targets(&mut self) -> Result<&mut Self> {
         self.targets_editor_mut()?.clear_targets();
@@ -394,6 +426,14 @@ impl RepositoryEditor {
         Ok(self)
     }
 
+    /// Sets whether the delegation to `role` from the targets in `targets_editor` is terminating
+    /// If `terminating` is `true`, target lookups that match the delegation's paths will not
+    /// consider any delegations after it.
+    pub fn terminating(&mut self, role: &str, terminating: bool) -> Result<&mut Self> {
+        self.targets_editor_mut()?.terminating(role, terminating)?;
+        Ok(self)
+    }
+
     /// Set the `Snapshot` version
     pub fn snapshot_version(&mut self, snapshot_version: NonZeroU64) -> &mut Self {
         self.snapshot_version = Some(snapshot_version);
@@ -708,7 +748,8 @@ impl RepositoryEditor {
     {
         Metafile {
             hashes: Some(Hashes {
-                sha256: role.sha256.to_vec().into(),
+                sha256: Some(role.sha256.to_vec().into()),
+                sha512: None,
                 _extra: HashMap::new(),
             }),
             length: Some(role.length),
@@ -746,7 +787,8 @@ impl RepositoryEditor {
     {
         Metafile {
             hashes: Some(Hashes {
-                sha256: role.sha256.to_vec().into(),
+                sha256: Some(role.sha256.to_vec().into()),
+                sha512: None,
                 _extra: HashMap::new(),
             }),
             length: Some(role.length),
@@ -263,10 +263,10 @@ impl SignedRepository {
 
     /// Crawls a given directory and symlinks any targets found to the given
     /// "out" directory. If consistent snapshots are used, the target files
-    /// are prefixed with their `sha256`.
+    /// are prefixed with their first listed digest (`sha256`, if listed).
     ///
     /// For each file found in the `indir`, the method gets the filename and
-    /// if the filename exists in `Targets`, the file's sha256 is compared
+    /// if the filename exists in `Targets`, the file's digests are compared
     /// against the data in `Targets`. If this data does not match, the
     /// method will fail.
     pub async fn link_targets<P1, P2>(
@@ -290,10 +290,10 @@ impl SignedRepository {
 
     /// Crawls a given directory and copies any targets found to the given
     /// "out" directory. If consistent snapshots are used, the target files
-    /// are prefixed with their `sha256`.
+    /// are prefixed with their first listed digest (`sha256`, if listed).
     ///
     /// For each file found in the `indir`, the method gets the filename and
-    /// if the filename exists in `Targets`, the file's sha256 is compared
+    /// if the filename exists in `Targets`, the file's digests are compared
     /// against the data in `Targets`. If this data does not match, the
     /// method will fail.
     pub async fn copy_targets<P1, P2>(
@@ -457,10 +457,10 @@ impl SignedDelegatedTargets {
 
     /// Crawls a given directory and symlinks any targets found to the given
     /// "out" directory. If consistent snapshots are used, the target files
-    /// are prefixed with their `sha256`.
+    /// are prefixed with their first listed digest (`sha256`, if listed).
     ///
     /// For each file found in the `indir`, the method gets the filename and
-    /// if the filename exists in `Targets`, the file's sha256 is compared
+    /// if the filename exists in `Targets`, the file's digests are compared
     /// against the data in `Targets`. If this data does not match, the
     /// method will fail.
     pub async fn link_targets<P1, P2>(
@@ -484,10 +484,10 @@ impl SignedDelegatedTargets {
 
     /// Crawls a given directory and copies any targets found to the given
     /// "out" directory. If consistent snapshots are used, the target files
-    /// are prefixed with their `sha256`.
+    /// are prefixed with their first listed digest (`sha256`, if listed).
     ///
     /// For each file found in the `indir`, the method gets the filename and
-    /// if the filename exists in `Targets`, the file's sha256 is compared
+    /// if the filename exists in `Targets`, the file's digests are compared
     /// against the data in `Targets`. If this data does not match, the
     /// method will fail.
     pub async fn copy_targets<P1, P2>(
@@ -747,33 +747,51 @@ trait TargetsWalker {
             )?)
         };
 
-        // create a Target object using the input path.
-        let target_from_path = Target::from_path(input)
-            .await
-            .context(error::TargetFromPathSnafu { path: input })?;
-
         // Use the file name to see if a target exists in the repo
         // with that name. If so...
         let repo_targets = &self.targets();
         let repo_target = repo_targets
             .get(&target_name)
             .context(error::PathIsNotTargetSnafu { path: input })?;
+
+        // create a Target object using the input path, with the hashes the repo lists.
+        let algorithms = repo_target
+            .hashes
+            .iter()
+            .map(|(algorithm, _)| algorithm)
+            .collect::<Vec<_>>();
+        let (_, digest) =
+            repo_target
+                .hashes
+                .iter()
+                .next()
+                .context(error::NoSupportedHashSnafu {
+                    context: target_name.raw(),
+                })?;
+        let target_from_path = Target::from_path_with_hashes(input, &algorithms)
+            .await
+            .context(error::TargetFromPathSnafu { path: input })?;
+
         // compare the hashes of the target from the repo and the target we just created.  They
         // should match, or we alert the caller; if target replacement is intended, it should
         // happen earlier, in RepositoryEditor.
-        ensure!(
-            target_from_path.hashes.sha256 == repo_target.hashes.sha256,
-            error::HashMismatchSnafu {
-                context: "target",
-                calculated: hex::encode(target_from_path.hashes.sha256),
-                expected: hex::encode(&repo_target.hashes.sha256),
-            }
-        );
+        for (algorithm, expected) in repo_target.hashes.iter() {
+            let calculated = target_from_path.hashes.get(algorithm).unwrap_or_default();
+            ensure!(
+                calculated == expected,
+                error::HashMismatchSnafu {
+                    context: "target",
+                    calculated: hex::encode(calculated),
+                    expected: hex::encode(expected),
+                }
+            );
+        }
 
+        // If using consistent snapshots, the target is written with its first listed digest.
         let dest = if self.consistent_snapshot() {
             outdir.join(format!(
                 "{}.{}",
-                hex::encode(&target_from_path.hashes.sha256),
+                hex::encode(digest),
                 target_name.resolved()
             ))
         } else {
@@ -798,7 +816,7 @@ trait TargetsWalker {
                 .fetch(url.clone())
                 .await
                 .with_context(|_| error::TransportSnafu { url: url.clone() })?;
-            let stream = DigestAdapter::sha256(stream, &repo_target.hashes.sha256, url.clone());
+            let stream = DigestAdapter::hashes(stream, &repo_target.hashes, url.clone())?;
 
             // The act of reading with the DigestAdapter verifies the checksum, assuming the read
             // succeeds.
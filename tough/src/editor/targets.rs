@@ -11,8 +11,8 @@ use crate::key_source::KeySource;
 use crate::schema::decoded::{Decoded, Hex};
 use crate::schema::key::Key;
 use crate::schema::{
-    DelegatedRole, DelegatedTargets, Delegations, KeyHolder, PathSet, RoleType, Signed, Target,
-    Targets,
+    DelegatedRole, DelegatedTargets, Delegations, HashAlgorithm, KeyHolder, PathSet, RoleType,
+    Signed, Target, Targets,
 };
 use crate::transport::{IntoVec, Transport};
 use crate::{encode_filename, Limits};
@@ -82,6 +82,9 @@ pub struct TargetsEditor {
     limits: Option<Limits>,
 
     transport: Option<Box<dyn Transport>>,
+
+    /// The hash algorithms listed for targets added by path
+    hash_algorithms: Option<Vec<HashAlgorithm>>,
 }
 
 impl TargetsEditor {
@@ -99,6 +102,7 @@ impl TargetsEditor {
             _extra: None,
             limits: None,
             transport: None,
+            hash_algorithms: None,
         }
     }
 
@@ -117,21 +121,26 @@ impl TargetsEditor {
             _extra: Some(targets._extra),
             limits: None,
             transport: None,
+            hash_algorithms: None,
         }
     }
 
     /// Creates a `TargetsEditor` with the provided targets from an already loaded repo
     /// `version` and `expires` are thrown out to encourage updating the version and expiration
     /// If a `Repository` has been loaded, use `from_repo()` to preserve the `Transport` and `Limits`.
-    pub fn from_repo(repo: Repository, name: &str) -> Result<Self> {
+    /// Any delegated targets metadata that the `Repository` has not loaded yet is fetched.
+    pub async fn from_repo(repo: Repository, name: &str) -> Result<Self> {
+        let repo_targets = repo.targets_with_delegations().await?;
         let (targets, key_holder) = if name == "targets" {
             (
-                repo.targets.signed.clone(),
+                repo_targets.signed.clone(),
                 KeyHolder::Root(repo.root.signed.clone()),
             )
         } else {
-            let targets = repo
+            let targets = repo_targets
+                .signed
                 .delegated_role(name)
+                .ok()
                 .context(error::DelegateNotFoundSnafu {
                     name: name.to_string(),
                 })?
@@ -141,7 +150,7 @@ impl TargetsEditor {
                 .signed
                 .clone();
             let key_holder = KeyHolder::Delegations(
-                repo.targets
+                repo_targets
                     .signed
                     .parent_of(name)
                     .context(error::DelegateMissingSnafu {
@@ -163,6 +172,7 @@ impl TargetsEditor {
             _extra: Some(targets._extra),
             limits: Some(repo.limits),
             transport: Some(repo.transport),
+            hash_algorithms: None,
         })
     }
 
@@ -176,6 +186,13 @@ impl TargetsEditor {
         self.transport = Some(transport);
     }
 
+    /// Set the hash algorithms listed for targets added with `add_target_path()` or
+    /// `add_target_paths()`. Defaults to SHA 256 only.
+    pub fn hash_algorithms(&mut self, algorithms: &[HashAlgorithm]) -> &mut Self {
+        self.hash_algorithms = Some(algorithms.to_vec());
+        self
+    }
+
     /// Add a `Target` to the `Targets` role
     pub fn add_target<T, E>(&mut self, name: T, target: Target) -> Result<&mut Self>
     where
@@ -216,7 +233,11 @@ impl TargetsEditor {
         )?;
 
         // Build a Target from the path given. If it is not a file, this will fail
-        let target = Target::from_path(target_path)
+        let algorithms = self
+            .hash_algorithms
+            .as_deref()
+            .unwrap_or(&[HashAlgorithm::Sha256]);
+        let target = Target::from_path_with_hashes(target_path, algorithms)
             .await
             .context(error::TargetFromPathSnafu { path: target_path })?;
 
@@ -332,6 +353,7 @@ impl TargetsEditor {
     /// Adds a `DelegatedRole` to `new_roles`
     /// To use `delegate_role()` a new `Targets` should be created using `TargetsEditor::new()`
     /// followed by `create_signed()` to provide a `Signed<DelegatedTargets>` for the new role.
+    /// The delegation is not terminating; use `terminating()` to change that.
     pub fn delegate_role(
         &mut self,
         targets: Signed<DelegatedTargets>,
@@ -357,6 +379,27 @@ impl TargetsEditor {
         Ok(self)
     }
 
+    /// Sets whether the delegation to `role`, made directly by this role, is terminating
+    /// If `terminating` is `true`, target lookups that match the delegation's paths will not
+    /// consider any delegations after it.
+    pub fn terminating(&mut self, role: &str, terminating: bool) -> Result<&mut Self> {
+        let delegated_role = self
+            .new_roles
+            .iter_mut()
+            .flatten()
+            .chain(
+                self.delegations
+                    .iter_mut()
+                    .flat_map(|delegations| delegations.roles.iter_mut()),
+            )
+            .find(|delegated_role| delegated_role.name == role)
+            .context(error::DelegateNotFoundSnafu {
+                name: role.to_string(),
+            })?;
+        delegated_role.terminating = terminating;
+        Ok(self)
+    }
+
     /// Removes a role from delegations
     /// If `recursive` is `false`, `role` is only removed if it is directly delegated by this role
     /// If `true` removes whichever role eventually delegates 'role'
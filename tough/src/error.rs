@@ -54,6 +54,14 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    /// The library failed to lock the datastore.
+    #[snafu(display("Failed to lock datastore file {}: {}", path.display(), source))]
+    DatastoreLock {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
     /// The library failed to remove a file in the datastore.
     #[snafu(display("Failed to remove file at datastore path {}: {}", path.display(), source))]
     DatastoreRemove {
@@ -63,14 +71,22 @@ pub enum Error {
     },
 
     /// The library failed to serialize an object to JSON to the datastore.
-    #[snafu(display("Failed to serialize {} to JSON at datastore path {}: {}", what, path.display(), source))]
+    #[snafu(display("Failed to serialize {} to JSON for the datastore: {}", file, source))]
     DatastoreSerialize {
-        what: String,
-        path: PathBuf,
+        file: String,
         source: serde_json::Error,
         backtrace: Backtrace,
     },
 
+    /// A [`Datastore`](crate::datastore::Datastore) failed to read, write or remove a file.
+    #[snafu(display("Datastore failed to {} '{}': {}", action, file, source))]
+    DatastoreAccess {
+        action: &'static str,
+        file: String,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Failed to create directory '{}': {}", path.display(), source))]
     DirCreate {
         path: PathBuf,
@@ -138,6 +154,13 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    /// The hashes listed for a file do not include any algorithm that tough supports.
+    #[snafu(display("No supported hash algorithm is listed for {}", context))]
+    NoSupportedHash {
+        context: String,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Source path for target must be file or symlink - '{}'", path.display()))]
     InvalidFileType { path: PathBuf, backtrace: Backtrace },
 
@@ -350,6 +373,35 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    /// The custom metadata of a target does not deserialize into the type requested by a
+    /// [`TargetQuery`](crate::TargetQuery).
+    #[snafu(display(
+        "Failed to deserialize custom metadata of target '{}' listed by role '{}': {}",
+        name.raw(),
+        role,
+        source
+    ))]
+    TargetCustomMetadata {
+        name: TargetName,
+        role: String,
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unable to move partial download to '{}': {}", path.display(), source))]
+    SaveTargetPartialPersist {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unable to serialize partial download record '{}': {}", path.display(), source))]
+    SaveTargetPartialSidecar {
+        path: PathBuf,
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display(
         "The target '{}' had an unsafe name. Not writing to '{}' because it is not in the outdir '{}'",
         name.raw(),
@@ -540,6 +592,36 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    /// An archive could not be read.
+    #[snafu(display("Failed to read archive '{}': {}", path.display(), source))]
+    ArchiveRead {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    /// The runtime of a blocking client could not be created.
+    #[snafu(display("Failed to create runtime for blocking client: {}", source))]
+    BlockingRuntime {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    /// A temporary directory to stage the contents of a bundle could not be created.
+    #[snafu(display("Failed to create temp directory for bundle: {}", source))]
+    BundleStaging {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    /// A bundle could not be written.
+    #[snafu(display("Failed to write bundle '{}': {}", path.display(), source))]
+    BundleWrite {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Failed to walk directory tree '{}': {}", directory.display(), source))]
     WalkDir {
         directory: PathBuf,
@@ -645,4 +727,55 @@ pub enum Error {
 
     #[snafu(display("The targets editor was not cleared"))]
     TargetsEditorSome,
+
+    /// None of the repository's mirrors serve a file.
+    #[snafu(display("No mirror serves '{}'", path))]
+    NoMirror { path: String, backtrace: Backtrace },
+
+    /// A TAP 4 map file could not be parsed.
+    #[snafu(display("Failed to parse map file: {}", source))]
+    ParseMapFile {
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+
+    /// A mapping in a TAP 4 map file refers to a repository that the map file does not list.
+    #[snafu(display("Repository '{}' is not listed in the map file", name))]
+    MapFileRepositoryMissing { name: String, backtrace: Backtrace },
+
+    /// A repository in a TAP 4 map file does not list any URLs.
+    #[snafu(display("Repository '{}' has no URLs in the map file", name))]
+    MapFileNoUrls { name: String, backtrace: Backtrace },
+
+    /// A mapping in a TAP 4 map file lists the same repository more than once.
+    #[snafu(display("Repository '{}' is listed more than once in a mapping", name))]
+    MapFileDuplicateRepository { name: String, backtrace: Backtrace },
+
+    /// A mapping in a TAP 4 map file requires more repositories to agree than it lists.
+    #[snafu(display(
+        "Mapping threshold {} is larger than its {} repositories",
+        threshold,
+        repositories
+    ))]
+    MapFileThresholdUnreachable {
+        threshold: u64,
+        repositories: usize,
+        backtrace: Backtrace,
+    },
+
+    /// The repositories mapped to a target did not agree on its length and hashes.
+    #[snafu(display(
+        "Only {} of the required {} repositories agree on target '{}'; disagreeing repositories: {}",
+        agreeing,
+        threshold,
+        name,
+        disagreeing.join(", ")
+    ))]
+    TargetConsensus {
+        name: String,
+        agreeing: usize,
+        threshold: u64,
+        disagreeing: Vec<String>,
+        backtrace: Backtrace,
+    },
 }
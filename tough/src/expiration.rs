@@ -0,0 +1,119 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Reports of when the metadata of each role of a repository expires.
+
+use crate::error::Result;
+use crate::schema::{Delegations, RoleType};
+use crate::Repository;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::num::NonZeroU64;
+
+/// When the metadata of a role expires, as reported by [`Repository::expirations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleExpiration {
+    /// The name of the role: `root`, `timestamp`, `snapshot`, `targets`, or the name of a
+    /// delegated targets role.
+    pub role: String,
+    /// The type of the role.
+    pub role_type: RoleType,
+    /// The version of the role's metadata.
+    pub version: NonZeroU64,
+    /// When the role's metadata expires.
+    pub expires: DateTime<Utc>,
+    /// The role that lists the keys trusted to sign the role's metadata: `root` for the top-level
+    /// roles, or the delegating role for a delegated targets role.
+    pub signing_role: String,
+}
+
+impl RoleExpiration {
+    fn top_level(role_type: RoleType, version: NonZeroU64, expires: DateTime<Utc>) -> Self {
+        Self {
+            role: role_type.to_string(),
+            role_type,
+            version,
+            expires,
+            signing_role: RoleType::Root.to_string(),
+        }
+    }
+}
+
+impl Repository {
+    /// Returns when the metadata of each role expires: root, timestamp, snapshot and targets,
+    /// followed by every delegated targets role that has been loaded, in preorder.
+    ///
+    /// Delegated targets roles are only loaded when needed; use
+    /// [`Repository::load_delegated_targets`] first to report on all of them.
+    pub fn expirations(&self) -> Vec<RoleExpiration> {
+        let mut expirations = vec![
+            RoleExpiration::top_level(
+                RoleType::Root,
+                self.root.signed.version,
+                self.root.signed.expires,
+            ),
+            RoleExpiration::top_level(
+                RoleType::Timestamp,
+                self.timestamp.signed.version,
+                self.timestamp.signed.expires,
+            ),
+            RoleExpiration::top_level(
+                RoleType::Snapshot,
+                self.snapshot.signed.version,
+                self.snapshot.signed.expires,
+            ),
+            RoleExpiration::top_level(
+                RoleType::Targets,
+                self.targets.signed.version,
+                self.targets.signed.expires,
+            ),
+        ];
+        if let Some(delegations) = &self.targets.signed.delegations {
+            delegated_expirations(
+                &RoleType::Targets.to_string(),
+                delegations,
+                &mut expirations,
+            );
+        }
+        expirations
+    }
+
+    /// Returns the roles from [`Repository::expirations`] whose metadata has expired or expires
+    /// before `time`.
+    pub fn expiring_before(&self, time: DateTime<Utc>) -> Vec<RoleExpiration> {
+        self.expirations()
+            .into_iter()
+            .filter(|expiration| expiration.expires < time)
+            .collect()
+    }
+
+    /// Returns the roles from [`Repository::expirations`] whose metadata has expired or expires
+    /// within `window` of the current time, as given by the repository's
+    /// [`Clock`](crate::clock::Clock).
+    pub async fn expiring_within(&self, window: TimeDelta) -> Result<Vec<RoleExpiration>> {
+        let now = self.datastore.system_time().await?;
+        Ok(self.expiring_before(now + window))
+    }
+}
+
+/// Appends the expirations of the loaded roles in `delegations`, which are delegated by
+/// `signing_role`, and of the roles they delegate to.
+fn delegated_expirations(
+    signing_role: &str,
+    delegations: &Delegations,
+    expirations: &mut Vec<RoleExpiration>,
+) {
+    for role in &delegations.roles {
+        if let Some(targets) = &role.targets {
+            expirations.push(RoleExpiration {
+                role: role.name.clone(),
+                role_type: RoleType::DelegatedTargets,
+                version: targets.signed.version,
+                expires: targets.signed.expires,
+                signing_role: signing_role.to_owned(),
+            });
+            if let Some(delegations) = &targets.signed.delegations {
+                delegated_expirations(&role.name, delegations, expirations);
+            }
+        }
+    }
+}
@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::error::{self, Result};
-use crate::io::{max_size_adapter, DigestAdapter};
+use crate::io::{max_size_adapter, DigestAdapter, Digests};
+use crate::schema::Hashes;
 use crate::transport::{Transport, TransportStream};
 use snafu::ResultExt;
 use url::Url;
@@ -22,13 +23,34 @@ pub(crate) async fn fetch_max_size(
     Ok(stream)
 }
 
-pub(crate) async fn fetch_sha256(
+pub(crate) async fn fetch_hashes(
     transport: &dyn Transport,
     url: Url,
     size: u64,
     specifier: &'static str,
-    sha256: &[u8],
+    hashes: &Hashes,
 ) -> Result<TransportStream> {
     let stream = fetch_max_size(transport, url.clone(), size, specifier).await?;
-    Ok(DigestAdapter::sha256(stream, sha256, url))
+    DigestAdapter::hashes(stream, hashes, url)
+}
+
+/// Fetches the rest of the file at `url`, following the bytes that `digests` were computed over,
+/// and checks that the whole file matches its expected digests and is at most `size` bytes.
+pub(crate) async fn fetch_resume(
+    transport: &dyn Transport,
+    url: Url,
+    size: u64,
+    specifier: &'static str,
+    digests: Digests,
+) -> Result<TransportStream> {
+    let start = digests.len();
+    let stream = if start == 0 {
+        transport.fetch(url.clone()).await
+    } else {
+        transport.fetch_range(url.clone(), start).await
+    }
+    .with_context(|_| error::TransportSnafu { url: url.clone() })?;
+
+    let stream = max_size_adapter(stream, url.clone(), size.saturating_sub(start), specifier);
+    Ok(DigestAdapter::resume(stream, digests, url))
 }
@@ -1,22 +1,35 @@
 //! The `http` module provides `HttpTransport` which enables `Repository` objects to be
 //! loaded over HTTP
-use crate::transport::TransportStream;
-use crate::{Transport, TransportError, TransportErrorKind};
+use crate::observer::{Event, Observer};
+use crate::transport::{skip_bytes, TransportStream};
+use crate::{ConditionalFetch, Transport, TransportError, TransportErrorKind, Validators};
 use async_trait::async_trait;
 use futures::{FutureExt, StreamExt};
 use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_core::Stream;
 use log::trace;
-use reqwest::header::{self, HeaderValue, ACCEPT_RANGES};
-use reqwest::{Client, ClientBuilder, Request, Response};
+use reqwest::header::{
+    self, HeaderMap, HeaderName, HeaderValue, ACCEPT_RANGES, ETAG, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::redirect::Policy;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use reqwest::{Certificate, Identity};
+use reqwest::{
+    Client, ClientBuilder, NoProxy, Proxy, Request, RequestBuilder, Response, StatusCode,
+};
 use reqwest::{Error, Method};
 use snafu::ResultExt;
 use snafu::Snafu;
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::task::Poll;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
 use url::Url;
 
 /// A builder for [`HttpTransport`] which allows settings customization.
@@ -28,12 +41,37 @@ use url::Url;
 /// let http_transport = HttpTransportBuilder::new()
 /// .tries(3)
 /// .backoff_factor(1.5)
+/// .pool_max_idle_per_host(8)
+/// .bearer_auth("tuf.example.com", "my-token")
 /// .build();
 /// ```
 ///
 /// See [`HttpTransport`] for proxy support and other behavior details.
 ///
-#[derive(Clone, Copy, Debug)]
+/// # Retries
+///
+/// Timeouts, connection errors, and responses with a 5XX or 429 status are retried up to `tries`
+/// times, with exponential backoff. If the response has a `Retry-After` header, the transport
+/// waits at least that long before retrying, unless the wait is longer than
+/// [`max_retry_after`](Self::max_retry_after) or the time left before the retry deadline, in which
+/// case the fetch fails instead. A [`retry_deadline`](Self::retry_deadline) stops
+/// retrying once a fetch has taken too long, and a [`circuit_breaker`](Self::circuit_breaker)
+/// stops sending requests to a host that keeps failing.
+///
+/// # Authentication
+///
+/// Headers and credentials are scoped to a host, and are only sent with requests to that host.
+/// The host is a name such as `tuf.example.com`, or a name and port such as
+/// `tuf.example.com:8443` to limit them to one port. Redirects that leave the host of a custom
+/// header are refused, and `Authorization` headers are dropped when a redirect leaves their host.
+///
+/// # TLS
+///
+/// `tough` does not choose a TLS implementation for `reqwest` unless the `rustls-tls` or
+/// `native-tls` feature is enabled. Either feature also enables the settings for extra root
+/// certificates, client certificates, and the [`TlsBackend`].
+///
+#[derive(Clone, Debug)]
 pub struct HttpTransportBuilder {
     timeout: Duration,
     connect_timeout: Duration,
@@ -41,6 +79,17 @@ pub struct HttpTransportBuilder {
     initial_backoff: Duration,
     max_backoff: Duration,
     backoff_factor: f32,
+    max_retry_after: Duration,
+    retry_deadline: Option<Duration>,
+    circuit_breaker: Option<(u32, Duration)>,
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Option<Duration>,
+    http2_prior_knowledge: bool,
+    authentication: Vec<HostAuthentication>,
+    proxy: Option<Url>,
+    no_proxy: Vec<String>,
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    tls: TlsSettings,
 }
 
 impl Default for HttpTransportBuilder {
@@ -53,6 +102,17 @@ impl Default for HttpTransportBuilder {
             initial_backoff: std::time::Duration::from_millis(100),
             max_backoff: std::time::Duration::from_secs(1),
             backoff_factor: 1.5,
+            max_retry_after: std::time::Duration::from_mins(1),
+            retry_deadline: None,
+            circuit_breaker: None,
+            pool_max_idle_per_host: usize::MAX,
+            pool_idle_timeout: Some(std::time::Duration::from_secs(90)),
+            http2_prior_knowledge: false,
+            authentication: Vec::new(),
+            proxy: None,
+            no_proxy: Vec::new(),
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            tls: TlsSettings::default(),
         }
     }
 }
@@ -107,28 +167,474 @@ impl HttpTransportBuilder {
         self
     }
 
+    /// Set the longest wait that a `Retry-After` header may ask for. If a server asks us to wait
+    /// longer, the fetch fails instead of retrying. Defaults to one minute.
+    #[must_use]
+    pub fn max_retry_after(mut self, value: Duration) -> Self {
+        self.max_retry_after = value;
+        self
+    }
+
+    /// Stop retrying once `value` has passed since the fetch started, or would pass before the
+    /// next try. This includes waits requested by `Retry-After` headers.
+    #[must_use]
+    pub fn retry_deadline(mut self, value: Duration) -> Self {
+        self.retry_deadline = Some(value);
+        self
+    }
+
+    /// After `failure_threshold` consecutive failed tries to a host, fail fetches from that host
+    /// without sending requests until `open_duration` has passed. The failures are counted across
+    /// all fetches through the transport and its clones. Once `open_duration` has passed, requests
+    /// are sent again; a success resets the count, and another failure stops requests again.
+    #[must_use]
+    pub fn circuit_breaker(mut self, failure_threshold: u32, open_duration: Duration) -> Self {
+        self.circuit_breaker = Some((failure_threshold, open_duration));
+        self
+    }
+
+    /// Set the maximum number of idle connections kept open to each host for reuse.
+    #[must_use]
+    pub fn pool_max_idle_per_host(mut self, value: usize) -> Self {
+        self.pool_max_idle_per_host = value;
+        self
+    }
+
+    /// Set how long an idle connection is kept open for reuse, or `None` to keep idle connections
+    /// open indefinitely.
+    #[must_use]
+    pub fn pool_idle_timeout(mut self, value: Option<Duration>) -> Self {
+        self.pool_idle_timeout = value;
+        self
+    }
+
+    /// Only use HTTP/2, without negotiating it with the server first. Use this for servers that
+    /// are known to speak HTTP/2, including over plain `http` URLs.
+    #[must_use]
+    pub fn http2_prior_knowledge(mut self, value: bool) -> Self {
+        self.http2_prior_knowledge = value;
+        self
+    }
+
+    /// Send a header with every request to `host`.
+    #[must_use]
+    pub fn header(mut self, host: impl Into<String>, name: HeaderName, value: HeaderValue) -> Self {
+        self.authentication.push(HostAuthentication {
+            host: host.into(),
+            authentication: Authentication::Header(name, value),
+        });
+        self
+    }
+
+    /// Authenticate requests to `host` with a bearer token.
+    #[must_use]
+    pub fn bearer_auth(mut self, host: impl Into<String>, token: impl Into<String>) -> Self {
+        self.authentication.push(HostAuthentication {
+            host: host.into(),
+            authentication: Authentication::Credentials(Credentials::Bearer(token.into())),
+        });
+        self
+    }
+
+    /// Authenticate requests to `host` with HTTP basic authentication.
+    #[must_use]
+    pub fn basic_auth(
+        mut self,
+        host: impl Into<String>,
+        username: impl Into<String>,
+        password: Option<String>,
+    ) -> Self {
+        self.authentication.push(HostAuthentication {
+            host: host.into(),
+            authentication: Authentication::Credentials(Credentials::Basic {
+                username: username.into(),
+                password,
+            }),
+        });
+        self
+    }
+
+    /// Authenticate requests to `host` with credentials from `provider`, which is asked for them
+    /// each time a request is sent, including retries. Use this for tokens that rotate.
+    #[must_use]
+    pub fn credential_provider(
+        mut self,
+        host: impl Into<String>,
+        provider: Arc<dyn CredentialProvider>,
+    ) -> Self {
+        self.authentication.push(HostAuthentication {
+            host: host.into(),
+            authentication: Authentication::Provider(provider),
+        });
+        self
+    }
+
+    /// Send requests through the proxy at `url`, instead of a proxy from the `HTTPS_PROXY` and
+    /// `NO_PROXY` environment variables.
+    #[must_use]
+    pub fn proxy(mut self, url: Url) -> Self {
+        self.proxy = Some(url);
+        self
+    }
+
+    /// Reach these hosts without the proxy set by [`HttpTransportBuilder::proxy`]. Entries are
+    /// matched in the same way as the `NO_PROXY` environment variable: a domain also matches its
+    /// subdomains, and IP addresses may be given as CIDR blocks.
+    #[must_use]
+    pub fn no_proxy<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.no_proxy.extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Trust the root certificates in `pem`, which may hold several certificates, in addition to
+    /// the built-in ones.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    #[must_use]
+    pub fn add_root_certificates(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Present a client certificate to servers that require mutual TLS. `certificate_pem` holds
+    /// the certificate chain, and `key_pem` the private key, which must be in PKCS #8 form for
+    /// [`TlsBackend::NativeTls`].
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    #[must_use]
+    pub fn client_identity(
+        mut self,
+        certificate_pem: impl Into<Vec<u8>>,
+        key_pem: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.tls.identity = Some(ClientIdentity {
+            certificate_pem: certificate_pem.into(),
+            key_pem: key_pem.into(),
+        });
+        self
+    }
+
+    /// Set the TLS implementation.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    #[must_use]
+    pub fn tls_backend(mut self, backend: TlsBackend) -> Self {
+        self.tls.backend = backend;
+        self
+    }
+
     /// Construct an [`HttpTransport`] transport from this builder's settings.
+    ///
+    /// If the HTTP client cannot be built, for example if the TLS backend cannot be initialized,
+    /// every fetch fails with that error. Use [`HttpTransportBuilder::try_build`] to handle this
+    /// case when the transport is built.
     pub fn build(self) -> HttpTransport {
-        HttpTransport { settings: self }
+        let client = self.build_client().map_err(Arc::new);
+        self.into_transport(client)
+    }
+
+    /// Construct an [`HttpTransport`] transport from this builder's settings, returning an error
+    /// if the HTTP client cannot be built.
+    pub fn try_build(self) -> Result<HttpTransport, HttpError> {
+        let client = self.build_client()?;
+        Ok(self.into_transport(Ok(client)))
+    }
+
+    /// Builds the HTTP client, which is shared by the clones of the transport.
+    fn build_client(&self) -> Result<Client, HttpError> {
+        let mut builder = ClientBuilder::new()
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout);
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(url) = &self.proxy {
+            let proxy = Proxy::all(url.as_str())
+                .context(ProxySnafu { url: url.clone() })?
+                .no_proxy(NoProxy::from_string(&self.no_proxy.join(",")));
+            builder = builder.proxy(proxy);
+        }
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        {
+            builder = self.tls.apply(builder)?;
+        }
+        let header_hosts: Vec<String> = self
+            .authentication
+            .iter()
+            .filter(|auth| matches!(auth.authentication, Authentication::Header(..)))
+            .map(|auth| auth.host.clone())
+            .collect();
+        if !header_hosts.is_empty() {
+            builder = builder.redirect(Policy::custom(move |attempt| {
+                let leaves_host = attempt.previous().last().is_some_and(|previous| {
+                    header_hosts.iter().any(|host| {
+                        host_matches(host, previous) && !host_matches(host, attempt.url())
+                    })
+                });
+                if leaves_host {
+                    attempt
+                        .error("refusing to redirect a request with custom headers to another host")
+                } else if attempt.previous().len() > MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else {
+                    attempt.follow()
+                }
+            }));
+        }
+        builder.build().context(HttpClientSnafu)
+    }
+
+    /// Creates a transport with these settings, which fails every fetch if `client` is an error.
+    fn into_transport(self, client: Result<Client, Arc<HttpError>>) -> HttpTransport {
+        HttpTransport {
+            client,
+            circuit_breaker: self
+                .circuit_breaker
+                .map(|(failure_threshold, open_duration)| {
+                    Arc::new(CircuitBreaker::new(failure_threshold, open_duration))
+                }),
+            settings: Arc::new(self),
+            observer: None,
+        }
     }
 }
 
+/// The TLS implementation used by an [`HttpTransport`]. Each is enabled by the `tough` feature
+/// of the same name. The default is `NativeTls` when it is enabled, as with `reqwest`.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TlsBackend {
+    /// The platform's TLS library, such as OpenSSL on Linux.
+    #[cfg(feature = "native-tls")]
+    #[default]
+    NativeTls,
+    /// `rustls`.
+    #[cfg(feature = "rustls-tls")]
+    #[cfg_attr(not(feature = "native-tls"), default)]
+    Rustls,
+}
+
+/// The TLS settings of an [`HttpTransportBuilder`], in PEM form until the client is built.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+#[derive(Clone, Debug, Default)]
+struct TlsSettings {
+    backend: TlsBackend,
+    root_certificates: Vec<Vec<u8>>,
+    identity: Option<ClientIdentity>,
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+impl TlsSettings {
+    fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, HttpError> {
+        builder = match self.backend {
+            #[cfg(feature = "native-tls")]
+            TlsBackend::NativeTls => builder.use_native_tls(),
+            #[cfg(feature = "rustls-tls")]
+            TlsBackend::Rustls => builder.use_rustls_tls(),
+        };
+        for pem in &self.root_certificates {
+            let certificates = Certificate::from_pem_bundle(pem).context(CertificateSnafu)?;
+            snafu::ensure!(!certificates.is_empty(), NoCertificatesSnafu);
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.to_identity(self.backend)?);
+        }
+        Ok(builder)
+    }
+}
+
+/// A client certificate and its private key, for mutual TLS.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+#[derive(Clone)]
+struct ClientIdentity {
+    certificate_pem: Vec<u8>,
+    key_pem: Vec<u8>,
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+impl ClientIdentity {
+    fn to_identity(&self, backend: TlsBackend) -> Result<Identity, HttpError> {
+        match backend {
+            #[cfg(feature = "native-tls")]
+            TlsBackend::NativeTls => Identity::from_pkcs8_pem(&self.certificate_pem, &self.key_pem),
+            #[cfg(feature = "rustls-tls")]
+            TlsBackend::Rustls => {
+                Identity::from_pem(&[&self.key_pem[..], b"\n", &self.certificate_pem].concat())
+            }
+        }
+        .context(ClientIdentitySnafu)
+    }
+}
+
+/// The private key is not printed.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field(
+                "certificate_pem",
+                &String::from_utf8_lossy(&self.certificate_pem),
+            )
+            .field("key_pem", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The number of redirects followed by the default redirect policy of `reqwest`.
+const MAX_REDIRECTS: usize = 10;
+
+/// Credentials for authenticating HTTP requests, as returned by a [`CredentialProvider`].
+#[derive(Clone)]
+pub enum Credentials {
+    /// Send an `Authorization: Bearer` header with the token.
+    Bearer(String),
+    /// Send an `Authorization: Basic` header with the username and password.
+    Basic {
+        /// The username.
+        username: String,
+        /// The password, if any.
+        password: Option<String>,
+    },
+}
+
+impl Credentials {
+    fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Credentials::Bearer(token) => request.bearer_auth(token),
+            Credentials::Basic { username, password } => {
+                request.basic_auth(username, password.as_ref())
+            }
+        }
+    }
+}
+
+/// Secrets are not printed.
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credentials::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Credentials::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+/// Supplies the credentials for HTTP requests to a host when each request is sent, for example to
+/// use tokens that rotate. See [`HttpTransportBuilder::credential_provider`].
+#[async_trait]
+pub trait CredentialProvider: std::fmt::Debug + Send + Sync {
+    /// Returns the credentials to send with a request for `url`.
+    async fn credentials(
+        &self,
+        url: &Url,
+    ) -> Result<Credentials, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+#[derive(Clone)]
+enum Authentication {
+    Header(HeaderName, HeaderValue),
+    Credentials(Credentials),
+    Provider(Arc<dyn CredentialProvider>),
+}
+
+/// Headers or credentials that are only sent to `host`.
+#[derive(Clone)]
+struct HostAuthentication {
+    host: String,
+    authentication: Authentication,
+}
+
+/// Header values are not printed, since they may hold secrets.
+impl std::fmt::Debug for HostAuthentication {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("HostAuthentication");
+        debug.field("host", &self.host);
+        match &self.authentication {
+            Authentication::Header(name, _) => debug.field("header", name),
+            Authentication::Credentials(credentials) => debug.field("credentials", credentials),
+            Authentication::Provider(provider) => debug.field("provider", provider),
+        };
+        debug.finish()
+    }
+}
+
+/// Whether `url` is on `host`, which is either a host name, or a host name and port.
+fn host_matches(host: &str, url: &Url) -> bool {
+    let Some(url_host) = url.host_str() else {
+        return false;
+    };
+    host.eq_ignore_ascii_case(url_host)
+        || url
+            .port_or_known_default()
+            .is_some_and(|port| host.eq_ignore_ascii_case(&format!("{url_host}:{port}")))
+}
+
+/// Adds the headers and credentials for the host of `url` to `request`.
+async fn authenticate(
+    mut request: RequestBuilder,
+    url: &Url,
+    settings: &HttpTransportBuilder,
+) -> Result<RequestBuilder, HttpError> {
+    for auth in &settings.authentication {
+        if !host_matches(&auth.host, url) {
+            continue;
+        }
+        request = match &auth.authentication {
+            Authentication::Header(name, value) => request.header(name, value),
+            Authentication::Credentials(credentials) => credentials.apply(request),
+            Authentication::Provider(provider) => provider
+                .credentials(url)
+                .await
+                .context(CredentialProviderSnafu { host: &auth.host })?
+                .apply(request),
+        };
+    }
+    Ok(request)
+}
+
 /// A [`Transport`] over HTTP with retry logic. Use the [`HttpTransportBuilder`] to construct a
 /// custom `HttpTransport`, or use `HttpTransport::default()`.
 ///
+/// The transport holds a single HTTP client, so connections are pooled and reused across fetches,
+/// retries, and clones of the transport.
+///
 /// This transport returns `FileNotFound` for the following HTTP response codes:
 /// - 403: Forbidden. (Some services return this code when a file does not exist.)
 /// - 404: Not Found.
 /// - 410: Gone.
 ///
+/// [`Transport::fetch_conditional`] sends the validators as `If-None-Match` and
+/// `If-Modified-Since` headers, and returns the `ETag` and `Last-Modified` headers of the response
+/// as validators.
+///
 /// # Proxy Support
 ///
 /// To use the `HttpTransport` with a proxy, specify the `HTTPS_PROXY` environment variable.
 /// The transport will also respect the `NO_PROXY` environment variable.
 ///
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct HttpTransport {
-    settings: HttpTransportBuilder,
+    settings: Arc<HttpTransportBuilder>,
+    /// The shared client, or the error that kept it from being built.
+    client: Result<Client, Arc<HttpError>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    observer: Option<Arc<dyn Observer>>,
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        HttpTransportBuilder::default().build()
+    }
 }
 
 /// Implement the `tough` `Transport` trait for `HttpRetryTransport`
@@ -138,7 +644,47 @@ impl Transport for HttpTransport {
     /// the `ClientSettings`.
     async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
         let r = RetryState::new(self.settings.initial_backoff);
-        Ok(fetch_with_retries(r, &self.settings, &url).boxed())
+        Ok(self.fetch_with_retries(r, &url)?.boxed())
+    }
+
+    /// Send a GET request for the bytes of the URL starting at `start`. If the server ignores the
+    /// byte range and sends the whole file, the leading bytes are discarded.
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        let mut r = RetryState::new(self.settings.initial_backoff);
+        r.next_byte = usize::try_from(start).map_err(|e| {
+            TransportError::new_with_cause(TransportErrorKind::Other, url.clone(), e)
+        })?;
+        Ok(self.fetch_with_retries(r, &url)?.boxed())
+    }
+
+    /// Send a GET request with the `validators` as conditional headers. A `304 Not Modified`
+    /// response means the file has not changed.
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        let r = RetryState::new(self.settings.initial_backoff);
+        let mut stream = self.fetch_with_retries(r, &url)?;
+        stream.validators = Some(validators.clone());
+        // Wait for the response, and the first chunk of its body, before deciding.
+        let first = match stream.next().await {
+            Some(Err(e)) => return Err(e),
+            first => first,
+        };
+        if stream.not_modified {
+            return Ok(ConditionalFetch::NotModified);
+        }
+        let validators = std::mem::take(&mut stream.response_validators);
+        Ok(ConditionalFetch::Modified {
+            stream: futures::stream::iter(first).chain(stream).boxed(),
+            validators,
+        })
+    }
+
+    /// Notify `observer` whenever a request is retried.
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.observer = Some(observer);
     }
 }
 
@@ -146,7 +692,7 @@ enum RequestState {
     /// A response is streaming.
     Streaming(BoxStream<'static, reqwest::Result<bytes::Bytes>>),
     /// A request is pending.
-    Pending(BoxFuture<'static, reqwest::Result<reqwest::Response>>),
+    Pending(BoxFuture<'static, Result<reqwest::Result<reqwest::Response>, HttpError>>),
     /// No ongoing request.
     None,
 }
@@ -164,11 +710,21 @@ impl std::fmt::Debug for RequestState {
 #[derive(Debug)]
 struct RetryStream {
     retry_state: RetryState,
-    settings: HttpTransportBuilder,
+    settings: Arc<HttpTransportBuilder>,
+    client: Client,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    observer: Option<Arc<dyn Observer>>,
     url: Url,
+    started: Instant,
     request: RequestState,
     done: bool,
     has_range_support: bool,
+    /// Validators to send as conditional headers, if any.
+    validators: Option<Validators>,
+    /// The validators of the response.
+    response_validators: Validators,
+    /// Whether the response was `304 Not Modified`.
+    not_modified: bool,
 }
 
 impl Stream for RetryStream {
@@ -227,8 +783,9 @@ impl RetryStream {
             Poll::Ready(Some(Err(err))) => match ErrorClass::from(err) {
                 ErrorClass::Fatal(e) => self.poll_err(e),
                 ErrorClass::FileNotFound(_) => unreachable!("streaming the response body already"),
-                ErrorClass::Retryable(e) => {
-                    if self.may_retry() {
+                ErrorClass::Retryable(e, retry_after) => {
+                    self.record_result(false);
+                    if self.may_retry(retry_after) {
                         match self.poll_new_request(cx) {
                             Ok(poll) => poll,
                             Err(_) => self.poll_err(e),
@@ -251,8 +808,20 @@ impl RetryStream {
             return None;
         };
         match request.as_mut().poll(cx) {
-            Poll::Ready(response) => {
+            Poll::Ready(Err(e)) => {
+                trace!(
+                    "{:?} - returning fatal error from authentication: {}",
+                    self.retry_state,
+                    e
+                );
+                self.poll_err(e)
+            }
+            Poll::Ready(Ok(response)) => {
                 let http_result: HttpResult = response.into();
+                self.record_result(!matches!(
+                    http_result,
+                    HttpResult::Err(ErrorClass::Retryable(..))
+                ));
                 match http_result {
                     HttpResult::Ok(response) => {
                         trace!("{:?} - returning from successful fetch", self.retry_state);
@@ -263,7 +832,20 @@ impl RetryStream {
                                 }
                             }
                         }
-                        self.request = RequestState::Streaming(response.bytes_stream().boxed());
+                        let next_byte = self.retry_state.next_byte;
+                        if next_byte == 0 {
+                            self.response_validators = validators(response.headers());
+                            self.not_modified = response.status() == StatusCode::NOT_MODIFIED;
+                        }
+                        let partial = response.status() == StatusCode::PARTIAL_CONTENT;
+                        let stream = response.bytes_stream();
+                        self.request = if next_byte == 0 || partial {
+                            RequestState::Streaming(stream.boxed())
+                        } else {
+                            // The server ignored the byte range and is sending the whole file.
+                            let next_byte = u64::try_from(next_byte).unwrap_or(u64::MAX);
+                            RequestState::Streaming(skip_bytes(stream, next_byte).boxed())
+                        };
                         cx.waker().wake_by_ref();
                         Poll::Pending
                     }
@@ -288,9 +870,9 @@ impl RetryStream {
                             e,
                         ))))
                     }
-                    HttpResult::Err(ErrorClass::Retryable(e)) => {
+                    HttpResult::Err(ErrorClass::Retryable(e, retry_after)) => {
                         trace!("{:?} - retryable error: {}", self.retry_state, e);
-                        if self.may_retry() {
+                        if self.may_retry(retry_after) {
                             match self.poll_new_request(cx) {
                                 Ok(poll) => poll,
                                 Err(_) => self.poll_err(e),
@@ -305,16 +887,48 @@ impl RetryStream {
         }
         .into()
     }
-    /// Check all criteria for a retry and account for it.
-    fn may_retry(&mut self) -> bool {
+    /// Count a finished try towards the host's circuit breaker.
+    fn record_result(&self, success: bool) {
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.record(&self.url, success);
+        }
+    }
+
+    /// Check all criteria for a retry and account for it. The server may ask us to wait with
+    /// `retry_after`.
+    fn may_retry(&mut self, retry_after: Option<Duration>) -> bool {
         let tries_left = self
             .settings
             .tries
             .saturating_sub(self.retry_state.current_try);
 
         self.retry_state.increment(&self.settings);
+        // the server may ask for any wait, so give up rather than sleep longer than we allow
+        let retry_after_allowed =
+            retry_after.is_none_or(|retry_after| retry_after <= self.settings.max_retry_after);
+        if let (true, Some(retry_after)) = (retry_after_allowed, retry_after) {
+            self.retry_state.wait = self.retry_state.wait.max(retry_after);
+        }
+        let within_deadline = self.settings.retry_deadline.is_none_or(|deadline| {
+            self.started
+                .elapsed()
+                .checked_add(self.retry_state.wait)
+                .is_some_and(|next_try| next_try < deadline)
+        });
 
-        tries_left > 0 && (self.has_range_support || self.retry_state.next_byte == 0)
+        let may_retry = tries_left > 0
+            && retry_after_allowed
+            && within_deadline
+            && (self.has_range_support || self.retry_state.next_byte == 0);
+        if let (true, Some(observer)) = (may_retry, &self.observer) {
+            observer.notify(&Event::RetryAttempted {
+                url: &self.url,
+                attempt: self.retry_state.current_try,
+                next_byte: u64::try_from(self.retry_state.next_byte).unwrap_or(u64::MAX),
+                wait: self.retry_state.wait,
+            });
+        }
+        may_retry
     }
 
     /// Move to `RequestState::Executing`.
@@ -322,21 +936,31 @@ impl RetryStream {
         &mut self,
         cx: &mut std::task::Context<'_>,
     ) -> Result<Poll<Option<Result<bytes::Bytes, TransportError>>>, HttpError> {
-        // create a reqwest client
-        let client = ClientBuilder::new()
-            .timeout(self.settings.timeout)
-            .connect_timeout(self.settings.connect_timeout)
-            .build()
-            .context(HttpClientSnafu)?;
+        // share the transport's client, and its connection pool
+        let client = self.client.clone();
 
         // build the request
-        let request = build_request(&client, self.retry_state.next_byte, &self.url)?;
+        let request = build_request(
+            &client,
+            self.retry_state.next_byte,
+            self.validators.as_ref(),
+            &self.url,
+        )?;
 
         let backoff = self.retry_state.wait;
+        let settings = Arc::clone(&self.settings);
+        let circuit_breaker = self.circuit_breaker.clone();
+        let url = self.url.clone();
 
         let delayed_request = async move {
             tokio::time::sleep(backoff).await;
-            client.execute(request).await
+            if let Some(circuit_breaker) = circuit_breaker {
+                circuit_breaker.check(&url)?;
+            }
+            // credentials are added when the request is sent, so that rotated tokens are used
+            let request =
+                authenticate(RequestBuilder::from_parts(client, request), &url, &settings).await?;
+            Ok(request.send().await)
         }
         .boxed();
 
@@ -373,7 +997,11 @@ impl RetryState {
     /// Increments the count and the wait duration.
     fn increment(&mut self, settings: &HttpTransportBuilder) {
         if self.current_try > 0 {
-            let new_wait = self.wait.mul_f32(settings.backoff_factor);
+            // a wait that does not fit in a `Duration` is longer than any `max_backoff`
+            let new_wait = Duration::try_from_secs_f64(
+                self.wait.as_secs_f64() * f64::from(settings.backoff_factor),
+            )
+            .unwrap_or(Duration::MAX);
             match new_wait.cmp(&settings.max_backoff) {
                 Ordering::Less => {
                     self.wait = new_wait;
@@ -388,17 +1016,30 @@ impl RetryState {
     }
 }
 
-/// Sends a `GET` request to the `url`. Retries the request as necessary per the `ClientSettings`.
-fn fetch_with_retries(r: RetryState, cs: &HttpTransportBuilder, url: &Url) -> RetryStream {
-    trace!("beginning fetch for '{}'", url);
+impl HttpTransport {
+    /// Sends a `GET` request to the `url`. Retries the request as necessary per the
+    /// `ClientSettings`. Fails if the HTTP client could not be built.
+    fn fetch_with_retries(&self, r: RetryState, url: &Url) -> Result<RetryStream, TransportError> {
+        trace!("beginning fetch for '{}'", url);
+        let client = self.client.clone().map_err(|e| {
+            TransportError::new_with_cause(TransportErrorKind::Other, url.clone(), e)
+        })?;
 
-    RetryStream {
-        retry_state: r,
-        settings: *cs,
-        url: url.clone(),
-        request: RequestState::None,
-        done: false,
-        has_range_support: false,
+        Ok(RetryStream {
+            retry_state: r,
+            settings: Arc::clone(&self.settings),
+            client,
+            circuit_breaker: self.circuit_breaker.clone(),
+            observer: self.observer.clone(),
+            url: url.clone(),
+            started: Instant::now(),
+            request: RequestState::None,
+            done: false,
+            has_range_support: false,
+            validators: None,
+            response_validators: Validators::default(),
+            not_modified: false,
+        })
     }
 }
 
@@ -417,8 +1058,9 @@ enum ErrorClass {
     Fatal(reqwest::Error),
     /// The file could not be found (HTTP status 403 or 404).
     FileNotFound(reqwest::Error),
-    /// We received an `Error`, or we received an HTTP response code that we can retry.
-    Retryable(reqwest::Error),
+    /// We received an `Error`, or we received an HTTP response code that we can retry, possibly
+    /// with a `Retry-After` header asking us to wait.
+    Retryable(reqwest::Error, Option<Duration>),
 }
 
 /// Takes the `Result` type from `reqwest::Client::execute`, and categorizes it into an
@@ -442,11 +1084,11 @@ impl From<reqwest::Error> for ErrorClass {
         if err.is_timeout() {
             // a connection timeout occurred
             trace!("timeout error during fetch: {}", err);
-            ErrorClass::Retryable(err)
+            ErrorClass::Retryable(err, None)
         } else if err.is_request() {
             // an error occurred while sending the request
             trace!("error sending request during fetch: {}", err);
-            ErrorClass::Retryable(err)
+            ErrorClass::Retryable(err, None)
         } else {
             // the error is not from an HTTP status code or a timeout, retries will not succeed.
             // these appear to be internal, reqwest errors and are expected to be unlikely.
@@ -458,6 +1100,7 @@ impl From<reqwest::Error> for ErrorClass {
 
 /// Checks the HTTP response code and converts a non-successful response code to an error.
 fn parse_response_code(response: reqwest::Response) -> HttpResult {
+    let retry_after = retry_after(response.headers());
     match response.error_for_status() {
         Ok(ok) => {
             trace!("response is success");
@@ -472,9 +1115,9 @@ fn parse_response_code(response: reqwest::Response) -> HttpResult {
                 trace!("error is fatal (no status): {}", err);
                 HttpResult::Err(ErrorClass::Fatal(err))
             }
-            Some(status) if status.is_server_error() => {
+            Some(status) if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS => {
                 trace!("error is retryable: {}", err);
-                HttpResult::Err(ErrorClass::Retryable(err))
+                HttpResult::Err(ErrorClass::Retryable(err, retry_after))
             }
             Some(status) if matches!(status.as_u16(), 403 | 404 | 410) => {
                 trace!("error is file not found: {}", err);
@@ -488,13 +1131,127 @@ fn parse_response_code(response: reqwest::Response) -> HttpResult {
     }
 }
 
+/// Reads how long the server asks us to wait before retrying from a `Retry-After` header, which
+/// holds either a number of seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        // values too large for a `u64` are still a request to wait longer than we ever will
+        return Some(Duration::from_secs(value.parse().unwrap_or(u64::MAX)));
+    }
+    let time = httpdate::parse_http_date(value).ok()?;
+    Some(
+        time.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Reads the validators of a response from its `ETag` and `Last-Modified` headers.
+fn validators(headers: &HeaderMap) -> Validators {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+    Validators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    }
+}
+
+/// Counts consecutive failed tries to each host, and stops requests to a host for a while when
+/// there are too many. It is shared by an [`HttpTransport`] and its clones.
+#[derive(Debug)]
+struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    hosts: Mutex<HashMap<String, HostCircuit>>,
+}
+
+#[derive(Debug, Default)]
+struct HostCircuit {
+    /// The number of consecutive failed tries.
+    failures: u32,
+    /// Requests are not sent until this time.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold,
+            open_duration,
+            hosts: Mutex::default(),
+        }
+    }
+
+    fn host(url: &Url) -> String {
+        format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        )
+    }
+
+    /// Returns an error if requests to the host of `url` are stopped.
+    fn check(&self, url: &Url) -> Result<(), HttpError> {
+        let host = Self::host(url);
+        let hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(open_until) = hosts.get(&host).and_then(|circuit| circuit.open_until) {
+            let now = Instant::now();
+            snafu::ensure!(
+                open_until <= now,
+                CircuitOpenSnafu {
+                    host,
+                    retry_in: open_until - now,
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Counts a finished try to the host of `url`.
+    fn record(&self, url: &Url, success: bool) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        let circuit = hosts.entry(Self::host(url)).or_default();
+        if success {
+            *circuit = HostCircuit::default();
+        } else {
+            circuit.failures = circuit.failures.saturating_add(1);
+            if circuit.failures >= self.failure_threshold {
+                circuit.open_until = Some(Instant::now() + self.open_duration);
+            }
+        }
+    }
+}
+
 /// Builds a GET request. If `next_byte` is greater than zero, adds a byte range header to the request.
-fn build_request(client: &Client, next_byte: usize, url: &Url) -> Result<Request, HttpError> {
+fn build_request(
+    client: &Client,
+    next_byte: usize,
+    validators: Option<&Validators>,
+    url: &Url,
+) -> Result<Request, HttpError> {
     if next_byte == 0 {
-        let request = client
-            .request(Method::GET, url.as_str())
-            .build()
-            .context(RequestBuildSnafu)?;
+        let mut request = client.request(Method::GET, url.as_str());
+        // Only ask for the whole file conditionally; a resumed download needs the rest of it.
+        if let Some(validators) = validators {
+            let conditions = [
+                (IF_NONE_MATCH, &validators.etag),
+                (IF_MODIFIED_SINCE, &validators.last_modified),
+            ];
+            for (name, value) in conditions {
+                if let Some(value) = value {
+                    let header_value =
+                        HeaderValue::from_str(value).context(InvalidHeaderSnafu {
+                            header_value: value,
+                        })?;
+                    request = request.header(name, header_value);
+                }
+            }
+        }
+        let request = request.build().context(RequestBuildSnafu)?;
         Ok(request)
     } else {
         let header_value_string = format!("bytes={next_byte}-");
@@ -516,6 +1273,25 @@ fn build_request(client: &Client, next_byte: usize, url: &Url) -> Result<Request
 #[non_exhaustive]
 #[allow(missing_docs)]
 pub enum HttpError {
+    #[snafu(display("Invalid root certificate: {}", source))]
+    Certificate { source: reqwest::Error },
+
+    #[snafu(display(
+        "Not sending requests to '{}' after repeated failures, for another {:?}",
+        host,
+        retry_in
+    ))]
+    CircuitOpen { host: String, retry_in: Duration },
+
+    #[snafu(display("Invalid client certificate or key: {}", source))]
+    ClientIdentity { source: reqwest::Error },
+
+    #[snafu(display("Failed to get credentials for '{}': {}", host, source))]
+    CredentialProvider {
+        host: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     #[snafu(display("A non-retryable error occurred: {}", source))]
     FetchFatal { source: reqwest::Error },
 
@@ -534,6 +1310,12 @@ pub enum HttpError {
         source: reqwest::header::InvalidHeaderValue,
     },
 
+    #[snafu(display("No certificates found in PEM root certificates"))]
+    NoCertificates,
+
+    #[snafu(display("Invalid proxy '{}': {}", url, source))]
+    Proxy { url: Url, source: reqwest::Error },
+
     #[snafu(display("Unable to create HTTP request: {}", source))]
     RequestBuild { source: reqwest::Error },
 }
@@ -549,3 +1331,36 @@ impl From<(Url, HttpError)> for TransportError {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn retry_after_value(value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        retry_after(&headers)
+    }
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after_value("30"), Some(Duration::from_secs(30)));
+        assert_eq!(retry_after_value("garbage"), None);
+        assert_eq!(
+            retry_after_value("18446744073709551615"),
+            Some(Duration::from_secs(u64::MAX))
+        );
+        assert_eq!(
+            retry_after_value("99999999999999999999999"),
+            Some(Duration::from_secs(u64::MAX))
+        );
+        assert_eq!(
+            retry_after_value("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        let wait = retry_after_value(&later).unwrap();
+        assert!(wait > Duration::from_secs(20) && wait <= Duration::from_secs(30));
+    }
+}
@@ -1,28 +1,96 @@
 // Copyright 2019 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{error, transport::TransportStream, TransportError};
+use crate::error::{self, Result};
+use crate::schema::Hashes;
+use crate::{transport::TransportStream, TransportError};
 use futures::StreamExt;
 use futures_core::Stream;
-use ring::digest::{Context, SHA256};
+use ring::digest::Context;
+use snafu::ensure;
 use std::{convert::TryInto, path::Path, task::Poll};
 use tokio::fs;
 use url::Url;
 
+/// The running digests of the leading bytes of a file, for every supported algorithm in its
+/// `Hashes`.
+#[derive(Clone)]
+pub(crate) struct Digests {
+    /// The expected digest and running digest for each algorithm being checked.
+    digests: Vec<(Vec<u8>, Context)>,
+    /// The number of bytes digested so far.
+    len: u64,
+}
+
+impl Digests {
+    /// Starts digesting a file that is expected to match `hashes`. Fails if `hashes` does not list
+    /// any supported algorithm. `context` describes the file for error messages.
+    pub(crate) fn new(hashes: &Hashes, context: &str) -> Result<Self> {
+        let digests = hashes
+            .iter()
+            .map(|(algorithm, hash)| (hash.to_owned(), Context::new(algorithm.digest_algorithm())))
+            .collect::<Vec<_>>();
+        ensure!(!digests.is_empty(), error::NoSupportedHashSnafu { context });
+        Ok(Self { digests, len: 0 })
+    }
+
+    /// Adds `bytes` to the running digests.
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for (_, digest) in &mut self.digests {
+            digest.update(bytes);
+        }
+        self.len = self
+            .len
+            .saturating_add(bytes.len().try_into().unwrap_or(u64::MAX));
+    }
+
+    /// The number of bytes digested so far.
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Checks that the bytes digested so far match every expected digest.
+    pub(crate) fn check(&self, context: &str) -> Result<()> {
+        for (hash, digest) in &self.digests {
+            let result = digest.clone().finish();
+            ensure!(
+                result.as_ref() == hash.as_slice(),
+                error::HashMismatchSnafu {
+                    context,
+                    calculated: hex::encode(result),
+                    expected: hex::encode(hash),
+                }
+            );
+        }
+        Ok(())
+    }
+}
+
 pub(crate) struct DigestAdapter {
     url: Url,
     stream: TransportStream,
-    hash: Vec<u8>,
-    digest: Context,
+    digests: Digests,
 }
 
 impl DigestAdapter {
-    pub(crate) fn sha256(stream: TransportStream, hash: &[u8], url: Url) -> TransportStream {
+    /// Checks `stream` against every digest in `hashes` whose algorithm is supported. Fails if
+    /// `hashes` does not list any supported algorithm.
+    pub(crate) fn hashes(
+        stream: TransportStream,
+        hashes: &Hashes,
+        url: Url,
+    ) -> Result<TransportStream> {
+        let digests = Digests::new(hashes, url.as_str())?;
+        Ok(Self::resume(stream, digests, url))
+    }
+
+    /// Checks that `stream`, following the bytes that `digests` were computed over, completes the
+    /// expected digests.
+    pub(crate) fn resume(stream: TransportStream, digests: Digests, url: Url) -> TransportStream {
         Self {
             url,
             stream,
-            hash: hash.to_owned(),
-            digest: Context::new(&SHA256),
+            digests,
         }
         .boxed()
     }
@@ -38,17 +106,10 @@ impl Stream for DigestAdapter {
         let poll = self.stream.as_mut().poll_next(cx);
         match &poll {
             Poll::Ready(Some(Ok(bytes))) => {
-                self.digest.update(bytes);
+                self.digests.update(bytes);
             }
             Poll::Ready(None) => {
-                let result = &self.digest.clone().finish();
-                if result.as_ref() != self.hash.as_slice() {
-                    let mismatch_err = error::HashMismatchSnafu {
-                        context: self.url.to_string(),
-                        calculated: hex::encode(result),
-                        expected: hex::encode(&self.hash),
-                    }
-                    .build();
+                if let Err(mismatch_err) = self.digests.check(self.url.as_str()) {
                     return Poll::Ready(Some(Err(TransportError::new_with_cause(
                         crate::TransportErrorKind::Other,
                         self.url.clone(),
@@ -116,7 +177,8 @@ pub async fn is_dir(path: impl AsRef<Path>) -> bool {
 #[cfg(test)]
 mod tests {
     use crate::{
-        io::{max_size_adapter, DigestAdapter},
+        io::{max_size_adapter, DigestAdapter, Digests},
+        schema::Hashes,
         transport::IntoVec,
     };
     use bytes::Bytes;
@@ -138,23 +200,89 @@ mod tests {
         assert!(stream.into_vec().await.is_err());
     }
 
+    fn sha256(hash: &[u8]) -> Hashes {
+        Hashes {
+            sha256: Some(hash.to_vec().into()),
+            ..Hashes::default()
+        }
+    }
+
     #[tokio::test]
     async fn test_digest_adapter() {
         let stream = stream::iter("hello".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
-        let stream = DigestAdapter::sha256(
+        let stream = DigestAdapter::hashes(
             stream,
-            &hex!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"),
+            &sha256(&hex!(
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+            )),
             Url::parse("file:///").unwrap(),
-        );
+        )
+        .unwrap();
         let buf = stream.into_vec().await.expect("consuming entire stream");
         assert_eq!(buf, b"hello");
 
         let stream = stream::iter("hello".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
-        let stream = DigestAdapter::sha256(
+        let stream = DigestAdapter::hashes(
             stream,
-            &hex!("0ebdc3317b75839f643387d783535adc360ca01f33c75f7c1e7373adcd675c0b"),
+            &sha256(&hex!(
+                "0ebdc3317b75839f643387d783535adc360ca01f33c75f7c1e7373adcd675c0b"
+            )),
             Url::parse("file:///").unwrap(),
+        )
+        .unwrap();
+        assert!(stream.into_vec().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_digest_adapter_resume() {
+        let hashes = sha256(&hex!(
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        ));
+        let mut digests = Digests::new(&hashes, "test").unwrap();
+        digests.update(b"he");
+        assert_eq!(digests.len(), 2);
+        assert!(digests.check("test").is_err());
+
+        let stream = stream::iter("llo".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
+        let stream =
+            DigestAdapter::resume(stream, digests.clone(), Url::parse("file:///").unwrap());
+        assert_eq!(stream.into_vec().await.unwrap(), b"llo");
+
+        let stream = stream::iter("hello".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
+        let stream = DigestAdapter::resume(stream, digests, Url::parse("file:///").unwrap());
+        assert!(stream.into_vec().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_digest_adapter_multiple_hashes() {
+        let hello_sha512 = hex!(
+            "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca7"
+            "2323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043"
         );
+        let stream = stream::iter("hello".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
+        let mut hashes = sha256(&hex!(
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        ));
+        hashes.sha512 = Some(hello_sha512.to_vec().into());
+        let stream =
+            DigestAdapter::hashes(stream, &hashes, Url::parse("file:///").unwrap()).unwrap();
+        let buf = stream.into_vec().await.expect("consuming entire stream");
+        assert_eq!(buf, b"hello");
+
+        // A matching SHA 256 digest is not enough if the SHA 512 digest does not match.
+        let stream = stream::iter("hello".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
+        let mut bad_sha512 = hello_sha512;
+        bad_sha512[0] ^= 1;
+        hashes.sha512 = Some(bad_sha512.to_vec().into());
+        let stream =
+            DigestAdapter::hashes(stream, &hashes, Url::parse("file:///").unwrap()).unwrap();
         assert!(stream.into_vec().await.is_err());
+
+        // Hashes without a supported algorithm cannot be verified.
+        let stream = stream::iter("hello".as_bytes().chunks(2).map(Bytes::from).map(Ok)).boxed();
+        assert!(
+            DigestAdapter::hashes(stream, &Hashes::default(), Url::parse("file:///").unwrap())
+                .is_err()
+        );
     }
 }
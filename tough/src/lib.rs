@@ -5,8 +5,13 @@
 //!
 //! This client adheres to [TUF version 1.0.0][spec], with the following exceptions:
 //!
-//! * Delegated roles (and TAP 3) are not yet supported.
-//! * TAP 4 (multiple repository consensus) is not yet supported.
+//! * Multi-role delegations (TAP 3) are not supported.
+//!
+//! Delegated targets roles are supported. Target lookups follow the preorder depth-first search of
+//! TUF 5.6.7, stopping at terminating delegations, and only fetch the delegated roles that they
+//! reach (see [`Repository::find_target`]).
+//!
+//! TAP 4 (multiple repository consensus) is supported by [`multi::MultiRepository`].
 //!
 //! [TUF repositories]: https://theupdateframework.github.io/
 //! [spec]: https://github.com/theupdateframework/specification/blob/9f148556ca15da2ec5c022c8b3e6f99a028e5fe5/tuf-spec.md
@@ -30,35 +35,61 @@
     clippy::result_large_err
 )]
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "archive")]
+mod bundle;
 mod cache;
-mod datastore;
+pub mod clock;
+pub mod datastore;
 pub mod editor;
 pub mod error;
+mod expiration;
 mod fetch;
 #[cfg(feature = "http")]
 pub mod http;
 mod io;
 pub mod key_source;
+mod mirror;
+pub mod multi;
+pub mod observer;
+mod partial;
+mod query;
 pub mod schema;
 pub mod sign;
 mod target_name;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod throttle;
 mod transport;
 mod urlpath;
 
-use crate::datastore::Datastore;
+use crate::clock::{Clock, SystemClock, TrustedTimePolicy};
+use crate::datastore::{Datastore, FilesystemDatastore, Store};
 use crate::error::Result;
-use crate::fetch::{fetch_max_size, fetch_sha256};
+pub use crate::expiration::RoleExpiration;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+pub use crate::http::TlsBackend;
 /// An HTTP transport that includes retries.
 #[cfg(feature = "http")]
-pub use crate::http::{HttpTransport, HttpTransportBuilder};
-use crate::io::is_dir;
+pub use crate::http::{CredentialProvider, Credentials, HttpTransport, HttpTransportBuilder};
+use crate::io::{is_dir, Digests};
+pub use crate::mirror::Mirror;
+use crate::mirror::Mirrors;
+use crate::observer::{Event, Observer, Observers};
+use crate::partial::PartialTarget;
+pub use crate::query::{TargetMatch, TargetQuery};
 use crate::schema::{
-    DelegatedRole, Delegations, Role, RoleType, Root, Signed, Snapshot, Timestamp,
+    DelegatedRole, Delegations, Role, RoleType, Root, Signed, Snapshot, Target, Timestamp,
 };
 pub use crate::target_name::TargetName;
+pub use crate::throttle::{RateLimit, ThrottledTransport};
+#[cfg(feature = "archive")]
+pub use crate::transport::ArchiveTransport;
 pub use crate::transport::IntoVec;
 pub use crate::transport::{
-    DefaultTransport, FilesystemTransport, Transport, TransportError, TransportErrorKind,
+    CachingTransport, ConditionalFetch, DefaultTransport, FilesystemTransport, Transport,
+    TransportError, TransportErrorKind, Validators,
 };
 pub use crate::urlpath::SafeUrlPath;
 use async_recursion::async_recursion;
@@ -70,11 +101,16 @@ use futures_core::Stream;
 use log::warn;
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use snafu::{ensure, OptionExt, ResultExt};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::SeekFrom;
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 use tempfile::NamedTempFile;
 use tokio::fs::{canonicalize, create_dir_all};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::OnceCell;
 use url::Url;
 
 /// Represents whether a Repository should fail to load when metadata is expired (`Safe`) or whether
@@ -172,9 +208,14 @@ pub struct RepositoryLoader<'a> {
     root: &'a [u8],
     metadata_base_url: Url,
     targets_base_url: Url,
+    mirrors: Vec<Mirror>,
+    observers: Observers,
     transport: Option<Box<dyn Transport + Send + Sync>>,
     limits: Option<Limits>,
-    datastore: Option<PathBuf>,
+    rate_limit: Option<RateLimit>,
+    datastore: Option<Box<dyn Datastore>>,
+    clock: Option<Box<dyn Clock>>,
+    trusted_time_policy: Option<TrustedTimePolicy>,
     expiration_enforcement: Option<ExpirationEnforcement>,
 }
 
@@ -193,9 +234,14 @@ impl<'a> RepositoryLoader<'a> {
             root: root.as_ref(),
             metadata_base_url,
             targets_base_url,
+            mirrors: Vec::new(),
+            observers: Observers::default(),
             transport: None,
             limits: None,
+            rate_limit: None,
             datastore: None,
+            clock: None,
+            trusted_time_policy: None,
             expiration_enforcement: None,
         }
     }
@@ -212,6 +258,27 @@ impl<'a> RepositoryLoader<'a> {
         self
     }
 
+    /// Add a [`Mirror`] to fall back to. Mirrors are tried in the order they are added, after the
+    /// `metadata_base_url` and `targets_base_url` passed to [`RepositoryLoader::new`].
+    ///
+    /// A file is fetched from the next mirror if the previous one does not have it, or fails with
+    /// [`TransportErrorKind::Other`], which includes files that exceed their expected length or
+    /// do not match their expected hash.
+    #[must_use]
+    pub fn mirror(mut self, mirror: Mirror) -> Self {
+        self.mirrors.push(mirror);
+        self
+    }
+
+    /// Add an [`Observer`] to notify of events while the repository is loaded, and while its
+    /// targets are fetched. Observers are also registered with the transport using
+    /// [`Transport::set_observer`].
+    #[must_use]
+    pub fn observer<O: Observer + 'static>(mut self, observer: O) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
     /// Set a the repository [`Limits`].
     #[must_use]
     pub fn limits(mut self, limits: Limits) -> Self {
@@ -219,6 +286,17 @@ impl<'a> RepositoryLoader<'a> {
         self
     }
 
+    /// Limit the rate at which metadata and targets are downloaded to a [`RateLimit`]. The limit
+    /// can be shared with other repositories, and changed while downloads are running.
+    ///
+    /// Downloads go through a [`ThrottledTransport`] wrapping the transport, so the sizes and
+    /// hashes of files are checked as they are without a limit.
+    #[must_use]
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
     /// Set a `datastore` directory path. `datastore` is a directory on a persistent filesystem.
     /// This directory's contents store the most recently fetched timestamp, snapshot, and targets
     /// metadata files to detect version rollback attacks.
@@ -226,9 +304,35 @@ impl<'a> RepositoryLoader<'a> {
     /// You may chose to provide a [`PathBuf`] to a directory on a persistent filesystem, which must
     /// exist prior to calling [`RepositoryLoader::load`]. If no datastore is provided, a temporary
     /// directory will be created and cleaned up for for you.
+    ///
+    /// This is a shortcut for passing a [`FilesystemDatastore`] to
+    /// [`RepositoryLoader::datastore_backend`].
+    #[must_use]
+    pub fn datastore<P: Into<PathBuf>>(self, datastore: P) -> Self {
+        self.datastore_backend(FilesystemDatastore::new(datastore))
+    }
+
+    /// Set the [`Datastore`] in which the most recently fetched metadata files are kept, such as
+    /// a [`MemoryDatastore`](crate::datastore::MemoryDatastore) or your own implementation.
+    #[must_use]
+    pub fn datastore_backend<D: Datastore + 'static>(mut self, datastore: D) -> Self {
+        self.datastore = Some(Box::new(datastore));
+        self
+    }
+
+    /// Set the [`Clock`] used to check whether metadata has expired. If no clock has been set,
+    /// [`SystemClock`] will be used.
     #[must_use]
-    pub fn datastore<P: Into<PathBuf>>(mut self, datastore: P) -> Self {
-        self.datastore = Some(datastore.into());
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Set the [`TrustedTimePolicy`], which decides what happens when the clock is earlier than
+    /// the latest known time. If no policy has been set, [`TrustedTimePolicy::Strict`] will be used.
+    #[must_use]
+    pub fn trusted_time_policy(mut self, policy: TrustedTimePolicy) -> Self {
+        self.trusted_time_policy = Some(policy);
         self
     }
 
@@ -305,6 +409,12 @@ pub enum Prefix {
     Digest,
 }
 
+/// Delegated targets roles that a [`Repository`] has verified, keyed by the name of the delegating
+/// role and the name of the delegated role, since the same role may be delegated with different
+/// keys.
+type DelegatedRoles =
+    Mutex<HashMap<(String, String), Arc<OnceCell<Arc<Signed<crate::schema::Targets>>>>>>;
+
 /// A TUF repository.
 ///
 /// You can create a `Repository` using a [`RepositoryLoader`].
@@ -312,30 +422,56 @@ pub enum Prefix {
 pub struct Repository {
     transport: Box<dyn Transport + Send + Sync>,
     consistent_snapshot: bool,
-    datastore: Datastore,
+    datastore: Store,
     earliest_expiration: DateTime<Utc>,
     earliest_expiration_role: RoleType,
     root: Signed<Root>,
     snapshot: Signed<Snapshot>,
     timestamp: Signed<Timestamp>,
     targets: Signed<crate::schema::Targets>,
+    /// Delegated roles that target lookups have loaded, so that each is only read and verified
+    /// once. Clones share these, and `refresh` starts over with an empty set.
+    delegated_roles: Arc<DelegatedRoles>,
     limits: Limits,
-    metadata_base_url: Url,
-    targets_base_url: Url,
+    mirrors: Mirrors,
+    observers: Observers,
     expiration_enforcement: ExpirationEnforcement,
 }
 
 impl Repository {
     /// Load and verify TUF repository metadata using a [`RepositoryLoader`] for the settings.
     async fn load(loader: RepositoryLoader<'_>) -> Result<Self> {
-        let datastore = Datastore::new(loader.datastore)?;
-        let transport = loader
+        let datastore = Store::new(
+            match loader.datastore {
+                Some(datastore) => datastore,
+                None => Box::new(FilesystemDatastore::temporary()?),
+            },
+            loader.clock.unwrap_or_else(|| Box::new(SystemClock)),
+            loader.trusted_time_policy.unwrap_or_default(),
+        );
+        let lock = datastore.lock().await?;
+        let observers = loader.observers;
+        let mut transport = loader
             .transport
             .unwrap_or_else(|| Box::new(DefaultTransport::new()));
+        if let Some(rate_limit) = loader.rate_limit {
+            transport = Box::new(ThrottledTransport::new(transport, rate_limit));
+        }
+        if !observers.is_empty() {
+            transport.set_observer(Arc::new(observers.clone()));
+        }
         let limits = loader.limits.unwrap_or_default();
         let expiration_enforcement = loader.expiration_enforcement.unwrap_or_default();
-        let metadata_base_url = parse_url(loader.metadata_base_url)?;
-        let targets_base_url = parse_url(loader.targets_base_url)?;
+        let mirrors = Mirrors::new(
+            std::iter::once(
+                Mirror::new()
+                    .metadata_base_url(loader.metadata_base_url)
+                    .targets_base_url(loader.targets_base_url),
+            )
+            .chain(loader.mirrors)
+            .collect(),
+            observers.clone(),
+        )?;
 
         // 0. Load the trusted root metadata file + 1. Update the root metadata file
         let root = load_root(
@@ -344,7 +480,8 @@ impl Repository {
             &datastore,
             limits.max_root_size,
             limits.max_root_updates,
-            &metadata_base_url,
+            &mirrors,
+            &observers,
             expiration_enforcement,
         )
         .await?;
@@ -355,7 +492,8 @@ impl Repository {
             &root,
             &datastore,
             limits.max_timestamp_size,
-            &metadata_base_url,
+            &mirrors,
+            &observers,
             expiration_enforcement,
         )
         .await?;
@@ -367,7 +505,8 @@ impl Repository {
             &timestamp,
             limits.max_snapshot_size,
             &datastore,
-            &metadata_base_url,
+            &mirrors,
+            &observers,
             expiration_enforcement,
         )
         .await?;
@@ -379,37 +518,153 @@ impl Repository {
             &snapshot,
             &datastore,
             limits.max_targets_size,
-            &metadata_base_url,
+            &mirrors,
+            &observers,
             expiration_enforcement,
         )
         .await?;
 
-        let expires_iter = [
-            (root.signed.expires, RoleType::Root),
-            (timestamp.signed.expires, RoleType::Timestamp),
-            (snapshot.signed.expires, RoleType::Snapshot),
-            (targets.signed.expires, RoleType::Targets),
-        ];
         let (earliest_expiration, earliest_expiration_role) =
-            expires_iter.iter().min_by_key(|tup| tup.0).unwrap();
+            earliest_expiration(&root, &timestamp, &snapshot, &targets);
+        drop(lock);
 
         Ok(Self {
             transport,
             consistent_snapshot: root.signed.consistent_snapshot,
             datastore,
-            earliest_expiration: *earliest_expiration,
-            earliest_expiration_role: *earliest_expiration_role,
+            earliest_expiration,
+            earliest_expiration_role,
             root,
             snapshot,
             timestamp,
             targets,
+            delegated_roles: Arc::default(),
             limits,
-            metadata_base_url,
-            targets_base_url,
+            mirrors,
+            observers,
             expiration_enforcement,
         })
     }
 
+    /// Updates the repository metadata in place, starting from the currently trusted root,
+    /// timestamp, snapshot and targets metadata rather than the root that the repository was
+    /// loaded with.
+    ///
+    /// The timestamp metadata is always fetched. Snapshot and targets metadata are only fetched if
+    /// the versions listed in the new timestamp and snapshot metadata have changed (or if a new
+    /// root was found). The same signature, rollback and freeze attack checks are applied as in
+    /// [`RepositoryLoader::load`].
+    ///
+    /// If `Err` is returned, the repository is left unchanged.
+    pub async fn refresh(&mut self) -> Result<()> {
+        let lock = self.datastore.lock().await?;
+        let transport = self.transport.as_ref();
+
+        // 1. Update the root metadata file, starting from the currently trusted root.
+        let root = update_root(
+            transport,
+            self.root.clone(),
+            &self.datastore,
+            self.limits.max_root_size,
+            self.limits.max_root_updates,
+            &self.mirrors,
+            &self.observers,
+            self.expiration_enforcement,
+        )
+        .await?;
+        let root_changed = root.signed.version != self.root.signed.version;
+
+        // 2. Download the timestamp metadata file
+        let timestamp = load_timestamp(
+            transport,
+            &root,
+            &self.datastore,
+            self.limits.max_timestamp_size,
+            &self.mirrors,
+            &self.observers,
+            self.expiration_enforcement,
+        )
+        .await?;
+
+        // 3. Download the snapshot metadata file, unless the timestamp metadata still lists the
+        //    version we trust.
+        let snapshot_version = timestamp
+            .signed
+            .meta
+            .get("snapshot.json")
+            .map(|meta| meta.version);
+        let snapshot_changed =
+            root_changed || snapshot_version != Some(self.snapshot.signed.version);
+        let snapshot = if snapshot_changed {
+            load_snapshot(
+                transport,
+                &root,
+                &timestamp,
+                self.limits.max_snapshot_size,
+                &self.datastore,
+                &self.mirrors,
+                &self.observers,
+                self.expiration_enforcement,
+            )
+            .await?
+        } else {
+            if self.expiration_enforcement == ExpirationEnforcement::Safe {
+                check_expired(&self.datastore, &self.snapshot.signed).await?;
+            }
+            self.snapshot.clone()
+        };
+
+        // 4. Download the targets metadata file, unless the snapshot metadata still lists the
+        //    version we trust.
+        let targets_version = snapshot
+            .signed
+            .meta
+            .get("targets.json")
+            .map(|meta| meta.version);
+        let targets = if root_changed || targets_version != Some(self.targets.signed.version) {
+            load_targets(
+                transport,
+                &root,
+                &snapshot,
+                &self.datastore,
+                self.limits.max_targets_size,
+                &self.mirrors,
+                &self.observers,
+                self.expiration_enforcement,
+            )
+            .await?
+        } else {
+            if self.expiration_enforcement == ExpirationEnforcement::Safe {
+                check_expired(&self.datastore, &self.targets.signed).await?;
+            }
+            let mut targets = self.targets.clone();
+            // Delegated targets metadata may have changed along with the snapshot, so forget any
+            // roles that were already loaded. They will be loaded again on demand, and checked
+            // against the new snapshot metadata.
+            if snapshot_changed {
+                if let Some(delegations) = &mut targets.signed.delegations {
+                    for role in &mut delegations.roles {
+                        role.targets = None;
+                    }
+                }
+            }
+            targets
+        };
+
+        let (earliest_expiration, earliest_expiration_role) =
+            earliest_expiration(&root, &timestamp, &snapshot, &targets);
+        self.consistent_snapshot = root.signed.consistent_snapshot;
+        self.earliest_expiration = earliest_expiration;
+        self.earliest_expiration_role = earliest_expiration_role;
+        self.root = root;
+        self.timestamp = timestamp;
+        self.snapshot = snapshot;
+        self.targets = targets;
+        self.delegated_roles = Arc::default();
+        drop(lock);
+        Ok(())
+    }
+
     /// Returns the list of targets present in the repository.
     pub fn targets(&self) -> &Signed<crate::schema::Targets> {
         &self.targets
@@ -430,9 +685,50 @@ impl Repository {
         &self.timestamp
     }
 
-    ///return a vec of all targets including all target files delegated by targets
-    pub fn all_targets(&self) -> impl Iterator<Item = (&TargetName, &schema::Target)> + '_ {
-        self.targets.signed.targets_iter()
+    /// Returns an iterator of all targets, including all target files delegated by targets.
+    ///
+    /// Delegated targets metadata is otherwise only fetched when [`Repository::read_target`] or
+    /// [`Repository::save_target`] needs it, so this first loads every delegated targets role that
+    /// has not been loaded yet (see [`Repository::load_delegated_targets`]).
+    pub async fn all_targets(
+        &mut self,
+    ) -> Result<impl Iterator<Item = (&TargetName, &schema::Target)> + '_> {
+        self.load_delegated_targets().await?;
+        Ok(self.targets.signed.targets_iter())
+    }
+
+    /// Fetches and verifies every delegated targets role that has not been loaded yet, so that
+    /// [`Repository::targets`] and [`Repository::delegated_role`] reflect the entire delegation
+    /// tree.
+    ///
+    /// Delegated roles are cached in the datastore, so roles that were already fetched by an
+    /// earlier target lookup are not downloaded again.
+    pub async fn load_delegated_targets(&mut self) -> Result<()> {
+        self.targets = self.targets_with_delegations().await?;
+        Ok(())
+    }
+
+    /// Searches the repository metadata for the target `name`, returning `Ok(None)` if no role
+    /// lists it.
+    ///
+    /// This follows the preorder depth-first search described in TUF 5.6.7. Delegated
+    /// targets metadata is only fetched (or read from the datastore) for roles that the search
+    /// walks into, i.e. roles whose paths match `name`. The search stops at the first
+    /// terminating role whose paths match `name`, even if that role does not list the target.
+    pub async fn find_target(&self, name: &TargetName) -> Result<Option<Target>> {
+        if let Some(target) = self.targets.signed.targets.get(name) {
+            return Ok(Some(target.clone()));
+        }
+        match &self.targets.signed.delegations {
+            Some(delegations) => match self
+                .find_delegated_target("targets", delegations, name, &mut HashSet::new())
+                .await?
+            {
+                ControlFlow::Break(target) => Ok(target),
+                ControlFlow::Continue(()) => Ok(None),
+            },
+            None => Ok(None),
+        }
     }
 
     /// Fetches a target from the repository.
@@ -451,15 +747,7 @@ impl Repository {
         name: &TargetName,
     ) -> Result<Option<impl Stream<Item = error::Result<Bytes>> + IntoVec<error::Error> + Send>>
     {
-        // Check for repository metadata expiration.
-        if self.expiration_enforcement == ExpirationEnforcement::Safe {
-            ensure!(
-                self.datastore.system_time().await? < self.earliest_expiration,
-                error::ExpiredMetadataSnafu {
-                    role: self.earliest_expiration_role
-                }
-            );
-        }
+        self.ensure_not_expired().await?;
 
         // 5. Verify the desired target against its targets metadata.
         //
@@ -478,9 +766,14 @@ impl Repository {
         //   HASH is one of the hashes of the targets file listed in the targets metadata file
         //   found earlier in step 4. In either case, the client MUST write the file to
         //   non-volatile storage as FILENAME.EXT.
-        Ok(if let Ok(target) = self.targets.signed.find_target(name) {
-            let (sha256, file) = self.target_digest_and_filename(target, name);
-            Some(self.fetch_target(target, &sha256, file.as_str()).await?)
+        Ok(if let Some(target) = self.find_target(name).await? {
+            let filenames = self.target_filenames(&target, name);
+            let digests = Digests::new(&target.hashes, name.raw())?;
+            Some(
+                self.fetch_target(name, &target, &filenames, &digests, 0)
+                    .await?
+                    .0,
+            )
         } else {
             None
         })
@@ -504,6 +797,113 @@ impl Repository {
     ///   outside of a delegated target's correct path of delegation.
     ///
     pub async fn save_target<P>(&self, name: &TargetName, outdir: P, prepend: Prefix) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let (target, resolved_filepath) = self.target_filepath(name, outdir, prepend).await?;
+        let filepath_dir = resolved_filepath
+            .parent()
+            .unwrap_or_else(|| unreachable!("checked by target_filepath"));
+
+        // Create a new temporary file for an atomic file creation.
+        let tmp_path = filepath_dir.to_owned();
+        let tmp = tokio::task::spawn_blocking(move || NamedTempFile::new_in(tmp_path))
+            .await
+            // We do not cancel the task nor do we expect it to panic
+            .unwrap_or_else(|_| unreachable!())
+            .context(error::NamedTempFileCreateSnafu { path: filepath_dir })?;
+
+        // Convert to `tokio::fs::File`.
+        let (f, tmp_path) = tmp.into_parts();
+        let mut f = tokio::fs::File::from_std(f);
+
+        // Fetch and write the target.
+        let digests = Digests::new(&target.hashes, name.raw())?;
+        self.write_target(name, &target, &mut f, &tmp_path, digests)
+            .await?;
+
+        // Reconstruct `NamedTempFile` in order to persist it at the target location.
+        let f = NamedTempFile::from_parts(f.into_std().await, tmp_path);
+        f.persist(&resolved_filepath)
+            .context(error::NamedTempFilePersistSnafu {
+                path: resolved_filepath,
+            })?;
+
+        Ok(())
+    }
+
+    /// Fetches a target from the repository and saves it to `outdir` like
+    /// [`Repository::save_target`], but keeps the downloaded bytes if the download is interrupted,
+    /// even by the process exiting.
+    ///
+    /// While downloading, the bytes are written to `<filename>.part` next to the destination, and
+    /// the target's name, length and hashes are recorded in `<filename>.part.json`. If these files
+    /// are left by an earlier call for the same target, the bytes they hold are hashed again and
+    /// only the rest of the target is fetched, using [`Transport::fetch_range`]. If the completed
+    /// file does not match the target's hashes, it is downloaded again from the start.
+    pub async fn save_target_resumable<P>(
+        &self,
+        name: &TargetName,
+        outdir: P,
+        prepend: Prefix,
+    ) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let (target, resolved_filepath) = self.target_filepath(name, outdir, prepend).await?;
+        let partial = PartialTarget::new(&resolved_filepath);
+        let (mut f, digests) = partial.open(name, &target).await?;
+
+        if let Err(e) = self
+            .write_target(name, &target, &mut f, partial.path(), digests)
+            .await
+        {
+            // A complete download that failed verification cannot be resumed.
+            if f.metadata().await.is_ok_and(|m| m.len() >= target.length) {
+                partial.remove().await?;
+            }
+            return Err(e);
+        }
+        f.sync_all().await.context(error::FileWriteSnafu {
+            path: partial.path(),
+        })?;
+        drop(f);
+        partial.persist(&resolved_filepath).await
+    }
+
+    /// Fetches the targets `names` and saves them to `outdir` like [`Repository::save_target`],
+    /// downloading and verifying up to `jobs` targets at once.
+    ///
+    /// A failure to save one target does not stop the others from being saved. The result for each
+    /// target is returned in the same order as `names`.
+    pub async fn save_targets<P>(
+        &self,
+        names: &[TargetName],
+        outdir: P,
+        prepend: Prefix,
+        jobs: NonZeroUsize,
+    ) -> Vec<(TargetName, Result<()>)>
+    where
+        P: AsRef<Path>,
+    {
+        let outdir = outdir.as_ref();
+        futures::stream::iter(names)
+            .map(
+                |name| async move { (name.clone(), self.save_target(name, outdir, prepend).await) },
+            )
+            .buffered(jobs.get())
+            .collect()
+            .await
+    }
+
+    /// Finds the target `name` and returns it, along with the path in `outdir` that it should be
+    /// saved at. Creates the directories leading to that path.
+    async fn target_filepath<P>(
+        &self,
+        name: &TargetName,
+        outdir: P,
+        prepend: Prefix,
+    ) -> Result<(Target, PathBuf)>
     where
         P: AsRef<Path>,
     {
@@ -528,18 +928,23 @@ impl Repository {
             );
         }
 
-        let filename = match prepend {
-            Prefix::Digest => {
-                let target = self.targets.signed.find_target(name).with_context(|_| {
-                    error::CacheTargetMissingSnafu {
-                        target_name: name.clone(),
-                    }
-                })?;
-                let sha256 = target.hashes.sha256.clone().into_vec();
-                format!("{}.{}", hex::encode(sha256), name.resolved())
-            }
-            Prefix::None => name.resolved().to_owned(),
-        };
+        self.ensure_not_expired().await?;
+        let target = self
+            .find_target(name)
+            .await?
+            .with_context(|| error::SaveTargetNotFoundSnafu { name: name.clone() })?;
+        let filename =
+            match prepend {
+                Prefix::Digest => {
+                    let (_, digest) = target.hashes.iter().next().with_context(|| {
+                        error::NoSupportedHashSnafu {
+                            context: name.raw(),
+                        }
+                    })?;
+                    format!("{}.{}", hex::encode(digest), name.resolved())
+                }
+                Prefix::None => name.resolved().to_owned(),
+            };
 
         let resolved_filepath = outdir.join(filename);
 
@@ -562,50 +967,355 @@ impl Repository {
             }
         );
 
-        // Fetch and write the target using NamedTempFile for an atomic file creation.
-        let mut stream = self
-            .read_target(name)
-            .await?
-            .with_context(|| error::SaveTargetNotFoundSnafu { name: name.clone() })?;
         create_dir_all(filepath_dir)
             .await
             .context(error::DirCreateSnafu {
                 path: &filepath_dir,
             })?;
+        Ok((target, resolved_filepath))
+    }
 
-        // Create a new temporary file.
-        let tmp_path = filepath_dir.to_owned();
-        let tmp = tokio::task::spawn_blocking(move || NamedTempFile::new_in(tmp_path))
-            .await
-            // We do not cancel the task nor do we expect it to panic
-            .unwrap_or_else(|_| unreachable!())
-            .context(error::NamedTempFileCreateSnafu { path: filepath_dir })?;
-
-        // Convert to `tokio::fs::File`.
-        let (f, tmp_path) = tmp.into_parts();
-        let mut f = tokio::fs::File::from_std(f);
-
-        // Write input stream to file.
-        while let Some(bytes) = stream.next().await {
-            f.write_all(bytes?.as_ref())
+    /// Fetches the target `name` and writes it to `file` at `path`, which already holds the
+    /// leading bytes of the target that `digests` were computed over.
+    ///
+    /// If the stream fails in a way that another mirror might not (such as a hash mismatch), the
+    /// target is fetched in full from the next mirror, and `file` is emptied once that mirror
+    /// responds. If a resumed download was completed but does not match the target's hashes, the
+    /// same mirror is tried again first.
+    async fn write_target(
+        &self,
+        name: &TargetName,
+        target: &Target,
+        file: &mut tokio::fs::File,
+        path: &Path,
+        mut digests: Digests,
+    ) -> Result<()> {
+        if digests.len() == target.length && digests.check(name.raw()).is_ok() {
+            return Ok(());
+        }
+        let filenames = self.target_filenames(target, name);
+        let mut skip = 0;
+        let mut last_error = None;
+        loop {
+            let start = digests.len();
+            let (mut stream, next_mirror) = match self
+                .fetch_target(name, target, &filenames, &digests, skip)
+                .await
+            {
+                Ok(fetched) => fetched,
+                // Report why the last mirror failed, rather than that no mirrors are left.
+                Err(e @ error::Error::NoMirror { .. }) => return Err(last_error.unwrap_or(e)),
+                Err(e) => return Err(e),
+            };
+            if last_error.is_some() {
+                file.set_len(0)
+                    .await
+                    .context(error::FileWriteSnafu { path })?;
+                file.seek(SeekFrom::Start(0))
+                    .await
+                    .context(error::FileWriteSnafu { path })?;
+            }
+            match write_stream(file, &mut stream, path).await {
+                Ok(()) => return Ok(()),
+                Err(e) if mirror::is_retryable(&e) => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+            let len = file
+                .metadata()
                 .await
-                .context(error::FileWriteSnafu { path: &tmp_path })?;
+                .context(error::FileMetadataSnafu { path })?
+                .len();
+            skip = if start > 0 && len >= target.length {
+                next_mirror - 1
+            } else {
+                next_mirror
+            };
+            digests = Digests::new(&target.hashes, name.raw())?;
         }
+    }
 
-        // Reconstruct `NamedTempFile` in order to persist it at the target location.
-        let f = NamedTempFile::from_parts(f.into_std().await, tmp_path);
-        f.persist(&resolved_filepath)
-            .context(error::NamedTempFilePersistSnafu {
-                path: resolved_filepath,
-            })?;
+    /// Returns the base URL of the mirror that most recently served the metadata file at `path`,
+    /// e.g. `timestamp.json` or `3.snapshot.json`, or `None` if it has not been fetched.
+    pub fn metadata_mirror(&self, path: &str) -> Option<Url> {
+        self.mirrors.metadata_mirror(path)
+    }
 
-        Ok(())
+    /// Returns the base URL of the mirror that most recently served the target `name`, or `None`
+    /// if it has not been fetched.
+    pub fn target_mirror(&self, name: &TargetName) -> Option<Url> {
+        self.mirrors.target_mirror(name)
     }
 
     /// Return the named `DelegatedRole` if found.
+    ///
+    /// Only roles listed by targets metadata that has already been loaded are found. Use
+    /// [`Repository::load_delegated_targets`] to load the entire delegation tree first.
     pub fn delegated_role(&self, name: &str) -> Option<&DelegatedRole> {
         self.targets.signed.delegated_role(name).ok()
     }
+
+    /// Returns an error if the repository metadata is expired and expirations are enforced.
+    async fn ensure_not_expired(&self) -> Result<()> {
+        if self.expiration_enforcement == ExpirationEnforcement::Safe {
+            ensure!(
+                self.datastore.system_time().await? < self.earliest_expiration,
+                error::ExpiredMetadataSnafu {
+                    role: self.earliest_expiration_role
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the top-level targets metadata with every delegated targets role loaded.
+    pub(crate) async fn targets_with_delegations(&self) -> Result<Signed<schema::Targets>> {
+        let mut targets = self.targets.clone();
+        if let Some(delegations) = &mut targets.signed.delegations {
+            self.load_delegations("targets", delegations).await?;
+        }
+
+        // This validation can only be done from the top level targets.json role. This check
+        // verifies that each target's delegate hierarchy is a match (i.e. it's delegate ownership
+        // is valid).
+        targets.signed.validate().context(error::InvalidPathSnafu)?;
+        Ok(targets)
+    }
+
+    /// Loads every role in `delegations` that has not been loaded yet, then follows the
+    /// delegations of each of those roles.
+    #[async_recursion]
+    async fn load_delegations(&self, delegator: &str, delegations: &mut Delegations) -> Result<()> {
+        for index in 0..delegations.roles.len() {
+            if delegations.roles[index].targets.is_none() {
+                let name = delegations.roles[index].name.clone();
+                let role = self
+                    .delegated_targets(delegator, delegations, &name)
+                    .await?;
+                delegations.roles[index].targets = Some(Signed::clone(&role));
+            }
+        }
+        for delegated_role in &mut delegations.roles {
+            if let Some(targets) = &mut delegated_role.targets {
+                if let Some(delegations) = &mut targets.signed.delegations {
+                    self.load_delegations(&delegated_role.name, delegations)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// TUF 5.6.7. Perform a preorder depth-first search for metadata about the desired
+    /// target among the roles in `delegations`. A role's metadata is loaded only when the search
+    /// reaches it.
+    ///
+    /// `delegator` is the name of the role that `delegations` belongs to.
+    ///
+    /// Returns `ControlFlow::Break` when the search is over, either because the target was found
+    /// or because a terminating role matched it, and `ControlFlow::Continue` when the caller
+    /// should go on to the next sibling role.
+    #[async_recursion]
+    async fn find_delegated_target(
+        &self,
+        delegator: &str,
+        delegations: &Delegations,
+        name: &TargetName,
+        visited: &mut HashSet<String>,
+    ) -> Result<ControlFlow<Option<Target>>> {
+        for delegated_role in &delegations.roles {
+            // If the target cannot match this DelegatedRole, then we do not want to load it or
+            // check any of its child roles either. Since we only descend into roles that match,
+            // the target is permitted by every role in the delegation chain.
+            if !delegated_role.paths.matches_target_name(name) {
+                continue;
+            }
+
+            // 5.6.7.1. If this role has been visited before, then skip this role (so that cycles
+            //   in the delegation graph are avoided).
+            if !visited.insert(delegated_role.name.clone()) {
+                continue;
+            }
+
+            let loaded;
+            let targets = if let Some(targets) = &delegated_role.targets {
+                targets
+            } else {
+                loaded = self
+                    .delegated_targets(delegator, delegations, &delegated_role.name)
+                    .await?;
+                loaded.as_ref()
+            };
+            if let Some(target) = targets.signed.targets.get(name) {
+                return Ok(ControlFlow::Break(Some(target.clone())));
+            }
+            if let Some(child_delegations) = &targets.signed.delegations {
+                if let ControlFlow::Break(target) = self
+                    .find_delegated_target(&delegated_role.name, child_delegations, name, visited)
+                    .await?
+                {
+                    return Ok(ControlFlow::Break(target));
+                }
+            }
+
+            // 5.6.7.2.1. If the current delegation is a terminating delegation, then jump to the
+            //   end of the search; no subsequent delegations are considered.
+            if delegated_role.terminating {
+                return Ok(ControlFlow::Break(None));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Returns the delegated targets role `name` from `delegator`'s `delegations`, loading it with
+    /// [`Repository::load_delegated_role`] the first time it is needed.
+    async fn delegated_targets(
+        &self,
+        delegator: &str,
+        delegations: &Delegations,
+        name: &str,
+    ) -> Result<Arc<Signed<schema::Targets>>> {
+        let cell = Arc::clone(
+            self.delegated_roles
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry((delegator.to_owned(), name.to_owned()))
+                .or_default(),
+        );
+        cell.get_or_try_init(|| async {
+            Ok(Arc::new(self.load_delegated_role(delegations, name).await?))
+        })
+        .await
+        .cloned()
+    }
+
+    /// Loads the delegated targets role `name` from
+    /// `delegations`. A copy in the datastore is used if it is still listed in snapshot metadata,
+    /// otherwise the role is fetched from the repository and written to the datastore.
+    async fn load_delegated_role(
+        &self,
+        delegations: &Delegations,
+        name: &str,
+    ) -> Result<Signed<schema::Targets>> {
+        // find the role file metadata
+        let role_meta = self
+            .snapshot
+            .signed
+            .meta
+            .get(&format!("{name}.json"))
+            .with_context(|| error::RoleNotInMetaSnafu { name })?;
+        let filename = format!("{}.json", encode_filename(name));
+        let lock = self.datastore.lock().await?;
+
+        // A previously fetched copy of the role is only trusted if it still verifies against the
+        // delegating role's keys.
+        let old_data = self.datastore.bytes(&filename).await?;
+        let old_role = old_data
+            .as_deref()
+            .and_then(|b| serde_json::from_slice::<Signed<schema::Targets>>(b).ok())
+            .filter(|old_role| delegations.verify_role(old_role, name).is_ok());
+        if let (Some(old_role), Some(old_data)) = (old_role.as_ref(), old_data.as_deref()) {
+            // The copy is only used in place of a fetch if it matches the length and hashes
+            // listed in snapshot metadata, just like a fetched copy.
+            let matches_meta = role_meta
+                .length
+                .is_none_or(|length| length == old_data.len() as u64)
+                && role_meta.hashes.as_ref().is_none_or(|hashes| {
+                    Digests::new(hashes, &filename).is_ok_and(|mut digests| {
+                        digests.update(old_data);
+                        digests.check(&filename).is_ok()
+                    })
+                });
+            if old_role.signed.version == role_meta.version && matches_meta {
+                if self.expiration_enforcement == ExpirationEnforcement::Safe {
+                    check_expired(&self.datastore, &old_role.signed).await?;
+                }
+                return Ok(old_role.clone());
+            }
+        }
+
+        // Download the delegated targets metadata file the same way as the top-level targets
+        // metadata file (step 4). If consistent snapshots are used, the filename is of the form
+        // VERSION_NUMBER.ROLENAME.json.
+        let path = if self.consistent_snapshot {
+            format!("{}.{}", role_meta.version, filename)
+        } else {
+            filename.clone()
+        };
+        let (max_targets_size, specifier) = match role_meta.length {
+            Some(length) => (length, "snapshot.json"),
+            None => (self.limits.max_targets_size, "max_targets_size parameter"),
+        };
+        let data = self
+            .mirrors
+            .fetch_metadata(
+                self.transport.as_ref(),
+                &path,
+                max_targets_size,
+                specifier,
+                role_meta.hashes.as_ref(),
+            )
+            .await?;
+        // since each role is a targets, we load them as such
+        let role: Signed<schema::Targets> =
+            serde_json::from_slice(&data).context(error::ParseMetadataSnafu {
+                role: RoleType::Targets,
+            })?;
+
+        // Check against snapshot metadata. The version number of the new delegated targets
+        // metadata file MUST match the version listed in the trusted snapshot metadata.
+        //
+        // (We already checked the hash while fetching it above.)
+        ensure!(
+            role.signed.version == role_meta.version,
+            error::VersionMismatchSnafu {
+                role: RoleType::Targets,
+                fetched: role.signed.version,
+                expected: role_meta.version
+            }
+        );
+
+        // Check for an arbitrary software attack. The new delegated targets metadata file MUST
+        // have been signed by a threshold of keys specified in the delegating role.
+        delegations
+            .verify_role(&role, name)
+            .context(error::VerifyMetadataSnafu {
+                role: RoleType::Targets,
+            })
+            .map_err(|e| self.observers.report(&path, e))?;
+
+        // Check for a rollback attack. The version number of the trusted delegated targets
+        // metadata file, if any, MUST be less than or equal to the version number of the new
+        // delegated targets metadata file.
+        if let Some(old_role) = old_role {
+            ensure!(
+                old_role.signed.version <= role.signed.version,
+                error::OlderMetadataSnafu {
+                    role: RoleType::DelegatedTargets,
+                    current_version: old_role.signed.version,
+                    new_version: role.signed.version
+                }
+            );
+        }
+
+        // Check for a freeze attack. The expiration timestamp in the new delegated targets
+        // metadata file MUST be higher than the fixed update start time.
+        if self.expiration_enforcement == ExpirationEnforcement::Safe {
+            check_expired(&self.datastore, &role.signed).await?;
+        }
+
+        // Now that everything seems okay, write the role file to the datastore. The fetched bytes
+        // are kept as they are, so that they can be checked against snapshot metadata later.
+        self.datastore.write(&filename, &data).await?;
+        drop(lock);
+        self.observers.notify(&Event::RoleVerified {
+            role: RoleType::DelegatedTargets,
+            name,
+            version: role.signed.version,
+            size: data.len() as u64,
+        });
+
+        Ok(role)
+    }
 }
 
 /// The set of characters that will be escaped when converting a delegated role name into a
@@ -631,9 +1341,22 @@ pub(crate) fn encode_filename<S: AsRef<str>>(name: S) -> String {
     utf8_percent_encode(name.as_ref(), &CHARACTERS_TO_ESCAPE).to_string()
 }
 
+/// Writes every chunk of `stream` to `file`.
+async fn write_stream<S>(file: &mut tokio::fs::File, stream: &mut S, path: &Path) -> Result<()>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    while let Some(bytes) = stream.next().await {
+        file.write_all(bytes?.as_ref())
+            .await
+            .context(error::FileWriteSnafu { path })?;
+    }
+    Ok(())
+}
+
 /// TUF v1.0.16, 5.2.9, 5.3.3, 5.4.5, 5.5.4, The expiration timestamp in the `[metadata]` file MUST
 /// be higher than the fixed update start time.
-async fn check_expired<T: Role>(datastore: &Datastore, role: &T) -> Result<()> {
+async fn check_expired<T: Role>(datastore: &Store, role: &T) -> Result<()> {
     ensure!(
         datastore.system_time().await? <= role.expires(),
         error::ExpiredMetadataSnafu { role: T::TYPE }
@@ -641,6 +1364,25 @@ async fn check_expired<T: Role>(datastore: &Datastore, role: &T) -> Result<()> {
     Ok(())
 }
 
+/// Returns the earliest expiration among the top-level roles, and the role it belongs to.
+fn earliest_expiration(
+    root: &Signed<Root>,
+    timestamp: &Signed<Timestamp>,
+    snapshot: &Signed<Snapshot>,
+    targets: &Signed<crate::schema::Targets>,
+) -> (DateTime<Utc>, RoleType) {
+    [
+        (root.signed.expires, RoleType::Root),
+        (timestamp.signed.expires, RoleType::Timestamp),
+        (snapshot.signed.expires, RoleType::Snapshot),
+        (targets.signed.expires, RoleType::Targets),
+    ]
+    .iter()
+    .copied()
+    .min_by_key(|tup| tup.0)
+    .unwrap()
+}
+
 /// Checks to see if the `Url` has a trailing slash and adds one if not. Without a trailing slash,
 /// the last component of a `Url` is considered to be a file. `metadata_url` and `targets_url`
 /// must refer to a base (i.e. directory), so we need them to end with a slash.
@@ -656,25 +1398,53 @@ fn parse_url(url: Url) -> Result<Url> {
 
 /// Steps 0 and 1 of the client application, which load the current root metadata file based on a
 /// trusted root metadata file.
+#[allow(clippy::too_many_arguments)]
 async fn load_root<R: AsRef<[u8]>>(
     transport: &dyn Transport,
     root: R,
-    datastore: &Datastore,
+    datastore: &Store,
     max_root_size: u64,
     max_root_updates: u64,
-    metadata_base_url: &Url,
+    mirrors: &Mirrors,
+    observers: &Observers,
     expiration_enforcement: ExpirationEnforcement,
 ) -> Result<Signed<Root>> {
     // 0. Load the trusted root metadata file. We assume that a good, trusted copy of this file was
     //    shipped with the package manager or software updater using an out-of-band process. Note
     //    that the expiration of the trusted root metadata file does not matter, because we will
     //    attempt to update it in the next step.
-    let mut root: Signed<Root> =
+    let root: Signed<Root> =
         serde_json::from_slice(root.as_ref()).context(error::ParseTrustedMetadataSnafu)?;
     root.signed
         .verify_role(&root)
         .context(error::VerifyTrustedMetadataSnafu)?;
 
+    update_root(
+        transport,
+        root,
+        datastore,
+        max_root_size,
+        max_root_updates,
+        mirrors,
+        observers,
+        expiration_enforcement,
+    )
+    .await
+}
+
+/// Step 1 of the client application, which updates a trusted root metadata file to the latest
+/// root metadata file in the repository.
+#[allow(clippy::too_many_arguments)]
+async fn update_root(
+    transport: &dyn Transport,
+    mut root: Signed<Root>,
+    datastore: &Store,
+    max_root_size: u64,
+    max_root_updates: u64,
+    mirrors: &Mirrors,
+    observers: &Observers,
+    expiration_enforcement: ExpirationEnforcement,
+) -> Result<Signed<Root>> {
     // Used in step 1.2
     let original_root_version = root.signed.version.get();
 
@@ -711,27 +1481,23 @@ async fn load_root<R: AsRef<[u8]>>(
             error::MaxUpdatesExceededSnafu { max_root_updates }
         );
         let path = format!("{}.root.json", root.signed.version.get() + 1);
-        let url = metadata_base_url
-            .join(&path)
-            .with_context(|_| error::JoinUrlSnafu {
-                path: path.clone(),
-                url: metadata_base_url.clone(),
-            })?;
-        match fetch_max_size(
-            transport,
-            url.clone(),
-            max_root_size,
-            "max_root_size argument",
-        )
-        .await
+        match mirrors
+            .fetch_metadata(
+                transport,
+                &path,
+                max_root_size,
+                "max_root_size argument",
+                None,
+            )
+            .await
         {
+            // If the last mirror failed to serve a file that exists, report the failure.
+            Err(e) if mirror::transport_error_kind(&e) == Some(TransportErrorKind::Other) => {
+                return Err(e)
+            }
             Err(_) => break, // If this file is not available, then go to step 1.8.
-            Ok(stream) => {
-                let data = match stream.into_vec().await {
-                    Ok(d) => d,
-                    Err(e) if e.kind() == TransportErrorKind::FileNotFound => break,
-                    err @ Err(_) => err.context(error::TransportSnafu { url })?,
-                };
+            Ok(data) => {
+                let report = |e| observers.report(&path, e);
                 let new_root: Signed<Root> =
                     serde_json::from_slice(&data).context(error::ParseMetadataSnafu {
                         role: RoleType::Root,
@@ -747,13 +1513,15 @@ async fn load_root<R: AsRef<[u8]>>(
                     .verify_role(&new_root)
                     .context(error::VerifyMetadataSnafu {
                         role: RoleType::Root,
-                    })?;
+                    })
+                    .map_err(report)?;
                 new_root
                     .signed
                     .verify_role(&new_root)
                     .context(error::VerifyMetadataSnafu {
                         role: RoleType::Root,
-                    })?;
+                    })
+                    .map_err(report)?;
 
                 // 1.4. Check for a rollback attack. The version number of the trusted root
                 //   metadata file (version N) must be less than or equal to the version number of
@@ -788,6 +1556,16 @@ async fn load_root<R: AsRef<[u8]>>(
                 // 1.6. Set the trusted root metadata file to the new root metadata file.
                 //
                 // (This is where version N+1 becomes version N.)
+                observers.notify(&Event::RoleVerified {
+                    role: RoleType::Root,
+                    name: "root",
+                    version: new_root.signed.version,
+                    size: data.len() as u64,
+                });
+                observers.notify(&Event::RootRotated {
+                    from: root.signed.version,
+                    to: new_root.signed.version,
+                });
                 root = new_root;
 
                 // 1.7. Repeat steps 1.1 to 1.7.
@@ -835,33 +1613,25 @@ async fn load_root<R: AsRef<[u8]>>(
 async fn load_timestamp(
     transport: &dyn Transport,
     root: &Signed<Root>,
-    datastore: &Datastore,
+    datastore: &Store,
     max_timestamp_size: u64,
-    metadata_base_url: &Url,
+    mirrors: &Mirrors,
+    observers: &Observers,
     expiration_enforcement: ExpirationEnforcement,
 ) -> Result<Signed<Timestamp>> {
     // 2. Download the timestamp metadata file, up to Y number of bytes (because the size is
     //    unknown.) The value for Y is set by the authors of the application using TUF. For
     //    example, Y may be tens of kilobytes. The filename used to download the timestamp metadata
     //    file is of the fixed form FILENAME.EXT (e.g., timestamp.json).
-    let path = "timestamp.json";
-    let url = metadata_base_url
-        .join(path)
-        .with_context(|_| error::JoinUrlSnafu {
-            path,
-            url: metadata_base_url.clone(),
-        })?;
-    let stream = fetch_max_size(
-        transport,
-        url.clone(),
-        max_timestamp_size,
-        "max_timestamp_size argument",
-    )
-    .await?;
-    let data = stream
-        .into_vec()
-        .await
-        .context(error::TransportSnafu { url })?;
+    let data = mirrors
+        .fetch_metadata(
+            transport,
+            "timestamp.json",
+            max_timestamp_size,
+            "max_timestamp_size argument",
+            None,
+        )
+        .await?;
     let timestamp: Signed<Timestamp> =
         serde_json::from_slice(&data).context(error::ParseMetadataSnafu {
             role: RoleType::Timestamp,
@@ -874,7 +1644,8 @@ async fn load_timestamp(
         .verify_role(&timestamp)
         .context(error::VerifyMetadataSnafu {
             role: RoleType::Timestamp,
-        })?;
+        })
+        .map_err(|e| observers.report("timestamp.json", e))?;
 
     // 2.2. Check for a rollback attack. The version number of the trusted timestamp metadata file,
     //   if any, must be less than or equal to the version number of the new timestamp metadata
@@ -897,6 +1668,11 @@ async fn load_timestamp(
         }
     }
 
+    // The signature is valid, so the timestamp metadata bounds how early the current time can be.
+    datastore
+        .observe_timestamp(timestamp.signed.expires)
+        .await?;
+
     // TUF v1.0.16, 5.3.3. Check for a freeze attack. The expiration timestamp in the new timestamp
     // metadata file MUST be higher than the fixed update start time. If so, the new timestamp
     // metadata file becomes the trusted timestamp metadata file. If the new timestamp metadata file
@@ -907,19 +1683,26 @@ async fn load_timestamp(
 
     // Now that everything seems okay, write the timestamp file to the datastore.
     datastore.create("timestamp.json", &timestamp).await?;
+    observers.notify(&Event::RoleVerified {
+        role: RoleType::Timestamp,
+        name: "timestamp",
+        version: timestamp.signed.version,
+        size: data.len() as u64,
+    });
 
     Ok(timestamp)
 }
 
 /// Step 3 of the client application, which loads the snapshot metadata file.
-#[allow(clippy::too_many_lines)]
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
 async fn load_snapshot(
     transport: &dyn Transport,
     root: &Signed<Root>,
     timestamp: &Signed<Timestamp>,
     max_snapshot_size: u64,
-    datastore: &Datastore,
-    metadata_base_url: &Url,
+    datastore: &Store,
+    mirrors: &Mirrors,
+    observers: &Observers,
     expiration_enforcement: ExpirationEnforcement,
 ) -> Result<Signed<Snapshot>> {
     // 3. Download snapshot metadata file, up to the number of bytes specified in the timestamp
@@ -943,35 +1726,15 @@ async fn load_snapshot(
     } else {
         "snapshot.json".to_owned()
     };
-    let url = metadata_base_url
-        .join(&path)
-        .with_context(|_| error::JoinUrlSnafu {
-            path: path.clone(),
-            url: metadata_base_url.clone(),
-        })?;
-    let stream = if let Some(hashes) = &snapshot_meta.hashes {
-        fetch_sha256(
-            transport,
-            url.clone(),
-            snapshot_meta.length.unwrap_or(max_snapshot_size),
-            "timestamp.json",
-            &hashes.sha256,
-        )
-        .await?
-    } else {
-        fetch_max_size(
+    let data = mirrors
+        .fetch_metadata(
             transport,
-            url.clone(),
+            &path,
             snapshot_meta.length.unwrap_or(max_snapshot_size),
             "timestamp.json",
+            snapshot_meta.hashes.as_ref(),
         )
-        .await?
-    };
-
-    let data = stream
-        .into_vec()
-        .await
-        .context(error::TransportSnafu { url })?;
+        .await?;
     let snapshot: Signed<Snapshot> =
         serde_json::from_slice(&data).context(error::ParseMetadataSnafu {
             role: RoleType::Snapshot,
@@ -982,7 +1745,7 @@ async fn load_snapshot(
     //   hashes and version do not match, discard the new snapshot metadata, abort the update
     //   cycle, and report the failure.
     //
-    // (We already checked the hash in `fetch_sha256` above.)
+    // (We already checked the hash while fetching it above.)
     ensure!(
         snapshot.signed.version == snapshot_meta.version,
         error::VersionMismatchSnafu {
@@ -1000,7 +1763,8 @@ async fn load_snapshot(
         .verify_role(&snapshot)
         .context(error::VerifyMetadataSnafu {
             role: RoleType::Snapshot,
-        })?;
+        })
+        .map_err(|e| observers.report(&path, e))?;
 
     // 3.3. Check for a rollback attack.
     //
@@ -1064,18 +1828,26 @@ async fn load_snapshot(
 
     // Now that everything seems okay, write the snapshot file to the datastore.
     datastore.create("snapshot.json", &snapshot).await?;
+    observers.notify(&Event::RoleVerified {
+        role: RoleType::Snapshot,
+        name: "snapshot",
+        version: snapshot.signed.version,
+        size: data.len() as u64,
+    });
 
     Ok(snapshot)
 }
 
 /// Step 4 of the client application, which loads the targets metadata file.
+#[allow(clippy::too_many_arguments)]
 async fn load_targets(
     transport: &dyn Transport,
     root: &Signed<Root>,
     snapshot: &Signed<Snapshot>,
-    datastore: &Datastore,
+    datastore: &Store,
     max_targets_size: u64,
-    metadata_base_url: &Url,
+    mirrors: &Mirrors,
+    observers: &Observers,
     expiration_enforcement: ExpirationEnforcement,
 ) -> Result<Signed<crate::schema::Targets>> {
     // 4. Download the top-level targets metadata file, up to either the number of bytes specified
@@ -1101,33 +1873,20 @@ async fn load_targets(
     } else {
         "targets.json".to_owned()
     };
-    let targets_url = metadata_base_url
-        .join(&path)
-        .with_context(|_| error::JoinUrlSnafu {
-            path,
-            url: metadata_base_url.clone(),
-        })?;
     let (max_targets_size, specifier) = match targets_meta.length {
         Some(length) => (length, "snapshot.json"),
         None => (max_targets_size, "max_targets_size parameter"),
     };
-    let stream = if let Some(hashes) = &targets_meta.hashes {
-        fetch_sha256(
+    let data = mirrors
+        .fetch_metadata(
             transport,
-            targets_url.clone(),
+            &path,
             max_targets_size,
             specifier,
-            &hashes.sha256,
+            targets_meta.hashes.as_ref(),
         )
-        .await?
-    } else {
-        fetch_max_size(transport, targets_url.clone(), max_targets_size, specifier).await?
-    };
-    let data = stream
-        .into_vec()
-        .await
-        .context(error::TransportSnafu { url: targets_url })?;
-    let mut targets: Signed<crate::schema::Targets> =
+        .await?;
+    let targets: Signed<crate::schema::Targets> =
         serde_json::from_slice(&data).context(error::ParseMetadataSnafu {
             role: RoleType::Targets,
         })?;
@@ -1137,7 +1896,7 @@ async fn load_targets(
     //   prevent a mix-and-match attack by man-in-the-middle attackers. If the new targets metadata
     //   file does not match, discard it, abort the update cycle, and report the failure.
     //
-    // (We already checked the hash in `fetch_sha256` above.)
+    // (We already checked the hash while fetching it above.)
     ensure!(
         targets.signed.version == targets_meta.version,
         error::VersionMismatchSnafu {
@@ -1155,7 +1914,8 @@ async fn load_targets(
         .verify_role(&targets)
         .context(error::VerifyMetadataSnafu {
             role: RoleType::Targets,
-        })?;
+        })
+        .map_err(|e| observers.report(&path, e))?;
 
     // 4.3. Check for a rollback attack. The version number of the trusted targets metadata file,
     //   if any, MUST be less than or equal to the version number of the new targets metadata file.
@@ -1188,123 +1948,20 @@ async fn load_targets(
 
     // Now that everything seems okay, write the targets file to the datastore.
     datastore.create("targets.json", &targets).await?;
+    observers.notify(&Event::RoleVerified {
+        role: RoleType::Targets,
+        name: "targets",
+        version: targets.signed.version,
+        size: data.len() as u64,
+    });
 
     // 4.5. Perform a preorder depth-first search for metadata about the desired target, beginning
     //   with the top-level targets role.
-    if let Some(delegations) = &mut targets.signed.delegations {
-        load_delegations(
-            transport,
-            snapshot,
-            root.signed.consistent_snapshot,
-            metadata_base_url,
-            max_targets_size,
-            delegations,
-            datastore,
-        )
-        .await?;
-    }
-
-    // This validation can only be done from the top level targets.json role. This check verifies
-    // that each target's delegate hierarchy is a match (i.e. it's delegate ownership is valid).
-    targets.signed.validate().context(error::InvalidPathSnafu)?;
+    //
+    // (Delegated targets metadata is loaded on demand by `Repository::find_target`.)
     Ok(targets)
 }
 
-// Follow the paths of delegations starting with the top level targets.json delegation
-#[async_recursion]
-async fn load_delegations(
-    transport: &dyn Transport,
-    snapshot: &Signed<Snapshot>,
-    consistent_snapshot: bool,
-    metadata_base_url: &Url,
-    max_targets_size: u64,
-    delegation: &mut Delegations,
-    datastore: &Datastore,
-) -> Result<()> {
-    let mut delegated_roles: HashMap<String, Option<Signed<crate::schema::Targets>>> =
-        HashMap::new();
-    for delegated_role in &delegation.roles {
-        // find the role file metadata
-        let role_meta = snapshot
-            .signed
-            .meta
-            .get(&format!("{}.json", &delegated_role.name))
-            .with_context(|| error::RoleNotInMetaSnafu {
-                name: delegated_role.name.clone(),
-            })?;
-
-        let path = if consistent_snapshot {
-            format!(
-                "{}.{}.json",
-                &role_meta.version,
-                encode_filename(&delegated_role.name)
-            )
-        } else {
-            format!("{}.json", encode_filename(&delegated_role.name))
-        };
-        let role_url = metadata_base_url
-            .join(&path)
-            .with_context(|_| error::JoinUrlSnafu {
-                path: path.clone(),
-                url: metadata_base_url.clone(),
-            })?;
-        let specifier = "max_targets_size parameter";
-        // load the role json file
-        let stream =
-            fetch_max_size(transport, role_url.clone(), max_targets_size, specifier).await?;
-        let data = stream
-            .into_vec()
-            .await
-            .context(error::TransportSnafu { url: role_url })?;
-        // since each role is a targets, we load them as such
-        let role: Signed<crate::schema::Targets> =
-            serde_json::from_slice(&data).context(error::ParseMetadataSnafu {
-                role: RoleType::Targets,
-            })?;
-        // verify each role with the delegation
-        delegation
-            .verify_role(&role, &delegated_role.name)
-            .context(error::VerifyMetadataSnafu {
-                role: RoleType::Targets,
-            })?;
-        ensure!(
-            role.signed.version == role_meta.version,
-            error::VersionMismatchSnafu {
-                role: RoleType::Targets,
-                fetched: role.signed.version,
-                expected: role_meta.version
-            }
-        );
-
-        datastore.create(&path, &role).await?;
-        delegated_roles.insert(delegated_role.name.clone(), Some(role));
-    }
-    // load all roles delegated by this role
-    for delegated_role in &mut delegation.roles {
-        delegated_role.targets =
-            delegated_roles
-                .remove(&delegated_role.name)
-                .with_context(|| error::DelegatedRolesNotConsistentSnafu {
-                    name: delegated_role.name.clone(),
-                })?;
-        if let Some(targets) = &mut delegated_role.targets {
-            if let Some(delegations) = &mut targets.signed.delegations {
-                load_delegations(
-                    transport,
-                    snapshot,
-                    consistent_snapshot,
-                    metadata_base_url,
-                    max_targets_size,
-                    delegations,
-                    datastore,
-                )
-                .await?;
-            }
-        }
-    }
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
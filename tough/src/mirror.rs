@@ -0,0 +1,245 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Provides [`Mirror`], which describes an additional location that a repository's metadata and
+//! targets can be fetched from.
+
+use crate::error::{self, Error, Result};
+use crate::fetch::{fetch_hashes, fetch_max_size, fetch_resume};
+use crate::io::Digests;
+use crate::observer::Observers;
+use crate::schema::{Hashes, PathPattern};
+use crate::transport::{IntoVec, Transport, TransportErrorKind, TransportStream};
+use crate::{parse_url, TargetName};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use url::Url;
+
+/// A location that serves a copy of a TUF repository.
+///
+/// A mirror may serve metadata, targets, or both. A mirror that serves targets may be restricted
+/// to the target names that match `target_paths`. Mirrors are added to a repository with
+/// [`RepositoryLoader::mirror`](crate::RepositoryLoader::mirror), and are tried in order after the
+/// URLs passed to [`RepositoryLoader::new`](crate::RepositoryLoader::new).
+#[derive(Debug, Clone, Default)]
+pub struct Mirror {
+    metadata_base_url: Option<Url>,
+    targets_base_url: Option<Url>,
+    target_paths: Option<Vec<PathPattern>>,
+}
+
+impl Mirror {
+    /// Create a new `Mirror` that does not serve anything until a base URL is set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the base URL where this mirror serves metadata (such as root.json).
+    #[must_use]
+    pub fn metadata_base_url(mut self, url: Url) -> Self {
+        self.metadata_base_url = Some(url);
+        self
+    }
+
+    /// Set the base URL where this mirror serves targets.
+    #[must_use]
+    pub fn targets_base_url(mut self, url: Url) -> Self {
+        self.targets_base_url = Some(url);
+        self
+    }
+
+    /// Restrict this mirror to targets whose names match one of `paths`. By default a mirror
+    /// serves every target.
+    #[must_use]
+    pub fn target_paths(mut self, paths: Vec<PathPattern>) -> Self {
+        self.target_paths = Some(paths);
+        self
+    }
+
+    fn serves_target(&self, name: &TargetName) -> bool {
+        match &self.target_paths {
+            Some(paths) => paths
+                .iter()
+                .any(|pattern| pattern.matches_target_name(name)),
+            None => true,
+        }
+    }
+}
+
+/// The base URL of the mirror that served each file.
+#[derive(Debug, Default)]
+struct ServedBy {
+    metadata: HashMap<String, Url>,
+    targets: HashMap<TargetName, Url>,
+}
+
+/// The ordered list of mirrors that a `Repository` fetches files from.
+#[derive(Debug, Clone)]
+pub(crate) struct Mirrors {
+    list: Vec<Mirror>,
+    observers: Observers,
+    served_by: Arc<Mutex<ServedBy>>,
+}
+
+impl Mirrors {
+    pub(crate) fn new(mirrors: Vec<Mirror>, observers: Observers) -> Result<Self> {
+        let mirrors = mirrors
+            .into_iter()
+            .map(|mirror| {
+                Ok(Mirror {
+                    metadata_base_url: mirror.metadata_base_url.map(parse_url).transpose()?,
+                    targets_base_url: mirror.targets_base_url.map(parse_url).transpose()?,
+                    target_paths: mirror.target_paths,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            list: mirrors,
+            observers,
+            served_by: Arc::default(),
+        })
+    }
+
+    /// Fetches the metadata file at `path` from the first mirror that can serve it. If a mirror
+    /// does not have the file, or fails with [`TransportErrorKind::Other`] (including a length or
+    /// hash mismatch), the next mirror is tried.
+    pub(crate) async fn fetch_metadata(
+        &self,
+        transport: &dyn Transport,
+        path: &str,
+        max_size: u64,
+        specifier: &'static str,
+        hashes: Option<&Hashes>,
+    ) -> Result<Vec<u8>> {
+        let mut result = error::NoMirrorSnafu { path }.fail();
+        for base_url in self
+            .list
+            .iter()
+            .filter_map(|mirror| mirror.metadata_base_url.as_ref())
+        {
+            let url = join(base_url, path)?;
+            result = async {
+                let stream = match hashes {
+                    Some(hashes) => {
+                        fetch_hashes(transport, url.clone(), max_size, specifier, hashes).await?
+                    }
+                    None => fetch_max_size(transport, url.clone(), max_size, specifier).await?,
+                };
+                stream
+                    .into_vec()
+                    .await
+                    .context(error::TransportSnafu { url })
+            }
+            .await
+            .map_err(|e| self.observers.report(path, e));
+            match &result {
+                Ok(_) => {
+                    self.lock()
+                        .metadata
+                        .insert(path.to_owned(), base_url.clone());
+                    break;
+                }
+                Err(e) if !is_retryable(e) => break,
+                Err(_) => {}
+            }
+        }
+        result
+    }
+
+    /// Opens a stream for the target `name` from the first mirror that can serve it. The target may
+    /// be stored under any of `filenames` (e.g. prefixed with any of its digests), which are tried
+    /// in order. If a mirror does not have the file, or fails with [`TransportErrorKind::Other`],
+    /// the next mirror is tried.
+    ///
+    /// If `digests` cover the leading bytes of the target, only the rest of the target is fetched.
+    ///
+    /// Mirrors before `skip` are not tried. The returned index can be passed as `skip` to try the
+    /// remaining mirrors, e.g. if the stream returns a hash mismatch.
+    pub(crate) async fn fetch_target(
+        &self,
+        transport: &dyn Transport,
+        name: &TargetName,
+        filenames: &[String],
+        size: u64,
+        digests: &Digests,
+        skip: usize,
+    ) -> Result<(TransportStream, Url, usize)> {
+        let mut result = error::NoMirrorSnafu { path: name.raw() }.fail();
+        for (index, mirror) in self.list.iter().enumerate().skip(skip) {
+            let base_url = match &mirror.targets_base_url {
+                Some(base_url) if mirror.serves_target(name) => base_url,
+                _ => continue,
+            };
+            for filename in filenames {
+                let url = join(base_url, filename)?;
+                match fetch_resume(
+                    transport,
+                    url.clone(),
+                    size,
+                    "targets.json",
+                    digests.clone(),
+                )
+                .await
+                {
+                    Ok(stream) => {
+                        self.lock().targets.insert(name.clone(), base_url.clone());
+                        return Ok((stream, url, index + 1));
+                    }
+                    // If the mirror does not have the file under this name, try the next name.
+                    Err(e)
+                        if transport_error_kind(&e) == Some(TransportErrorKind::FileNotFound) =>
+                    {
+                        result = Err(e);
+                    }
+                    Err(e) if is_retryable(&e) => {
+                        result = Err(e);
+                        break;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the base URL of the mirror that served the metadata file at `path`.
+    pub(crate) fn metadata_mirror(&self, path: &str) -> Option<Url> {
+        self.lock().metadata.get(path).cloned()
+    }
+
+    /// Returns the base URL of the mirror that served the target `name`.
+    pub(crate) fn target_mirror(&self, name: &TargetName) -> Option<Url> {
+        self.lock().targets.get(name).cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ServedBy> {
+        // The map is always left in a consistent state, so a poisoned lock is still usable.
+        self.served_by
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns `true` if `error` should cause the next mirror to be tried.
+pub(crate) fn is_retryable(error: &Error) -> bool {
+    matches!(
+        transport_error_kind(error),
+        Some(TransportErrorKind::FileNotFound | TransportErrorKind::Other)
+    )
+}
+
+/// Returns the kind of the transport error that caused `error`, if any.
+pub(crate) fn transport_error_kind(error: &Error) -> Option<TransportErrorKind> {
+    match error {
+        Error::Transport { source, .. } => Some(source.kind()),
+        _ => None,
+    }
+}
+
+fn join(base_url: &Url, path: &str) -> Result<Url> {
+    base_url.join(path).with_context(|_| error::JoinUrlSnafu {
+        path,
+        url: base_url.clone(),
+    })
+}
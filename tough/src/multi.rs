@@ -0,0 +1,431 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Provides [`MultiRepository`], which implements [TAP 4] (multiple repository consensus on
+//! entrusted targets).
+//!
+//! A TAP 4 map file lists the repositories a client knows about, and maps target paths to the
+//! repositories that must agree on them:
+//!
+//! ```json
+//! {
+//!   "repositories": {
+//!     "primary": ["https://primary.example.com/"],
+//!     "mirror": ["https://mirror.example.com/"]
+//!   },
+//!   "mapping": [
+//!     {
+//!       "paths": ["firmware/*"],
+//!       "repositories": ["primary", "mirror"],
+//!       "threshold": 2,
+//!       "terminating": true
+//!     },
+//!     {
+//!       "paths": ["*"],
+//!       "repositories": ["primary"],
+//!       "threshold": 1,
+//!       "terminating": false
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Each repository URL is the base of a repository laid out the way `tuftool` writes it, i.e.
+//! with metadata under `metadata/` and targets under `targets/`. If a repository lists more than
+//! one URL, the URLs after the first are used as fallback [`Mirror`]s.
+//!
+//! [TAP 4]: https://github.com/theupdateframework/taps/blob/master/tap4.md
+
+use crate::clock::{Clock, TrustedTimePolicy};
+use crate::datastore::FilesystemDatastore;
+use crate::error::{self, Result};
+use crate::observer::{Observer, Observers};
+use crate::schema::{PathPattern, Target};
+use crate::transport::{DefaultTransport, IntoVec, Transport};
+use crate::{
+    ExpirationEnforcement, Limits, Mirror, RateLimit, Repository, RepositoryLoader, TargetName,
+};
+use bytes::Bytes;
+use futures_core::Stream;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt};
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use std::sync::Arc;
+use url::Url;
+
+/// The contents of a TAP 4 map file.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct MapFile {
+    /// The repositories known to the client, by name. Each repository is a list of URLs that
+    /// serve the same repository, in the order they should be tried.
+    pub repositories: HashMap<String, Vec<String>>,
+
+    /// Maps target paths to the repositories that are trusted to sign them. Mappings are
+    /// considered in order.
+    pub mapping: Vec<Mapping>,
+}
+
+/// Maps a set of target paths to the repositories that must agree on them.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Mapping {
+    /// The target paths covered by this mapping.
+    pub paths: Vec<PathPattern>,
+
+    /// The names of the repositories that are trusted to sign these targets.
+    pub repositories: Vec<String>,
+
+    /// The number of `repositories` that must agree on a target's length and hashes.
+    pub threshold: NonZeroU64,
+
+    /// Indicates whether subsequent mappings should be considered when this mapping matches a
+    /// target path but cannot resolve it.
+    pub terminating: bool,
+}
+
+impl Mapping {
+    fn matches_target_name(&self, target_name: &TargetName) -> bool {
+        self.paths
+            .iter()
+            .any(|pattern| pattern.matches_target_name(target_name))
+    }
+}
+
+/// A `MultiRepositoryLoader` loads every repository listed in a TAP 4 map file.
+///
+/// `metadata_dir` is a directory on a persistent filesystem with one subdirectory per repository
+/// in the map file. Each subdirectory must contain a trusted `root.json` for that repository,
+/// which you must ship with your software using an out-of-band process. The subdirectory is also
+/// used as the repository's datastore (see [`RepositoryLoader::datastore`]).
+#[derive(Debug, Clone)]
+pub struct MultiRepositoryLoader<'a> {
+    map_file: &'a [u8],
+    metadata_dir: PathBuf,
+    transport: Option<Box<dyn Transport + Send + Sync>>,
+    observers: Observers,
+    limits: Option<Limits>,
+    rate_limit: Option<RateLimit>,
+    clock: Option<Box<dyn Clock>>,
+    trusted_time_policy: Option<TrustedTimePolicy>,
+    expiration_enforcement: Option<ExpirationEnforcement>,
+}
+
+impl<'a> MultiRepositoryLoader<'a> {
+    /// Create a new `MultiRepositoryLoader` from the contents of a TAP 4 map file.
+    pub fn new<P: Into<PathBuf>>(map_file: &'a impl AsRef<[u8]>, metadata_dir: P) -> Self {
+        Self {
+            map_file: map_file.as_ref(),
+            metadata_dir: metadata_dir.into(),
+            transport: None,
+            observers: Observers::default(),
+            limits: None,
+            rate_limit: None,
+            clock: None,
+            trusted_time_policy: None,
+            expiration_enforcement: None,
+        }
+    }
+
+    /// Load and verify the metadata of every repository in the map file.
+    pub async fn load(self) -> Result<MultiRepository> {
+        MultiRepository::load(self).await
+    }
+
+    /// Set the transport used for every repository. If no transport has been set,
+    /// [`DefaultTransport`] will be used.
+    #[must_use]
+    pub fn transport<T: Transport + Send + Sync + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Box::new(transport));
+        self
+    }
+
+    /// Add an [`Observer`] to notify of events for every repository.
+    #[must_use]
+    pub fn observer<O: Observer + 'static>(mut self, observer: O) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Set the [`Limits`] used for every repository.
+    #[must_use]
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Limit the rate at which every repository is downloaded to a [`RateLimit`], which they
+    /// share. See [`RepositoryLoader::rate_limit`].
+    #[must_use]
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Set the [`Clock`] used for every repository.
+    #[must_use]
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Set the [`TrustedTimePolicy`] used for every repository.
+    #[must_use]
+    pub fn trusted_time_policy(mut self, policy: TrustedTimePolicy) -> Self {
+        self.trusted_time_policy = Some(policy);
+        self
+    }
+
+    /// Set the [`ExpirationEnforcement`] used for every repository.
+    #[must_use]
+    pub fn expiration_enforcement(mut self, exp: ExpirationEnforcement) -> Self {
+        self.expiration_enforcement = Some(exp);
+        self
+    }
+}
+
+/// A set of TUF repositories that must agree on targets, as described by a TAP 4 map file.
+///
+/// You can create a `MultiRepository` using a [`MultiRepositoryLoader`].
+#[derive(Debug, Clone)]
+pub struct MultiRepository {
+    repositories: HashMap<String, Repository>,
+    mapping: Vec<Mapping>,
+}
+
+impl MultiRepository {
+    async fn load(loader: MultiRepositoryLoader<'_>) -> Result<Self> {
+        let map_file: MapFile =
+            serde_json::from_slice(loader.map_file).context(error::ParseMapFileSnafu)?;
+        for mapping in &map_file.mapping {
+            let mut names = HashSet::new();
+            for name in &mapping.repositories {
+                ensure!(
+                    map_file.repositories.contains_key(name),
+                    error::MapFileRepositoryMissingSnafu { name }
+                );
+                ensure!(
+                    names.insert(name.as_str()),
+                    error::MapFileDuplicateRepositorySnafu { name }
+                );
+            }
+            ensure!(
+                mapping.threshold.get() <= names.len() as u64,
+                error::MapFileThresholdUnreachableSnafu {
+                    threshold: mapping.threshold.get(),
+                    repositories: names.len(),
+                }
+            );
+        }
+        let transport = loader
+            .transport
+            .unwrap_or_else(|| Box::new(DefaultTransport::new()));
+
+        let mut repositories = HashMap::new();
+        for (name, urls) in &map_file.repositories {
+            let repo_dir = loader.metadata_dir.join(name);
+            let root_path = repo_dir.join("root.json");
+            let root = tokio::fs::read(&root_path)
+                .await
+                .context(error::FileReadSnafu { path: &root_path })?;
+
+            let base_urls = urls
+                .iter()
+                .map(|url| {
+                    crate::parse_url(
+                        Url::parse(url).context(error::ParseUrlSnafu { url: url.as_str() })?,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let (base_url, mirror_urls) = base_urls
+                .split_first()
+                .context(error::MapFileNoUrlsSnafu { name })?;
+            let repository = RepositoryLoader {
+                root: &root,
+                metadata_base_url: join_dir(base_url, "metadata")?,
+                targets_base_url: join_dir(base_url, "targets")?,
+                mirrors: mirror_urls
+                    .iter()
+                    .map(|url| {
+                        Ok(Mirror::new()
+                            .metadata_base_url(join_dir(url, "metadata")?)
+                            .targets_base_url(join_dir(url, "targets")?))
+                    })
+                    .collect::<Result<_>>()?,
+                observers: loader.observers.clone(),
+                transport: Some(transport.clone()),
+                limits: loader.limits,
+                rate_limit: loader.rate_limit.clone(),
+                clock: loader.clock.clone(),
+                trusted_time_policy: loader.trusted_time_policy,
+                datastore: Some(Box::new(FilesystemDatastore::new(repo_dir.clone()))),
+                expiration_enforcement: loader.expiration_enforcement,
+            }
+            .load()
+            .await?;
+            repositories.insert(name.clone(), repository);
+        }
+
+        Ok(Self {
+            repositories,
+            mapping: map_file.mapping,
+        })
+    }
+
+    /// Returns the repository named `name` in the map file.
+    pub fn repository(&self, name: &str) -> Option<&Repository> {
+        self.repositories.get(name)
+    }
+
+    /// Returns a mutable reference to the repository named `name` in the map file, e.g. to call
+    /// [`Repository::refresh`].
+    pub fn repository_mut(&mut self, name: &str) -> Option<&mut Repository> {
+        self.repositories.get_mut(name)
+    }
+
+    /// Searches the mappings for the target `name`, returning the target once a threshold of the
+    /// mapped repositories all agree with each other on its length and hashes. Two repositories
+    /// agree on the hashes if they list at least one common algorithm, and the digests of every
+    /// common algorithm match. A repository that fails to look up the target does not agree with
+    /// the others.
+    ///
+    /// Returns `Ok(None)` if no mapping resolves the target and no repositories disagreed about
+    /// it. If a matching mapping's repositories disagree, an
+    /// [`Error::TargetConsensus`](error::Error::TargetConsensus) that lists the disagreeing
+    /// repositories is returned (after any later mappings have also failed to resolve it, if the
+    /// mapping is not terminating). Otherwise, if a repository failed to look up the target, its
+    /// error is returned.
+    pub async fn find_target(&self, name: &TargetName) -> Result<Option<Target>> {
+        Ok(self.resolve(name).await?.map(|(target, _)| target))
+    }
+
+    /// Fetches a target that a threshold of the mapped repositories agree on. See
+    /// [`MultiRepository::find_target`] for how the target is resolved, and
+    /// [`Repository::read_target`] for how it is fetched.
+    pub async fn read_target(
+        &self,
+        name: &TargetName,
+    ) -> Result<Option<impl Stream<Item = Result<Bytes>> + IntoVec<error::Error> + Send>> {
+        match self.resolve(name).await? {
+            Some((_, repository)) => repository.read_target(name).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the target `name` and the first repository that signed the agreed-upon metadata.
+    async fn resolve(&self, name: &TargetName) -> Result<Option<(Target, &Repository)>> {
+        let mut disagreement = None;
+        let mut lookup_error = None;
+        for mapping in &self.mapping {
+            if !mapping.matches_target_name(name) {
+                continue;
+            }
+
+            let mut found: Vec<(&str, Target)> = Vec::new();
+            for repo_name in &mapping.repositories {
+                let repository = self
+                    .repositories
+                    .get(repo_name)
+                    .context(error::MapFileRepositoryMissingSnafu { name: repo_name })?;
+                match repository.find_target(name).await {
+                    Ok(Some(target)) => found.push((repo_name.as_str(), target)),
+                    Ok(None) => {}
+                    Err(err) => {
+                        // A repository that fails to look up the target doesn't agree with the
+                        // others, but the threshold may still be met without it.
+                        warn!(
+                            "Failed to find target '{}' in repository '{}': {}",
+                            name.raw(),
+                            repo_name,
+                            err
+                        );
+                        lookup_error.get_or_insert(err);
+                    }
+                }
+            }
+
+            // Repositories may list different hash algorithms, so rather than requiring identical
+            // metadata, the repositories' targets are compared pairwise: two repositories agree if
+            // the lengths match, and the digests match for every algorithm they have in common (of
+            // which there must be at least one). Every repository in a group must agree with every
+            // other repository in it.
+            let largest = largest_agreeing_group(&found);
+            if largest.len() as u64 >= mapping.threshold.get() {
+                let (repo_name, target) = largest[0];
+                return Ok(Some((target.clone(), &self.repositories[*repo_name])));
+            }
+
+            if !found.is_empty() && disagreement.is_none() {
+                // Report every repository outside of the largest group of agreeing repositories.
+                let mut disagreeing: Vec<String> = mapping
+                    .repositories
+                    .iter()
+                    .filter(|repo_name| !largest.iter().any(|(agreed, _)| agreed == repo_name))
+                    .cloned()
+                    .collect();
+                disagreeing.sort();
+                disagreement = Some(error::TargetConsensusSnafu {
+                    name: name.raw(),
+                    agreeing: largest.len(),
+                    threshold: mapping.threshold.get(),
+                    disagreeing,
+                });
+            }
+
+            if mapping.terminating {
+                break;
+            }
+        }
+
+        match (disagreement, lookup_error) {
+            (Some(context), _) => context.fail(),
+            (None, Some(err)) => Err(err),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// Returns the largest group of `found` targets that all agree with each other.
+fn largest_agreeing_group<'a, 'b>(found: &'a [(&'b str, Target)]) -> Vec<&'a (&'b str, Target)> {
+    fn agree(a: &Target, b: &Target) -> bool {
+        a.length == b.length && a.hashes.matches(&b.hashes)
+    }
+
+    /// Extends `group` with the targets from `found[next..]`, keeping the largest group in
+    /// `largest`. There are only as many targets as repositories in a mapping, so an exhaustive
+    /// search is cheap.
+    fn search<'a, 'b>(
+        found: &'a [(&'b str, Target)],
+        next: usize,
+        group: &mut Vec<&'a (&'b str, Target)>,
+        largest: &mut Vec<&'a (&'b str, Target)>,
+    ) {
+        if group.len() > largest.len() {
+            largest.clone_from(group);
+        }
+        if group.len() + (found.len() - next) <= largest.len() {
+            return;
+        }
+        for (i, candidate) in found.iter().enumerate().skip(next) {
+            if group.iter().all(|(_, member)| agree(member, &candidate.1)) {
+                group.push(candidate);
+                search(found, i + 1, group, largest);
+                group.pop();
+            }
+        }
+    }
+
+    let mut largest = Vec::new();
+    search(found, 0, &mut Vec::new(), &mut largest);
+    largest
+}
+
+/// Joins `dir` onto `base_url` as a directory, i.e. with a trailing slash.
+fn join_dir(base_url: &Url, dir: &str) -> Result<Url> {
+    let path = format!("{dir}/");
+    base_url.join(&path).with_context(|_| error::JoinUrlSnafu {
+        path,
+        url: base_url.clone(),
+    })
+}
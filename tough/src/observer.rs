@@ -0,0 +1,129 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Provides the [`Observer`] trait, which is notified of [`Event`]s while a repository is loaded
+//! and its targets are fetched. This can be used to show progress or to record metrics.
+//!
+//! Observers are registered with [`RepositoryLoader::observer`](crate::RepositoryLoader::observer).
+
+use crate::error::Error;
+use crate::schema::RoleType;
+use crate::TargetName;
+use std::error::Error as _;
+use std::fmt::Debug;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Something that happened while loading a repository or fetching a target.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// A metadata file was fetched and passed every check.
+    RoleVerified {
+        /// The type of the role.
+        role: RoleType,
+        /// The name of the role, e.g. `timestamp`, or the name of a delegated targets role.
+        name: &'a str,
+        /// The version of the metadata.
+        version: NonZeroU64,
+        /// The size of the metadata file in bytes.
+        size: u64,
+    },
+    /// A new root metadata file was trusted, replacing the previous one.
+    RootRotated {
+        /// The version of the previously trusted root.
+        from: NonZeroU64,
+        /// The version of the newly trusted root.
+        to: NonZeroU64,
+    },
+    /// Bytes of a target were received.
+    TargetProgress {
+        /// The name of the target.
+        name: &'a TargetName,
+        /// The number of bytes received so far, including any bytes kept from an earlier,
+        /// interrupted download.
+        received: u64,
+        /// The length of the target in bytes.
+        total: u64,
+    },
+    /// A [`Transport`](crate::Transport) is about to retry a request.
+    RetryAttempted {
+        /// The URL being fetched.
+        url: &'a Url,
+        /// The number of the retry, starting at 1.
+        attempt: u32,
+        /// The offset of the first byte that will be requested.
+        next_byte: u64,
+        /// How long the transport waits before retrying.
+        wait: Duration,
+    },
+    /// A file did not match its expected signatures, length or hashes. If the file can be fetched
+    /// from another mirror, this is not fatal.
+    VerificationFailed {
+        /// The metadata filename or target name that failed verification.
+        file: &'a str,
+        /// Why verification failed.
+        error: &'a Error,
+    },
+}
+
+/// Receives [`Event`]s. Observers are notified on the task that caused the event, so they should
+/// return quickly.
+pub trait Observer: Debug + Send + Sync {
+    /// Called when `event` happens.
+    fn notify(&self, event: &Event<'_>);
+}
+
+impl<T: Observer + ?Sized> Observer for Arc<T> {
+    fn notify(&self, event: &Event<'_>) {
+        self.as_ref().notify(event);
+    }
+}
+
+/// The observers registered with a `RepositoryLoader`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Observers(Vec<Arc<dyn Observer>>);
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Arc<dyn Observer>) {
+        self.0.push(observer);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Notifies the observers of a [`Event::VerificationFailed`] for `file` if `error` is a
+    /// verification failure, then returns `error`.
+    pub(crate) fn report(&self, file: &str, error: Error) -> Error {
+        if let Some(cause) = verification_error(&error) {
+            self.notify(&Event::VerificationFailed { file, error: cause });
+        }
+        error
+    }
+}
+
+impl Observer for Observers {
+    fn notify(&self, event: &Event<'_>) {
+        for observer in &self.0 {
+            observer.notify(event);
+        }
+    }
+}
+
+/// Returns the verification failure that caused `error`, if any. Hash and length mismatches are
+/// found while streaming a file, so they are wrapped in transport errors.
+fn verification_error(error: &Error) -> Option<&Error> {
+    match error {
+        Error::VerifyMetadata { .. }
+        | Error::HashMismatch { .. }
+        | Error::MaxSizeExceeded { .. } => Some(error),
+        Error::Transport { source, .. } => source
+            .source()
+            .and_then(|cause| cause.downcast_ref::<Error>())
+            .and_then(verification_error),
+        _ => None,
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Keeps the bytes of an interrupted target download so that a later run can resume it, see
+//! [`Repository::save_target_resumable`](crate::Repository::save_target_resumable).
+
+use crate::error::{self, Result};
+use crate::io::Digests;
+use crate::schema::{Hashes, Target};
+use crate::TargetName;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// The size of the buffer used to digest the bytes of a partial download.
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Records which target a partial download holds, so that it is only resumed for that target.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Sidecar {
+    name: String,
+    length: u64,
+    hashes: Hashes,
+}
+
+/// A partial download of a target that will be saved at `filepath`. The bytes downloaded so far
+/// are kept in `<filepath>.part`, and the target they belong to is recorded in
+/// `<filepath>.part.json`.
+#[derive(Debug)]
+pub(crate) struct PartialTarget {
+    path: PathBuf,
+    sidecar_path: PathBuf,
+}
+
+impl PartialTarget {
+    pub(crate) fn new(filepath: &Path) -> Self {
+        let mut path = filepath.as_os_str().to_owned();
+        path.push(".part");
+        let mut sidecar_path = path.clone();
+        sidecar_path.push(".json");
+        Self {
+            path: path.into(),
+            sidecar_path: sidecar_path.into(),
+        }
+    }
+
+    /// The path of the file that holds the bytes downloaded so far.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the partial download of `target`. If an earlier download of the same target was
+    /// interrupted, the bytes it saved are digested again and kept, and the returned file is
+    /// positioned after them. Otherwise the download starts from an empty file.
+    pub(crate) async fn open(&self, name: &TargetName, target: &Target) -> Result<(File, Digests)> {
+        let sidecar = Sidecar {
+            name: name.raw().to_owned(),
+            length: target.length,
+            hashes: target.hashes.clone(),
+        };
+        let mut digests = Digests::new(&target.hashes, name.raw())?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)
+            .await
+            .context(error::FileOpenSnafu { path: &self.path })?;
+
+        if self.read_sidecar().await.as_ref() == Some(&sidecar) {
+            let mut buf = vec![0; READ_BUFFER_SIZE];
+            loop {
+                let count = file
+                    .read(&mut buf)
+                    .await
+                    .context(error::FileReadSnafu { path: &self.path })?;
+                if count == 0 {
+                    break;
+                }
+                digests.update(&buf[..count]);
+            }
+            // Keep the bytes if more can be appended to them, or if they are already the target.
+            if digests.len() < target.length
+                || (digests.len() == target.length && digests.check(name.raw()).is_ok())
+            {
+                return Ok((file, digests));
+            }
+            digests = Digests::new(&target.hashes, name.raw())?;
+        }
+
+        // Empty the file before recording which target it holds, so that a download interrupted
+        // in between does not resume from bytes of a different target.
+        file.set_len(0)
+            .await
+            .context(error::FileWriteSnafu { path: &self.path })?;
+        file.seek(SeekFrom::Start(0))
+            .await
+            .context(error::FileWriteSnafu { path: &self.path })?;
+        let buf = serde_json::to_vec(&sidecar).context(error::SaveTargetPartialSidecarSnafu {
+            path: &self.sidecar_path,
+        })?;
+        tokio::fs::write(&self.sidecar_path, buf)
+            .await
+            .context(error::FileWriteSnafu {
+                path: &self.sidecar_path,
+            })?;
+        Ok((file, digests))
+    }
+
+    /// Moves the completed download to `filepath` and removes its sidecar.
+    pub(crate) async fn persist(&self, filepath: &Path) -> Result<()> {
+        tokio::fs::rename(&self.path, filepath)
+            .await
+            .context(error::SaveTargetPartialPersistSnafu { path: filepath })?;
+        remove_file(&self.sidecar_path).await
+    }
+
+    /// Removes the partial download and its sidecar.
+    pub(crate) async fn remove(&self) -> Result<()> {
+        remove_file(&self.path).await?;
+        remove_file(&self.sidecar_path).await
+    }
+
+    async fn read_sidecar(&self) -> Option<Sidecar> {
+        let buf = tokio::fs::read(&self.sidecar_path).await.ok()?;
+        serde_json::from_slice(&buf).ok()
+    }
+}
+
+async fn remove_file(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context(error::RemoveTargetSnafu { path })
+        }
+        _ => Ok(()),
+    }
+}
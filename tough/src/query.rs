@@ -0,0 +1,316 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Queries over the targets of a repository and their custom metadata.
+
+use crate::error::{self, Result};
+use crate::schema::{PathPattern, Target, Targets};
+use crate::{Repository, TargetName};
+use serde::de::DeserializeOwned;
+use snafu::ResultExt;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The name of the top-level targets role, as reported in [`TargetMatch::role`].
+const TOP_LEVEL_ROLE: &str = "targets";
+
+/// The characters that separate the components of a version string in
+/// [`TargetQuery::sort_by_version`].
+const VERSION_SEPARATORS: &[char] = &['.', '-'];
+
+type Filter<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+type Order<T> = Arc<dyn Fn(&T, &T) -> Ordering + Send + Sync>;
+
+/// A query over the targets of a repository, run with [`Repository::query_targets`].
+///
+/// The custom metadata of each target (see [`Target::custom`]) is deserialized into `T`, which
+/// filters and sort orders can then inspect. Use [`serde_json::Value`] as `T` to query targets
+/// without a fixed custom metadata layout.
+///
+/// ```no_run
+/// # use serde::Deserialize;
+/// # use tough::schema::PathPattern;
+/// # use tough::{Repository, TargetQuery};
+/// #[derive(Deserialize)]
+/// struct Custom {
+///     arch: String,
+///     version: String,
+/// }
+///
+/// # async fn query(repository: &mut Repository) -> Result<(), Box<dyn std::error::Error>> {
+/// let query = TargetQuery::<Custom>::new()
+///     .name(PathPattern::new("images/*.img")?)
+///     .filter(|custom| custom.arch == "x86_64")
+///     .sort_by_version(|custom| custom.version.clone());
+/// for found in repository.query_targets(&query).await? {
+///     println!("{} ({})", found.name.raw(), found.custom.version);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct TargetQuery<T> {
+    name: Option<PathPattern>,
+    role: Option<String>,
+    filters: Vec<Filter<T>>,
+    order: Option<Order<T>>,
+    _custom: PhantomData<fn() -> T>,
+}
+
+/// A target found by a [`TargetQuery`].
+#[derive(Debug, Clone)]
+pub struct TargetMatch<T> {
+    /// The name of the target.
+    pub name: TargetName,
+    /// The name of the role that lists the target; `targets` for the top-level targets role.
+    pub role: String,
+    /// The target metadata.
+    pub target: Target,
+    /// The custom metadata of the target.
+    pub custom: T,
+}
+
+impl<T: DeserializeOwned> TargetQuery<T> {
+    /// Creates a query that matches every target.
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            role: None,
+            filters: Vec::new(),
+            order: None,
+            _custom: PhantomData,
+        }
+    }
+
+    /// Only match targets whose names match `pattern`.
+    #[must_use]
+    pub fn name(mut self, pattern: PathPattern) -> Self {
+        self.name = Some(pattern);
+        self
+    }
+
+    /// Only match targets listed by the role named `role`. Use `targets` for the top-level
+    /// targets role.
+    #[must_use]
+    pub fn role<S: Into<String>>(mut self, role: S) -> Self {
+        self.role = Some(role.into());
+        self
+    }
+
+    /// Only match targets whose custom metadata satisfies `filter`. Filters added by repeated
+    /// calls must all be satisfied.
+    #[must_use]
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Sorts matches by the key that `key` extracts from their custom metadata.
+    #[must_use]
+    pub fn sort_by_key<F, K>(mut self, key: F) -> Self
+    where
+        F: Fn(&T) -> K + Send + Sync + 'static,
+        K: Ord,
+    {
+        self.order = Some(Arc::new(move |a, b| key(a).cmp(&key(b))));
+        self
+    }
+
+    /// Sorts matches by the version string that `version` extracts from their custom metadata.
+    ///
+    /// Versions are compared component by component, splitting on `.` and `-`. Components that
+    /// are both numbers are compared numerically and others are compared as strings, so `1.10.0`
+    /// sorts after `1.9.2`. As in semantic versioning, anything after the first `-` is a
+    /// pre-release, which sorts before the same version without one, so `1.0.0-rc1` sorts before
+    /// `1.0.0`, and anything after a `+` is build metadata, which is ignored.
+    #[must_use]
+    pub fn sort_by_version<F, V>(mut self, version: F) -> Self
+    where
+        F: Fn(&T) -> V + Send + Sync + 'static,
+        V: AsRef<str>,
+    {
+        self.order = Some(Arc::new(move |a, b| {
+            compare_versions(version(a).as_ref(), version(b).as_ref())
+        }));
+        self
+    }
+
+    /// Runs the query over `targets` and the delegated roles that have been loaded into it.
+    ///
+    /// Only the entries that a lookup of their name resolves to are matched, following the same
+    /// rules as [`Targets::find_target`]. An entry is skipped if an earlier role lists the same
+    /// name, if the paths of its role or of any role that delegates to it do not match its name,
+    /// or if an earlier terminating role matches its name.
+    ///
+    /// Matches are sorted by name unless a sort order was given, in which case matches that are
+    /// equal in that order are sorted by name.
+    pub fn run(&self, targets: &Targets) -> Result<Vec<TargetMatch<T>>> {
+        let mut matches = Vec::new();
+        self.collect(targets, TOP_LEVEL_ROLE, targets, &mut matches)?;
+        matches.sort_by(|a, b| a.name.resolved().cmp(b.name.resolved()));
+        if let Some(order) = &self.order {
+            matches.sort_by(|a, b| order(&a.custom, &b.custom));
+        }
+        Ok(matches)
+    }
+
+    /// Adds the matching entries of `targets`, which belongs to `role`, and of the roles it
+    /// delegates to. `top_level` is searched to check that each entry is the one a lookup of its
+    /// name would find.
+    fn collect(
+        &self,
+        top_level: &Targets,
+        role: &str,
+        targets: &Targets,
+        matches: &mut Vec<TargetMatch<T>>,
+    ) -> Result<()> {
+        if self.role.as_deref().is_none_or(|wanted| wanted == role) {
+            for (name, target) in &targets.targets {
+                if let Some(pattern) = &self.name {
+                    if !pattern.matches_target_name(name) {
+                        continue;
+                    }
+                }
+                let reachable = top_level
+                    .find_target(name)
+                    .is_ok_and(|found| std::ptr::eq(found, target));
+                if !reachable {
+                    continue;
+                }
+                let custom = serde_json::from_value(target.custom.clone().into_iter().collect())
+                    .context(error::TargetCustomMetadataSnafu {
+                        name: name.clone(),
+                        role,
+                    })?;
+                if self.filters.iter().all(|filter| filter(&custom)) {
+                    matches.push(TargetMatch {
+                        name: name.clone(),
+                        role: role.to_owned(),
+                        target: target.clone(),
+                        custom,
+                    });
+                }
+            }
+        }
+        if let Some(delegations) = &targets.delegations {
+            for delegated_role in &delegations.roles {
+                if let Some(delegated_targets) = &delegated_role.targets {
+                    self.collect(
+                        top_level,
+                        &delegated_role.name,
+                        &delegated_targets.signed,
+                        matches,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: DeserializeOwned> Default for TargetQuery<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for TargetQuery<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            role: self.role.clone(),
+            filters: self.filters.clone(),
+            order: self.order.clone(),
+            _custom: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for TargetQuery<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TargetQuery")
+            .field("name", &self.name.as_ref().map(PathPattern::value))
+            .field("role", &self.role)
+            .field("filters", &self.filters.len())
+            .field("sorted", &self.order.is_some())
+            .finish()
+    }
+}
+
+/// Compares two version strings as described in [`TargetQuery::sort_by_version`].
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_release, a_pre_release) = split_version(a);
+    let (b_release, b_pre_release) = split_version(b);
+    compare_components(a_release, b_release).then_with(|| match (a_pre_release, b_pre_release) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre_release), Some(b_pre_release)) => {
+            compare_components(a_pre_release, b_pre_release)
+        }
+    })
+}
+
+/// Splits a version string into its release and pre-release, dropping any build metadata.
+fn split_version(version: &str) -> (&str, Option<&str>) {
+    let version = version.split('+').next().unwrap_or_default();
+    match version.split_once('-') {
+        Some((release, pre_release)) => (release, Some(pre_release)),
+        None => (version, None),
+    }
+}
+
+/// Compares two parts of version strings component by component.
+fn compare_components(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split(VERSION_SEPARATORS);
+    let mut b_parts = b.split(VERSION_SEPARATORS);
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_part), Some(b_part)) => match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+                (Ok(a_number), Ok(b_number)) => a_number.cmp(&b_number),
+                _ => a_part.cmp(b_part),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl Repository {
+    /// Returns the targets that match `query`, including target files delegated by targets.
+    ///
+    /// Like [`Repository::all_targets`], this first loads every delegated targets role that has
+    /// not been loaded yet. Fails if the custom metadata of a target that matches the name and role
+    /// of the query cannot be deserialized into `T`.
+    pub async fn query_targets<T: DeserializeOwned>(
+        &mut self,
+        query: &TargetQuery<T>,
+    ) -> Result<Vec<TargetMatch<T>>> {
+        self.load_delegated_targets().await?;
+        query.run(&self.targets.signed)
+    }
+}
+
+#[test]
+fn compare_versions_test() {
+    assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+    assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Less);
+    assert_eq!(compare_versions("2.0.0-rc1", "2.0.0-rc2"), Ordering::Less);
+    assert_eq!(compare_versions("1.0.0", "1.0.0"), Ordering::Equal);
+    assert_eq!(compare_versions("1.0.a", "1.0.1"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0.0", "1.0.0-rc1"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0.0-rc1", "1.0.0"), Ordering::Less);
+    assert_eq!(compare_versions("1.0.0-rc1", "0.9.9"), Ordering::Greater);
+    assert_eq!(
+        compare_versions("1.0.0+build2", "1.0.0+build1"),
+        Ordering::Equal
+    );
+}
@@ -1,6 +1,7 @@
 //! Provides a wrapper and traits for abstracting over decoded keys or different types.
 
 use crate::schema::error::{self, Error};
+use crate::schema::key::ECDSA_P384_PUBLIC_KEY_LEN;
 use crate::schema::spki;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use snafu::ResultExt;
@@ -113,16 +114,25 @@ impl Decode for EcdsaPem {
             Some(spki::OID_EC_PARAM_SECP256R1),
             s,
         )
+        .or_else(|_| {
+            spki::decode(
+                spki::OID_EC_PUBLIC_KEY,
+                Some(spki::OID_EC_PARAM_SECP384R1),
+                s,
+            )
+        })
     }
 }
 
 impl Encode for EcdsaPem {
     fn encode(b: &[u8]) -> String {
-        spki::encode(
-            spki::OID_EC_PUBLIC_KEY,
-            Some(spki::OID_EC_PARAM_SECP256R1),
-            b,
-        )
+        // The curve is determined by the length of the uncompressed public key.
+        let curve = if b.len() == ECDSA_P384_PUBLIC_KEY_LEN {
+            spki::OID_EC_PARAM_SECP384R1
+        } else {
+            spki::OID_EC_PARAM_SECP256R1
+        };
+        spki::encode(spki::OID_EC_PUBLIC_KEY, Some(curve), b)
     }
 }
 
@@ -108,6 +108,10 @@ pub enum Error {
     #[snafu(display("TUF targets must be files, given: '{}'", path.display()))]
     TargetNotAFile { path: PathBuf, backtrace: Backtrace },
 
+    /// Unable to create a TUF target without any hash algorithms
+    #[snafu(display("No hash algorithms given for target '{}'", path.display()))]
+    TargetNoHashAlgorithms { path: PathBuf, backtrace: Backtrace },
+
     /// Target doesn't have proper permissions from parent delegations
     #[snafu(display("Invalid file permissions from parent delegation: {}", child))]
     UnmatchedPath { child: String },
@@ -24,8 +24,8 @@ use std::str::FromStr;
 /// where:
 /// KEYTYPE is a string denoting a public key signature system, such as RSA or ECDSA.
 ///
-/// SCHEME is a string denoting a corresponding signature scheme.  For example: "rsassa-pss-sha256"
-/// and "ecdsa-sha2-nistp256".
+/// SCHEME is a string denoting a corresponding signature scheme.  For example: "rsassa-pss-sha256",
+/// "rsa-pkcs1v15-sha256", "ecdsa-sha2-nistp256" and "ecdsa-sha2-nistp384".
 ///
 /// KEYVAL is a dictionary containing the public portion of the key:
 /// `"keyval" : {"public" : PUBLIC}`
@@ -83,9 +83,36 @@ pub enum Key {
 /// Used to identify the RSA signature scheme in use.
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
 pub enum RsaScheme {
     /// `rsassa-pss-sha256`: RSA Probabilistic signature scheme with appendix.
     RsassaPssSha256,
+    /// `rsassa-pss-sha384`: RSA Probabilistic signature scheme with appendix, using SHA-384.
+    RsassaPssSha384,
+    /// `rsassa-pss-sha512`: RSA Probabilistic signature scheme with appendix, using SHA-512.
+    RsassaPssSha512,
+    /// `rsa-pkcs1v15-sha256`: RSA PKCS#1 v1.5 signatures using SHA-256.
+    RsaPkcs1v15Sha256,
+}
+
+impl RsaScheme {
+    pub(crate) fn verification_algorithm(self) -> &'static ring::signature::RsaParameters {
+        match self {
+            RsaScheme::RsassaPssSha256 => &ring::signature::RSA_PSS_2048_8192_SHA256,
+            RsaScheme::RsassaPssSha384 => &ring::signature::RSA_PSS_2048_8192_SHA384,
+            RsaScheme::RsassaPssSha512 => &ring::signature::RSA_PSS_2048_8192_SHA512,
+            RsaScheme::RsaPkcs1v15Sha256 => &ring::signature::RSA_PKCS1_2048_8192_SHA256,
+        }
+    }
+
+    pub(crate) fn signing_algorithm(self) -> &'static dyn ring::signature::RsaEncoding {
+        match self {
+            RsaScheme::RsassaPssSha256 => &ring::signature::RSA_PSS_SHA256,
+            RsaScheme::RsassaPssSha384 => &ring::signature::RSA_PSS_SHA384,
+            RsaScheme::RsassaPssSha512 => &ring::signature::RSA_PSS_SHA512,
+            RsaScheme::RsaPkcs1v15Sha256 => &ring::signature::RSA_PKCS1_SHA256,
+        }
+    }
 }
 
 /// Represents a deserialized (decoded) RSA public key.
@@ -121,12 +148,41 @@ pub struct Ed25519Key {
 /// Used to identify the ECDSA signature scheme in use.
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
 pub enum EcdsaScheme {
     /// `ecdsa-sha2-nistp256`: Elliptic Curve Digital Signature Algorithm with NIST P-256 curve
     /// signing and SHA-256 hashing.
     EcdsaSha2Nistp256,
+    /// `ecdsa-sha2-nistp384`: Elliptic Curve Digital Signature Algorithm with NIST P-384 curve
+    /// signing and SHA-384 hashing.
+    EcdsaSha2Nistp384,
+}
+
+impl EcdsaScheme {
+    /// Returns the scheme for an uncompressed public key, based on the size of the curve it is
+    /// on. Returns `None` if the key is not on a supported curve.
+    pub(crate) fn from_public_key(public: &[u8]) -> Option<Self> {
+        match public.len() {
+            ECDSA_P256_PUBLIC_KEY_LEN => Some(EcdsaScheme::EcdsaSha2Nistp256),
+            ECDSA_P384_PUBLIC_KEY_LEN => Some(EcdsaScheme::EcdsaSha2Nistp384),
+            _ => None,
+        }
+    }
+
+    fn verification_algorithm(self) -> &'static ring::signature::EcdsaVerificationAlgorithm {
+        match self {
+            EcdsaScheme::EcdsaSha2Nistp256 => &ring::signature::ECDSA_P256_SHA256_ASN1,
+            EcdsaScheme::EcdsaSha2Nistp384 => &ring::signature::ECDSA_P384_SHA384_ASN1,
+        }
+    }
 }
 
+/// The length of an uncompressed NIST P-256 public key.
+pub(crate) const ECDSA_P256_PUBLIC_KEY_LEN: usize = 65;
+
+/// The length of an uncompressed NIST P-384 public key.
+pub(crate) const ECDSA_P384_PUBLIC_KEY_LEN: usize = 97;
+
 /// Represents a deserialized (decoded)  Ecdsa public key.
 #[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub struct EcdsaKey {
@@ -153,17 +209,8 @@ impl Key {
     /// Verify a signature of an object made with this key.
     pub(super) fn verify(&self, msg: &[u8], signature: &[u8]) -> bool {
         let (alg, public_key): (&dyn VerificationAlgorithm, untrusted::Input<'_>) = match self {
-            Key::Ecdsa {
-                scheme: EcdsaScheme::EcdsaSha2Nistp256,
-                keyval,
-                ..
-            }
-            | Key::EcdsaOld {
-                scheme: EcdsaScheme::EcdsaSha2Nistp256,
-                keyval,
-                ..
-            } => (
-                &ring::signature::ECDSA_P256_SHA256_ASN1,
+            Key::Ecdsa { scheme, keyval, .. } | Key::EcdsaOld { scheme, keyval, .. } => (
+                scheme.verification_algorithm(),
                 untrusted::Input::from(&keyval.public),
             ),
             Key::Ed25519 {
@@ -174,12 +221,8 @@ impl Key {
                 &ring::signature::ED25519,
                 untrusted::Input::from(&keyval.public),
             ),
-            Key::Rsa {
-                scheme: RsaScheme::RsassaPssSha256,
-                keyval,
-                ..
-            } => (
-                &ring::signature::RSA_PSS_2048_8192_SHA256,
+            Key::Rsa { scheme, keyval, .. } => (
+                scheme.verification_algorithm(),
                 untrusted::Input::from(&keyval.public),
             ),
         };
@@ -221,11 +264,11 @@ impl FromStr for Key {
             }
         } else if let Ok(public) = serde_plain::from_str::<Decoded<EcdsaFlex>>(s) {
             Ok(Key::Ecdsa {
+                scheme: EcdsaScheme::from_public_key(&public).ok_or(KeyParseError(()))?,
                 keyval: EcdsaKey {
                     public,
                     _extra: HashMap::new(),
                 },
-                scheme: EcdsaScheme::EcdsaSha2Nistp256,
                 _extra: HashMap::new(),
             })
         } else {
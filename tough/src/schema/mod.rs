@@ -21,15 +21,15 @@ use chrono::{DateTime, Utc};
 use globset::{Glob, GlobMatcher};
 use hex::ToHex;
 use olpc_cjson::CanonicalFormatter;
-use ring::digest::{digest, Context, SHA256};
+use ring::digest::{digest, Algorithm, Context, SHA256, SHA512};
 use serde::de::Error as SerdeDeError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use serde_plain::{derive_display_from_serialize, derive_fromstr_from_deserialize};
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 use std::collections::HashMap;
 use std::num::NonZeroU64;
-use std::ops::{Deref, DerefMut};
+use std::ops::{ControlFlow, Deref, DerefMut};
 use std::path::Path;
 use std::str::FromStr;
 use tokio::fs::File;
@@ -320,11 +320,21 @@ pub struct Metafile {
     pub _extra: HashMap<String, Value>,
 }
 
-/// Represents the hash dictionary in a `snapshot.json` file.
-#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+/// Represents the hash dictionary of a metadata file in `snapshot.json` or `timestamp.json`, or
+/// of a target in a targets metadata file.
+///
+/// The TUF specification allows any hash algorithm to be listed. The algorithms that tough can
+/// verify have their own fields; any others are kept in `_extra`. When a file is fetched, it is
+/// checked against every known algorithm that is listed, and at least one must be listed.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
 pub struct Hashes {
-    /// The SHA 256 digest of a metadata file.
-    pub sha256: Decoded<Hex>,
+    /// The SHA 256 digest of the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<Decoded<Hex>>,
+
+    /// The SHA 512 digest of the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha512: Option<Decoded<Hex>>,
 
     /// Extra arguments found during deserialization.
     ///
@@ -335,6 +345,68 @@ pub struct Hashes {
     pub _extra: HashMap<String, Value>,
 }
 
+/// A hash algorithm that tough can compute and verify.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum HashAlgorithm {
+    /// SHA 256, listed as `sha256`.
+    Sha256,
+    /// SHA 512, listed as `sha512`.
+    Sha512,
+}
+
+derive_display_from_serialize!(HashAlgorithm);
+derive_fromstr_from_deserialize!(HashAlgorithm);
+
+impl HashAlgorithm {
+    /// Every hash algorithm that tough supports, in order of preference.
+    pub const ALL: [HashAlgorithm; 2] = [HashAlgorithm::Sha256, HashAlgorithm::Sha512];
+
+    pub(crate) fn digest_algorithm(self) -> &'static Algorithm {
+        match self {
+            HashAlgorithm::Sha256 => &SHA256,
+            HashAlgorithm::Sha512 => &SHA512,
+        }
+    }
+}
+
+impl Hashes {
+    /// Returns the digest listed for `algorithm`, if any.
+    pub fn get(&self, algorithm: HashAlgorithm) -> Option<&[u8]> {
+        match algorithm {
+            HashAlgorithm::Sha256 => self.sha256.as_deref(),
+            HashAlgorithm::Sha512 => self.sha512.as_deref(),
+        }
+    }
+
+    /// Sets the digest for `algorithm`.
+    pub fn set(&mut self, algorithm: HashAlgorithm, digest: Vec<u8>) {
+        let digest = Some(Decoded::from(digest));
+        match algorithm {
+            HashAlgorithm::Sha256 => self.sha256 = digest,
+            HashAlgorithm::Sha512 => self.sha512 = digest,
+        }
+    }
+
+    /// Returns every listed digest whose algorithm tough supports, in order of preference.
+    pub fn iter(&self) -> impl Iterator<Item = (HashAlgorithm, &[u8])> {
+        HashAlgorithm::ALL
+            .iter()
+            .filter_map(move |&algorithm| Some((algorithm, self.get(algorithm)?)))
+    }
+
+    /// Returns `true` if `self` and `other` list at least one common supported algorithm, and
+    /// every supported algorithm they both list has the same digest.
+    pub fn matches(&self, other: &Hashes) -> bool {
+        let mut common = self
+            .iter()
+            .filter_map(|(algorithm, digest)| Some((digest, other.get(algorithm)?)))
+            .peekable();
+        common.peek().is_some() && common.all(|(digest, other)| digest == other)
+    }
+}
+
 impl Snapshot {
     /// Create a new `Snapshot` object.
     pub fn new(spec_version: String, version: NonZeroU64, expires: DateTime<Utc>) -> Self {
@@ -454,22 +526,39 @@ pub struct Target {
 }
 
 impl Target {
-    /// Given a path, returns a Target struct
+    /// Given a path, returns a Target struct with the SHA 256 digest of the file.
     pub async fn from_path<P>(path: P) -> Result<Target>
     where
         P: AsRef<Path>,
     {
-        // Ensure the given path is a file
+        Self::from_path_with_hashes(path, &[HashAlgorithm::Sha256]).await
+    }
+
+    /// Given a path, returns a Target struct with the digest of the file for each of
+    /// `algorithms`. At least one algorithm must be given.
+    pub async fn from_path_with_hashes<P>(path: P, algorithms: &[HashAlgorithm]) -> Result<Target>
+    where
+        P: AsRef<Path>,
+    {
         let path = path.as_ref();
+        ensure!(
+            !algorithms.is_empty(),
+            error::TargetNoHashAlgorithmsSnafu { path }
+        );
+
+        // Ensure the given path is a file
         if !path.is_file() {
             return error::TargetNotAFileSnafu { path }.fail();
         }
 
-        // Get the sha256 and length of the target
+        // Get the digests and length of the target
         let mut file = File::open(path)
             .await
             .context(error::FileOpenSnafu { path })?;
-        let mut digest = Context::new(&SHA256);
+        let mut digests = algorithms
+            .iter()
+            .map(|&algorithm| (algorithm, Context::new(algorithm.digest_algorithm())))
+            .collect::<Vec<_>>();
         let mut buf = [0; 8 * 1024];
         let mut length = 0;
         loop {
@@ -480,18 +569,21 @@ impl Target {
             {
                 0 => break,
                 n => {
-                    digest.update(&buf[..n]);
+                    for (_, digest) in &mut digests {
+                        digest.update(&buf[..n]);
+                    }
                     length += n as u64;
                 }
             }
         }
 
+        let mut hashes = Hashes::default();
+        for (algorithm, digest) in digests {
+            hashes.set(algorithm, digest.finish().as_ref().to_vec());
+        }
         Ok(Target {
             length,
-            hashes: Hashes {
-                sha256: Decoded::from(digest.finish().as_ref().to_vec()),
-                _extra: HashMap::new(),
-            },
+            hashes,
             custom: HashMap::new(),
             _extra: HashMap::new(),
         })
@@ -514,12 +606,28 @@ impl Targets {
     /// Given a target url, returns a reference to the Target struct or error if the target is
     /// unreachable.
     ///
+    /// Delegated roles are searched in order, and the search stops at the first terminating role
+    /// whose paths match `target_name`.
+    ///
     /// **Caution**: does not imply that delegations in this struct or any child are valid.
     ///
     pub fn find_target(&self, target_name: &TargetName) -> Result<&Target> {
-        if let Some(target) = self.targets.get(target_name) {
+        if let ControlFlow::Break(Some(target)) = self.search_target(target_name) {
             return Ok(target);
         }
+        error::TargetNotFoundSnafu {
+            name: target_name.clone(),
+        }
+        .fail()
+    }
+
+    /// Preorder depth-first search for `target_name`. Returns `ControlFlow::Break` once the
+    /// search is over, either because the target was found or because a terminating role
+    /// matched it.
+    fn search_target(&self, target_name: &TargetName) -> ControlFlow<Option<&Target>> {
+        if let Some(target) = self.targets.get(target_name) {
+            return ControlFlow::Break(Some(target));
+        }
         if let Some(delegations) = &self.delegations {
             for role in &delegations.roles {
                 // If the target cannot match this DelegatedRole, then we do not want to recurse and
@@ -528,16 +636,29 @@ impl Targets {
                     continue;
                 }
                 if let Some(targets) = &role.targets {
-                    if let Ok(target) = targets.signed.find_target(target_name) {
-                        return Ok(target);
-                    }
+                    targets.signed.search_target(target_name)?;
+                }
+                if role.terminating {
+                    return ControlFlow::Break(None);
                 }
             }
         }
-        error::TargetNotFoundSnafu {
-            name: target_name.clone(),
-        }
-        .fail()
+        ControlFlow::Continue(())
+    }
+
+    /// Returns `true` if `target_name` is listed by this role, or by a delegated role whose paths,
+    /// and the paths of every role that delegates to it, match `target_name`.
+    fn owns_target(&self, target_name: &TargetName) -> bool {
+        self.targets.contains_key(target_name)
+            || self.delegations.as_ref().is_some_and(|delegations| {
+                delegations.roles.iter().any(|role| {
+                    role.paths.matches_target_name(target_name)
+                        && role
+                            .targets
+                            .as_ref()
+                            .is_some_and(|targets| targets.signed.owns_target(target_name))
+                })
+            })
     }
 
     /// Returns a hashmap of all targets and all delegated targets recursively
@@ -609,14 +730,12 @@ impl Targets {
         {
             if role.name == name {
                 return Ok(role);
-            } else if let Ok(role) = role
-                .targets
-                .as_ref()
-                .ok_or(error::Error::NoTargets)?
-                .signed
-                .delegated_role(name)
-            {
-                return Ok(role);
+            }
+            // Roles whose metadata has not been loaded cannot be searched.
+            if let Some(targets) = &role.targets {
+                if let Ok(role) = targets.signed.delegated_role(name) {
+                    return Ok(role);
+                }
             }
         }
         Err(error::Error::RoleNotFound {
@@ -634,14 +753,11 @@ impl Targets {
         {
             if role.name == name {
                 return Ok(role);
-            } else if let Ok(role) = role
-                .targets
-                .as_mut()
-                .ok_or(error::Error::NoTargets)?
-                .signed
-                .delegated_role_mut(name)
-            {
-                return Ok(role);
+            }
+            if let Some(targets) = &mut role.targets {
+                if let Ok(role) = targets.signed.delegated_role_mut(name) {
+                    return Ok(role);
+                }
             }
         }
         Err(error::Error::RoleNotFound {
@@ -718,13 +834,21 @@ impl Targets {
         needed_roles
     }
 
-    /// Calls `find_target` on each target (recursively provided by `targets_iter`). This
-    /// proves that the target is either owned by us, or correctly matches through some hierarchy of
-    /// [`PathSets`] below us. When called on the top level [`Targets`] of a repository, this proves
-    /// that the ownership of each target is valid.
+    /// Checks each target (recursively provided by `targets_iter`). This proves that the target is
+    /// either owned by us, or correctly matches through some hierarchy of [`PathSets`] below us.
+    /// When called on the top level [`Targets`] of a repository, this proves that the ownership of
+    /// each target is valid.
+    ///
+    /// Terminating delegations are not considered here, so a target that is shadowed by an
+    /// earlier terminating role is still valid, it just cannot be found by `find_target`.
     pub(crate) fn validate(&self) -> Result<()> {
         for (target_name, _) in self.targets_iter() {
-            self.find_target(target_name)?;
+            ensure!(
+                self.owns_target(target_name),
+                error::TargetNotFoundSnafu {
+                    name: target_name.clone(),
+                }
+            );
         }
         Ok(())
     }
@@ -950,9 +1074,15 @@ impl PathPattern {
         &self.value
     }
 
-    fn matches_target_name(&self, target_name: &TargetName) -> bool {
+    pub(crate) fn matches_target_name(&self, target_name: &TargetName) -> bool {
         self.glob.is_match(target_name.resolved())
     }
+
+    /// Returns whether `value`, such as a URL, matches the pattern.
+    #[cfg(feature = "testing")]
+    pub(crate) fn is_match(&self, value: &str) -> bool {
+        self.glob.is_match(value)
+    }
 }
 
 impl FromStr for PathPattern {
@@ -1025,7 +1155,7 @@ impl FromStr for PathHashPrefix {
 impl PathSet {
     /// Given a `target_name`, returns whether or not this `PathSet` contains a pattern or hash
     /// prefix that matches.
-    fn matches_target_name(&self, target_name: &TargetName) -> bool {
+    pub(crate) fn matches_target_name(&self, target_name: &TargetName) -> bool {
         match self {
             Self::Paths(paths) => {
                 for path in paths {
@@ -1161,7 +1291,8 @@ fn targets_iter_and_map_test() {
     let nothing = Target {
         length: 0,
         hashes: Hashes {
-            sha256: [0u8].to_vec().into(),
+            sha256: Some([0u8].to_vec().into()),
+            sha512: None,
             _extra: HashMap::default(),
         },
         custom: HashMap::default(),
@@ -1248,3 +1379,100 @@ fn targets_iter_and_map_test() {
     assert!(map.contains_key(&TargetName::new("b.txt").unwrap()));
     assert!(map.contains_key(&TargetName::new("c.txt").unwrap()));
 }
+
+#[test]
+fn find_target_terminating_test() {
+    use maplit::hashmap;
+
+    let nothing = Target {
+        length: 0,
+        hashes: Hashes {
+            sha256: Some([0u8].to_vec().into()),
+            sha512: None,
+            _extra: HashMap::default(),
+        },
+        custom: HashMap::default(),
+        _extra: HashMap::default(),
+    };
+    let targets = |targets: HashMap<TargetName, Target>, delegations: Option<Delegations>| {
+        Some(Signed {
+            signed: Targets {
+                spec_version: String::new(),
+                version: NonZeroU64::new(1).unwrap(),
+                expires: Utc::now(),
+                targets,
+                delegations,
+                _extra: HashMap::default(),
+            },
+            signatures: vec![],
+        })
+    };
+    let role = |name: &str, pattern: &str, terminating: bool, targets| DelegatedRole {
+        name: name.to_string(),
+        keyids: vec![],
+        threshold: NonZeroU64::new(1).unwrap(),
+        paths: PathSet::Paths(vec![PathPattern::new(pattern).unwrap()]),
+        terminating,
+        targets,
+    };
+
+    // "first" is a terminating delegation for "*.txt" that lists nothing but delegates "*" to
+    // "nested". "second" lists a.txt, b.txt and c.bin.
+    let nested = role(
+        "nested",
+        "*",
+        false,
+        targets(
+            hashmap! {
+                TargetName::new("a.txt").unwrap() => nothing.clone(),
+                TargetName::new("c.bin").unwrap() => nothing.clone(),
+            },
+            None,
+        ),
+    );
+    let first = role(
+        "first",
+        "*.txt",
+        true,
+        targets(
+            HashMap::new(),
+            Some(Delegations {
+                keys: HashMap::default(),
+                roles: vec![nested],
+            }),
+        ),
+    );
+    let second = role(
+        "second",
+        "*",
+        false,
+        targets(
+            hashmap! {
+                TargetName::new("a.txt").unwrap() => nothing.clone(),
+                TargetName::new("b.txt").unwrap() => nothing.clone(),
+                TargetName::new("c.bin").unwrap() => nothing,
+            },
+            None,
+        ),
+    );
+    let top = Targets {
+        spec_version: String::new(),
+        version: NonZeroU64::new(1).unwrap(),
+        expires: Utc::now(),
+        targets: HashMap::new(),
+        delegations: Some(Delegations {
+            keys: HashMap::default(),
+            roles: vec![first, second],
+        }),
+        _extra: HashMap::default(),
+    };
+
+    // a.txt is found in "nested" before the search can reach "second".
+    assert!(top.find_target(&TargetName::new("a.txt").unwrap()).is_ok());
+    // b.txt matches the terminating role "first", so "second" is never searched.
+    assert!(top.find_target(&TargetName::new("b.txt").unwrap()).is_err());
+    // c.bin is listed by "nested", but "first" does not permit it, so it comes from "second".
+    assert!(top.find_target(&TargetName::new("c.bin").unwrap()).is_ok());
+    // Shadowed targets still pass validation, since their paths are delegated correctly.
+    assert!(top.validate().is_ok());
+}
@@ -25,6 +25,7 @@ use untrusted::Input;
 pub(super) static OID_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113_549, 1, 1, 1];
 pub(super) static OID_EC_PUBLIC_KEY: &[u64] = &[1, 2, 840, 10_045, 2, 1];
 pub(super) static OID_EC_PARAM_SECP256R1: &[u64] = &[1, 2, 840, 10_045, 3, 1, 7];
+pub(super) static OID_EC_PARAM_SECP384R1: &[u64] = &[1, 3, 132, 0, 34];
 
 /// Wrap a bit string in a `SubjectPublicKeyInfo` document.
 pub(super) fn encode(algorithm_oid: &[u64], parameters_oid: Option<&[u64]>, b: &[u8]) -> String {
@@ -4,7 +4,7 @@
 //! Provides the `Sign` trait which abstracts over the method of signing with different key types.
 
 use crate::error::{self, Result};
-use crate::schema::key::Key;
+use crate::schema::key::{EcdsaScheme, Key, RsaScheme};
 use crate::sign::SignKeyPair::ECDSA;
 use crate::sign::SignKeyPair::ED25519;
 use crate::sign::SignKeyPair::RSA;
@@ -72,20 +72,11 @@ impl Sign for Ed25519KeyPair {
     }
 }
 
-/// Implements the Sign trait for RSA keypairs
+/// Implements the Sign trait for RSA keypairs, using the `rsassa-pss-sha256` scheme
 #[async_trait]
 impl Sign for RsaKeyPair {
     fn tuf_key(&self) -> Key {
-        use crate::schema::key::{RsaKey, RsaScheme};
-
-        Key::Rsa {
-            keyval: RsaKey {
-                public: self.public_key().as_ref().to_vec().into(),
-                _extra: HashMap::new(),
-            },
-            scheme: RsaScheme::RsassaPssSha256,
-            _extra: HashMap::new(),
-        }
+        rsa_tuf_key(self, RsaScheme::RsassaPssSha256)
     }
 
     async fn sign(
@@ -93,25 +84,50 @@ impl Sign for RsaKeyPair {
         msg: &[u8],
         rng: &(dyn SecureRandom + Sync),
     ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let mut signature = vec![0; self.public().modulus_len()];
-        self.sign(&ring::signature::RSA_PSS_SHA256, rng, msg, &mut signature)
-            .context(error::SignSnafu)?;
-        Ok(signature)
+        rsa_sign(self, RsaScheme::RsassaPssSha256, msg, rng)
+    }
+}
+
+fn rsa_tuf_key(key_pair: &RsaKeyPair, scheme: RsaScheme) -> Key {
+    use crate::schema::key::RsaKey;
+
+    Key::Rsa {
+        keyval: RsaKey {
+            public: key_pair.public_key().as_ref().to_vec().into(),
+            _extra: HashMap::new(),
+        },
+        scheme,
+        _extra: HashMap::new(),
     }
 }
 
-/// Implements the Sign trait for ECDSA keypairs
+fn rsa_sign(
+    key_pair: &RsaKeyPair,
+    scheme: RsaScheme,
+    msg: &[u8],
+    rng: &(dyn SecureRandom + Sync),
+) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mut signature = vec![0; key_pair.public().modulus_len()];
+    key_pair
+        .sign(scheme.signing_algorithm(), rng, msg, &mut signature)
+        .context(error::SignSnafu)?;
+    Ok(signature)
+}
+
+/// Implements the Sign trait for ECDSA keypairs. The scheme (`ecdsa-sha2-nistp256` or
+/// `ecdsa-sha2-nistp384`) is determined by the key's curve.
 #[async_trait]
 impl Sign for EcdsaKeyPair {
     fn tuf_key(&self) -> Key {
-        use crate::schema::key::{EcdsaKey, EcdsaScheme};
+        use crate::schema::key::EcdsaKey;
 
+        let public = self.public_key().as_ref();
         Key::Ecdsa {
+            scheme: EcdsaScheme::from_public_key(public).unwrap_or(EcdsaScheme::EcdsaSha2Nistp256),
             keyval: EcdsaKey {
-                public: self.public_key().as_ref().to_vec().into(),
+                public: public.to_vec().into(),
                 _extra: HashMap::new(),
             },
-            scheme: EcdsaScheme::EcdsaSha2Nistp256,
             _extra: HashMap::new(),
         }
     }
@@ -130,8 +146,8 @@ impl Sign for EcdsaKeyPair {
 #[derive(Debug)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum SignKeyPair {
-    /// RSA key pair
-    RSA(RsaKeyPair),
+    /// RSA key pair, and the scheme it signs with
+    RSA(RsaKeyPair, RsaScheme),
     /// ED25519 key pair
     ED25519(Ed25519KeyPair),
     /// ECDSA key pair
@@ -142,7 +158,7 @@ pub enum SignKeyPair {
 impl Sign for SignKeyPair {
     fn tuf_key(&self) -> Key {
         match self {
-            RSA(key) => key.tuf_key(),
+            RSA(key, scheme) => rsa_tuf_key(key, *scheme),
             ED25519(key) => key.tuf_key(),
             ECDSA(key) => key.tuf_key(),
         }
@@ -154,7 +170,7 @@ impl Sign for SignKeyPair {
         rng: &(dyn SecureRandom + Sync),
     ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync + 'static>> {
         match self {
-            RSA(key) => (key as &dyn Sign).sign(msg, rng).await,
+            RSA(key, scheme) => rsa_sign(key, *scheme, msg, rng),
             ED25519(key) => (key as &dyn Sign).sign(msg, rng).await,
             ECDSA(key) => (key as &dyn Sign).sign(msg, rng).await,
         }
@@ -163,27 +179,40 @@ impl Sign for SignKeyPair {
 
 /// Parses a supplied keypair and if it is recognized, returns an object that
 /// implements the Sign trait
-/// Accepted Keys: ED25519 pkcs8, Ecdsa pkcs8, RSA
+/// Accepted Keys: ED25519 pkcs8, Ecdsa (P-256 or P-384) pkcs8, RSA
+///
+/// RSA keys sign with the `rsassa-pss-sha256` scheme; use [`parse_keypair_with_rsa_scheme`] to
+/// choose another.
 pub fn parse_keypair(key: &[u8]) -> Result<impl Sign> {
+    parse_keypair_with_rsa_scheme(key, RsaScheme::RsassaPssSha256)
+}
+
+/// Parses a supplied keypair like [`parse_keypair`], signing with `rsa_scheme` if it is an RSA
+/// key.
+pub fn parse_keypair_with_rsa_scheme(key: &[u8], rsa_scheme: RsaScheme) -> Result<SignKeyPair> {
+    let rng = rand::SystemRandom::new();
     if let Ok(ed25519_key_pair) = Ed25519KeyPair::from_pkcs8(key) {
         Ok(SignKeyPair::ED25519(ed25519_key_pair))
-    } else if let Ok(ecdsa_key_pair) = EcdsaKeyPair::from_pkcs8(
+    } else if let Some(ecdsa_key_pair) = [
         &ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING,
-        key,
-        &rand::SystemRandom::new(),
-    ) {
+        &ring::signature::ECDSA_P384_SHA384_ASN1_SIGNING,
+    ]
+    .iter()
+    .find_map(|algorithm| EcdsaKeyPair::from_pkcs8(algorithm, key, &rng).ok())
+    {
         Ok(SignKeyPair::ECDSA(ecdsa_key_pair))
     } else if let Ok(pem) = pem::parse(key) {
         match pem.tag() {
             "PRIVATE KEY" => {
                 if let Ok(rsa_key_pair) = RsaKeyPair::from_pkcs8(pem.contents()) {
-                    Ok(SignKeyPair::RSA(rsa_key_pair))
+                    Ok(SignKeyPair::RSA(rsa_key_pair, rsa_scheme))
                 } else {
                     error::KeyUnrecognizedSnafu.fail()
                 }
             }
             "RSA PRIVATE KEY" => Ok(SignKeyPair::RSA(
                 RsaKeyPair::from_der(pem.contents()).context(error::KeyRejectedSnafu)?,
+                rsa_scheme,
             )),
             _ => error::KeyUnrecognizedSnafu.fail(),
         }
@@ -0,0 +1,439 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Transports for testing programs that use `tough` without a network.
+//!
+//! * [`RecordingTransport`] saves the files fetched through another transport to a directory.
+//! * [`ReplayTransport`] serves the files in such a directory.
+//! * [`FaultTransport`] injects errors, delays, and damaged files into the fetches of another
+//!   transport, so that failure paths can be tested in-process.
+//!
+//! A file is recorded at `<host>/<path>` in the directory, or `<host>_<port>/<path>` if the URL
+//! has a port, or `_/<path>` if it has no host. The file for
+//! `https://example.com/metadata/timestamp.json` is recorded at
+//! `example.com/metadata/timestamp.json`, for example, so a directory of responses can also be
+//! written by hand. Files which are not in the directory are reported as
+//! [`TransportErrorKind::FileNotFound`].
+//!
+//! ```no_run
+//! # use tough::schema::PathPattern;
+//! # use tough::testing::{Fault, FaultTransport, ReplayTransport};
+//! # use tough::RepositoryLoader;
+//! # use url::Url;
+//! # async fn load() -> Result<(), Box<dyn std::error::Error>> {
+//! let root = std::fs::read("root.json")?;
+//! // Fail the first fetch of the timestamp metadata.
+//! let transport = FaultTransport::new(ReplayTransport::new("tests/data/responses")).fault_times(
+//!     "*/timestamp.json".parse::<PathPattern>()?,
+//!     Fault::Error,
+//!     1,
+//! );
+//! let loader = RepositoryLoader::new(
+//!     &root,
+//!     Url::parse("https://example.com/metadata/")?,
+//!     Url::parse("https://example.com/targets/")?,
+//! )
+//! .transport(transport);
+//! assert!(loader.clone().load().await.is_err());
+//! assert!(loader.load().await.is_ok());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! This module is only available with the `testing` feature.
+
+use crate::observer::Observer;
+use crate::schema::PathPattern;
+use crate::transport::{StreamFile, TransportStream};
+use crate::{
+    ConditionalFetch, FilesystemTransport, Transport, TransportError, TransportErrorKind,
+    Validators,
+};
+use async_trait::async_trait;
+use bytes::BytesMut;
+use futures::StreamExt;
+use log::warn;
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// The path at which the file for `url` is recorded in `directory`, if the URL has a path that
+/// can be mapped to a file.
+fn recording_path(directory: &Path, url: &Url) -> Option<PathBuf> {
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}_{port}"),
+        (Some(host), None) if !host.is_empty() => host.to_owned(),
+        _ => "_".to_owned(),
+    };
+    let mut path = directory.join(host);
+    for segment in url.path_segments()? {
+        // Segments are kept percent-encoded, so they cannot contain a path separator.
+        if segment.is_empty() || segment == "." || segment == ".." {
+            return None;
+        }
+        path.push(segment);
+    }
+    Some(path)
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// A [`Transport`] that serves the files in a directory written by [`RecordingTransport`], or by
+/// hand. See the [module documentation](self) for its layout.
+#[derive(Debug, Clone)]
+pub struct ReplayTransport {
+    directory: Arc<PathBuf>,
+}
+
+impl ReplayTransport {
+    /// Creates a `ReplayTransport` which serves the files in `directory`.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: Arc::new(directory.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        self.fetch_range(url, 0).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        let Some(path) = recording_path(&self.directory, &url) else {
+            return Err(TransportError::new(TransportErrorKind::FileNotFound, url));
+        };
+        let map_io_err = move |e: std::io::Error| {
+            let kind = match e.kind() {
+                ErrorKind::NotFound => TransportErrorKind::FileNotFound,
+                _ => TransportErrorKind::Other,
+            };
+            TransportError::new_with_cause(kind, &url, e)
+        };
+        let stream = FilesystemTransport::open(path, start)
+            .await
+            .map_err(map_io_err.clone())?;
+        Ok(stream.map(move |chunk| chunk.map_err(&map_io_err)).boxed())
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// A [`Transport`] that saves the files fetched through another transport to a directory, to be
+/// served by [`ReplayTransport`]. See the [module documentation](self) for its layout.
+///
+/// A file is saved once it has been read to the end. Resumed downloads, which only fetch part of
+/// a file, are not saved. Files that are not found are removed from the directory.
+#[derive(Debug, Clone)]
+pub struct RecordingTransport<T> {
+    transport: T,
+    directory: Arc<PathBuf>,
+}
+
+impl<T: Transport + Clone> RecordingTransport<T> {
+    /// Creates a `RecordingTransport` which fetches files through `transport` and saves them in
+    /// `directory`.
+    pub fn new<P: Into<PathBuf>>(transport: T, directory: P) -> Self {
+        Self {
+            transport,
+            directory: Arc::new(directory.into()),
+        }
+    }
+
+    /// Removes the recording of `url` if the fetch that failed with `error` did not find it.
+    async fn forget(&self, url: &Url, error: TransportError) -> TransportError {
+        if let (TransportErrorKind::FileNotFound, Some(path)) =
+            (error.kind(), recording_path(&self.directory, url))
+        {
+            // A previous recording may have found the file.
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        error
+    }
+
+    /// Returns `stream`, which saves the file fetched from `url` once it is read to the end.
+    async fn record(&self, url: &Url, stream: TransportStream) -> TransportStream {
+        let Some(path) = recording_path(&self.directory, url) else {
+            return stream;
+        };
+        match StreamFile::create(path, &[]).await {
+            Ok(file) => file.write_stream(stream),
+            Err(e) => {
+                warn!("Failed to record '{}': {}", url, e);
+                stream
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Transport + Clone> Transport for RecordingTransport<T> {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        match self.transport.fetch(url.clone()).await {
+            Ok(stream) => Ok(self.record(&url, stream).await),
+            Err(e) => Err(self.forget(&url, e).await),
+        }
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        self.transport.fetch_range(url, start).await
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        // A file that has not been modified keeps its previous recording.
+        match self
+            .transport
+            .fetch_conditional(url.clone(), validators)
+            .await
+        {
+            Ok(ConditionalFetch::NotModified) => Ok(ConditionalFetch::NotModified),
+            Ok(ConditionalFetch::Modified { stream, validators }) => {
+                Ok(ConditionalFetch::Modified {
+                    stream: self.record(&url, stream).await,
+                    validators,
+                })
+            }
+            Err(e) => Err(self.forget(&url, e).await),
+        }
+    }
+
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.transport.set_observer(observer);
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// A fault that [`FaultTransport`] injects into a fetch. Byte offsets are counted from the start
+/// of the returned stream, which for a resumed download is not the start of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Fault {
+    /// Fail the fetch with a [`TransportErrorKind::Other`] error.
+    Error,
+    /// Fail the fetch with a [`TransportErrorKind::FileNotFound`] error.
+    FileNotFound,
+    /// Wait before fetching, like a slow server.
+    Delay(Duration),
+    /// End the file after this many bytes, as if it were shorter.
+    Truncate(u64),
+    /// Fail with a [`TransportErrorKind::Other`] error after this many bytes, as if the connection
+    /// was lost.
+    Interrupt(u64),
+    /// Invert the bits of the byte at this offset.
+    Corrupt(u64),
+}
+
+#[derive(Debug)]
+struct FaultRule {
+    pattern: PathPattern,
+    fault: Fault,
+    /// The number of fetches left to inject the fault into, if limited.
+    remaining: Option<AtomicU32>,
+}
+
+impl FaultRule {
+    /// Returns whether the fault should be injected into a fetch of `url`, counting it if so.
+    fn applies(&self, url: &Url) -> bool {
+        if !self.pattern.is_match(url.as_str()) {
+            return false;
+        }
+        match &self.remaining {
+            None => true,
+            Some(remaining) => remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok(),
+        }
+    }
+}
+
+/// A [`Transport`] that injects [`Fault`]s into the fetches of another transport whose URL
+/// matches a pattern, such as `*/timestamp.json`.
+///
+/// Every matching fault is injected, in the order they were added. Faults added with
+/// [`FaultTransport::fault_times`] are only injected into the given number of fetches, counted
+/// across the transport and its clones, so that recovery can be tested.
+#[derive(Debug, Clone)]
+pub struct FaultTransport<T> {
+    transport: T,
+    rules: Vec<Arc<FaultRule>>,
+}
+
+impl<T: Transport + Clone> FaultTransport<T> {
+    /// Creates a `FaultTransport` which fetches files through `transport`, without faults.
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Injects `fault` into every fetch of a URL that matches `pattern`.
+    #[must_use]
+    pub fn fault(self, pattern: PathPattern, fault: Fault) -> Self {
+        self.add_rule(pattern, fault, None)
+    }
+
+    /// Injects `fault` into the first `times` fetches of a URL that matches `pattern`.
+    #[must_use]
+    pub fn fault_times(self, pattern: PathPattern, fault: Fault, times: u32) -> Self {
+        self.add_rule(pattern, fault, Some(AtomicU32::new(times)))
+    }
+
+    /// Injects the faults that happen before a fetch of `url`, and returns the faults to inject
+    /// into the content of the file.
+    async fn before_fetch(&self, url: &Url) -> Result<Vec<Fault>, TransportError> {
+        let faults: Vec<Fault> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.applies(url))
+            .map(|rule| rule.fault)
+            .collect();
+        for fault in &faults {
+            match fault {
+                Fault::Delay(delay) => tokio::time::sleep(*delay).await,
+                Fault::Error => {
+                    return Err(TransportError::new_with_cause(
+                        TransportErrorKind::Other,
+                        url,
+                        "injected error",
+                    ))
+                }
+                Fault::FileNotFound => {
+                    return Err(TransportError::new(TransportErrorKind::FileNotFound, url))
+                }
+                _ => {}
+            }
+        }
+        Ok(faults)
+    }
+
+    fn add_rule(
+        mut self,
+        pattern: PathPattern,
+        fault: Fault,
+        remaining: Option<AtomicU32>,
+    ) -> Self {
+        self.rules.push(Arc::new(FaultRule {
+            pattern,
+            fault,
+            remaining,
+        }));
+        self
+    }
+}
+
+/// Injects a `fault` that damages the content of `stream`.
+fn damage(stream: TransportStream, fault: Fault, url: Url) -> TransportStream {
+    let end = match fault {
+        Fault::Truncate(end) | Fault::Interrupt(end) => Some(end),
+        _ => None,
+    };
+    futures::stream::unfold(
+        (stream, 0_u64, false),
+        move |(mut stream, position, done)| {
+            let url = url.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                if end.is_some_and(|end| position >= end) {
+                    return match fault {
+                        Fault::Interrupt(_) => Some((
+                            Err(TransportError::new_with_cause(
+                                TransportErrorKind::Other,
+                                url,
+                                "injected interruption",
+                            )),
+                            (stream, position, true),
+                        )),
+                        _ => None,
+                    };
+                }
+                let mut bytes = match stream.next().await? {
+                    Ok(bytes) => bytes,
+                    Err(e) => return Some((Err(e), (stream, position, true))),
+                };
+                if let Some(end) = end {
+                    let len = usize::try_from(end - position).unwrap_or(usize::MAX);
+                    bytes.truncate(len);
+                }
+                if let Fault::Corrupt(offset) = fault {
+                    if let Some(index) = offset
+                        .checked_sub(position)
+                        .and_then(|index| usize::try_from(index).ok())
+                        .filter(|index| *index < bytes.len())
+                    {
+                        let mut damaged = BytesMut::from(bytes.as_ref());
+                        damaged[index] = !damaged[index];
+                        bytes = damaged.freeze();
+                    }
+                }
+                let position = position + bytes.len() as u64;
+                Some((Ok(bytes), (stream, position, false)))
+            }
+        },
+    )
+    .boxed()
+}
+
+/// Injects each of `faults` that damages the content of `stream`.
+fn damage_all(mut stream: TransportStream, faults: &[Fault], url: &Url) -> TransportStream {
+    for fault in faults {
+        if let Fault::Truncate(_) | Fault::Interrupt(_) | Fault::Corrupt(_) = fault {
+            stream = damage(stream, *fault, url.clone());
+        }
+    }
+    stream
+}
+
+#[async_trait]
+impl<T: Transport + Clone> Transport for FaultTransport<T> {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        self.fetch_range(url, 0).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        let faults = self.before_fetch(&url).await?;
+        let stream = if start == 0 {
+            self.transport.fetch(url.clone()).await?
+        } else {
+            self.transport.fetch_range(url.clone(), start).await?
+        };
+        Ok(damage_all(stream, &faults, &url))
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        let faults = self.before_fetch(&url).await?;
+        Ok(
+            match self
+                .transport
+                .fetch_conditional(url.clone(), validators)
+                .await?
+            {
+                ConditionalFetch::NotModified => ConditionalFetch::NotModified,
+                ConditionalFetch::Modified { stream, validators } => ConditionalFetch::Modified {
+                    stream: damage_all(stream, &faults, &url),
+                    validators,
+                },
+            },
+        )
+    }
+
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.transport.set_observer(observer);
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Provides [`RateLimit`] and [`ThrottledTransport`], which limit the rate at which files are
+//! downloaded.
+
+use crate::observer::Observer;
+use crate::transport::TransportStream;
+use crate::{ConditionalFetch, Transport, TransportError, Validators};
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::convert::TryFrom;
+use std::num::NonZeroU64;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::time::Instant;
+use url::Url;
+
+/// A limit on the rate at which files are downloaded, in bytes per second.
+///
+/// The limit is shared by every download that uses it, including concurrent downloads and
+/// downloads through clones of the `RateLimit`, so their combined rate stays within it. It can be
+/// changed, or removed, while downloads are running.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    state: Arc<Mutex<RateLimitState>>,
+}
+
+#[derive(Debug)]
+struct RateLimitState {
+    bytes_per_second: Option<NonZeroU64>,
+    /// The time at which the bytes that have been allowed so far are paid for.
+    next_free: Instant,
+}
+
+impl RateLimit {
+    /// Creates a `RateLimit` of `bytes_per_second`.
+    pub fn new(bytes_per_second: NonZeroU64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RateLimitState {
+                bytes_per_second: Some(bytes_per_second),
+                next_free: Instant::now(),
+            })),
+        }
+    }
+
+    /// The current limit in bytes per second, or `None` if downloads are not limited.
+    pub fn bytes_per_second(&self) -> Option<NonZeroU64> {
+        self.lock().bytes_per_second
+    }
+
+    /// Changes the limit to `bytes_per_second`, or removes it if `None`. Running downloads use
+    /// the new limit from their next chunk of data.
+    pub fn set_bytes_per_second(&self, bytes_per_second: Option<NonZeroU64>) {
+        self.lock().bytes_per_second = bytes_per_second;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RateLimitState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits until `bytes` more bytes may be downloaded without exceeding the limit.
+    async fn acquire(&self, bytes: u64) {
+        let until = {
+            let mut state = self.lock();
+            let Some(bytes_per_second) = state.bytes_per_second else {
+                return;
+            };
+            let nanos = u128::from(bytes) * 1_000_000_000 / u128::from(bytes_per_second.get());
+            let cost = Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX));
+            state.next_free = state.next_free.max(Instant::now()) + cost;
+            state.next_free
+        };
+        tokio::time::sleep_until(until).await;
+    }
+
+    /// Passes `stream` through, holding back each chunk until it is within the limit.
+    fn throttle(&self, stream: TransportStream) -> TransportStream {
+        let rate_limit = self.clone();
+        stream
+            .then(move |chunk| {
+                let rate_limit = rate_limit.clone();
+                async move {
+                    if let Ok(bytes) = &chunk {
+                        rate_limit
+                            .acquire(u64::try_from(bytes.len()).unwrap_or(u64::MAX))
+                            .await;
+                    }
+                    chunk
+                }
+            })
+            .boxed()
+    }
+}
+
+/// A [`Transport`] that limits the rate at which files are downloaded through another transport
+/// to a [`RateLimit`].
+///
+/// The limit is applied to the content returned by the other transport, before the size and hash
+/// of the file are checked, so it does not change which files are accepted. Use
+/// [`RepositoryLoader::rate_limit`](crate::RepositoryLoader::rate_limit) to limit the downloads of
+/// a repository.
+#[derive(Debug, Clone)]
+pub struct ThrottledTransport<T> {
+    transport: T,
+    rate_limit: RateLimit,
+}
+
+impl<T: Transport + Clone> ThrottledTransport<T> {
+    /// Creates a `ThrottledTransport` which fetches files through `transport` within
+    /// `rate_limit`.
+    pub fn new(transport: T, rate_limit: RateLimit) -> Self {
+        Self {
+            transport,
+            rate_limit,
+        }
+    }
+
+    /// The [`RateLimit`] of the transport, which can be used to change it.
+    pub fn rate_limit(&self) -> &RateLimit {
+        &self.rate_limit
+    }
+}
+
+#[async_trait]
+impl<T: Transport + Clone> Transport for ThrottledTransport<T> {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        Ok(self.rate_limit.throttle(self.transport.fetch(url).await?))
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        Ok(self
+            .rate_limit
+            .throttle(self.transport.fetch_range(url, start).await?))
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        Ok(
+            match self.transport.fetch_conditional(url, validators).await? {
+                ConditionalFetch::NotModified => ConditionalFetch::NotModified,
+                ConditionalFetch::Modified { stream, validators } => ConditionalFetch::Modified {
+                    stream: self.rate_limit.throttle(stream),
+                    validators,
+                },
+            },
+        )
+    }
+
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.transport.set_observer(observer);
+    }
+}
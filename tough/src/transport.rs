@@ -1,18 +1,38 @@
+use crate::observer::Observer;
 use crate::SafeUrlPath;
 #[cfg(feature = "http")]
 use crate::{HttpTransport, HttpTransportBuilder};
 use async_trait::async_trait;
 use bytes::Bytes;
 use dyn_clone::DynClone;
+#[cfg(feature = "archive")]
+use flate2::read::GzDecoder;
 use futures::{StreamExt, TryStreamExt};
 use futures_core::Stream;
+use log::warn;
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "archive")]
+use snafu::ResultExt;
+#[cfg(feature = "archive")]
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
-use std::io::{self, ErrorKind};
-use std::path::Path;
+use std::io::{self, ErrorKind, SeekFrom};
+#[cfg(feature = "archive")]
+use std::io::{BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::Arc;
+use tempfile::TempPath;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt};
+#[cfg(feature = "archive")]
+use tokio::sync::mpsc::Sender;
 use tokio_util::io::ReaderStream;
 use url::Url;
+#[cfg(feature = "archive")]
+use zip::ZipArchive;
 
 pub type TransportStream = Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>;
 
@@ -45,6 +65,124 @@ impl<S: Stream<Item = Result<Bytes, E>> + Send, E: Send> IntoVec<E> for S {
 pub trait Transport: Debug + DynClone + Send + Sync {
     /// Opens a `Read` object for the file specified by `url`.
     async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError>;
+
+    /// Opens a `Read` object for the file specified by `url`, starting at byte offset `start`.
+    ///
+    /// This is used to resume an interrupted download. The default implementation fetches the
+    /// whole file and discards the first `start` bytes; implement it to avoid transferring them.
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        Ok(skip_bytes(self.fetch(url).await?, start).boxed())
+    }
+
+    /// Opens a `Read` object for the file specified by `url`, unless it has not changed since a
+    /// previous fetch returned `validators`.
+    ///
+    /// This is used by [`CachingTransport`]. The default implementation always fetches the file,
+    /// and returns no validators for it, so nothing is cached.
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        _validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        Ok(ConditionalFetch::Modified {
+            stream: self.fetch(url).await?,
+            validators: Validators::default(),
+        })
+    }
+
+    /// Registers an [`Observer`] to notify of events that happen within the transport, such as
+    /// retries. [`RepositoryLoader`](crate::RepositoryLoader) calls this with the observers that
+    /// were registered with it. The default implementation ignores the observer.
+    fn set_observer(&mut self, _observer: Arc<dyn Observer>) {}
+}
+
+/// Discards the first `count` bytes of `stream`.
+pub(crate) fn skip_bytes<S, E>(stream: S, count: u64) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    let mut remaining = count;
+    stream.filter_map(move |chunk| {
+        let chunk = match chunk {
+            Ok(mut bytes) if remaining > 0 => {
+                let skipped = usize::try_from(remaining)
+                    .unwrap_or(usize::MAX)
+                    .min(bytes.len());
+                remaining -= u64::try_from(skipped).unwrap_or(remaining);
+                let rest = bytes.split_off(skipped);
+                (!rest.is_empty()).then_some(Ok(rest))
+            }
+            chunk => Some(chunk),
+        };
+        std::future::ready(chunk)
+    })
+}
+
+/// Values that identify a version of a file, such as the `ETag` and `Last-Modified` headers of an
+/// HTTP response. A transport can use them to tell whether a file has changed since it was last
+/// fetched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    /// An opaque identifier of the version of the file.
+    pub etag: Option<String>,
+    /// The time at which the file was last modified, as an HTTP date.
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    /// Returns `true` if there are no validators, so the file cannot be fetched conditionally.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// The result of [`Transport::fetch_conditional`].
+pub enum ConditionalFetch {
+    /// The file has not changed since it was fetched with the given validators.
+    NotModified,
+    /// The file has changed, or could not be fetched conditionally.
+    Modified {
+        /// The content of the file.
+        stream: TransportStream,
+        /// The validators of this version of the file, to use for the next fetch.
+        validators: Validators,
+    },
+}
+
+impl Debug for ConditionalFetch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionalFetch::NotModified => f.write_str("NotModified"),
+            ConditionalFetch::Modified { validators, .. } => f
+                .debug_struct("Modified")
+                .field("validators", validators)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Forwards to the boxed transport, so that boxed transports can be wrapped by other transports.
+#[async_trait]
+impl Transport for Box<dyn Transport + Send + Sync> {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        self.as_ref().fetch(url).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        self.as_ref().fetch_range(url, start).await
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        self.as_ref().fetch_conditional(url, validators).await
+    }
+
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.as_mut().set_observer(observer);
+    }
 }
 
 // Implements `Clone` for `Transport` trait objects (i.e. on `Box::<dyn Clone>`). To facilitate
@@ -168,11 +306,15 @@ impl Error for TransportError {
 pub struct FilesystemTransport;
 
 impl FilesystemTransport {
-    async fn open(
+    pub(crate) async fn open(
         file_path: impl AsRef<Path>,
+        start: u64,
     ) -> Result<impl Stream<Item = Result<Bytes, io::Error>> + Send, io::Error> {
-        // Open the file
-        let f = tokio::fs::File::open(file_path).await?;
+        // Open the file and seek to the first requested byte
+        let mut f = tokio::fs::File::open(file_path).await?;
+        if start > 0 {
+            f.seek(SeekFrom::Start(start)).await?;
+        }
 
         // And convert to stream
         let reader = tokio::io::BufReader::new(f);
@@ -185,6 +327,10 @@ impl FilesystemTransport {
 #[async_trait]
 impl Transport for FilesystemTransport {
     async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        self.fetch_range(url, 0).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
         // If the scheme isn't "file://", reject
         if url.scheme() != "file" {
             return Err(TransportError::new(
@@ -196,7 +342,7 @@ impl Transport for FilesystemTransport {
         let file_path = url.safe_url_filepath();
 
         // Open the file
-        let stream = Self::open(file_path).await;
+        let stream = Self::open(file_path, start).await;
 
         // And map to `TransportError`
         let map_io_err = move |e: io::Error| -> TransportError {
@@ -215,21 +361,526 @@ impl Transport for FilesystemTransport {
 
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
+/// The size of the chunks that [`ArchiveTransport`] streams files in.
+#[cfg(feature = "archive")]
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Provides a [`Transport`] for files inside a tar, gzip-compressed tar, or zip archive, without
+/// extracting them.
+///
+/// Files are served from `archive:///` URLs whose path is the path of the file within the archive.
+/// A repository archived as `metadata/` and `targets/` directories can be loaded with the base
+/// URLs `archive:///metadata/` and `archive:///targets/`, for example:
+///
+/// ```no_run
+/// # use tough::{ArchiveTransport, RepositoryLoader};
+/// # use url::Url;
+/// # async fn load() -> Result<(), Box<dyn std::error::Error>> {
+/// let root = std::fs::read("root.json")?;
+/// let transport = ArchiveTransport::open("repository.tar.gz")?;
+/// let repository = RepositoryLoader::new(
+///     &root,
+///     Url::parse("archive:///metadata/")?,
+///     Url::parse("archive:///targets/")?,
+/// )
+/// .transport(transport)
+/// .load()
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Files that are not in the archive are reported as [`TransportErrorKind::FileNotFound`].
+///
+/// Tar and zip archives are read directly at the position of each file. A gzip-compressed tar
+/// archive cannot be read at a position, so every fetch decompresses the archive from its start up
+/// to the file: loading a repository from one costs about the size of the archive for each file
+/// that is fetched. Prefer an uncompressed tar or a zip archive for repositories with many
+/// targets, or extract the archive and use [`FilesystemTransport`].
+#[cfg(feature = "archive")]
+#[derive(Debug, Clone)]
+pub struct ArchiveTransport {
+    path: Arc<PathBuf>,
+    format: ArchiveFormat,
+    /// The regular files in the archive, by path.
+    entries: Arc<HashMap<String, ArchiveEntry>>,
+}
+
+#[cfg(feature = "archive")]
+#[derive(Debug, Clone, Copy)]
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+#[cfg(feature = "archive")]
+#[derive(Debug, Clone, Copy)]
+struct ArchiveEntry {
+    /// For tar archives, the offset of the file data in the (decompressed) archive. For zip
+    /// archives, the index of the file.
+    position: u64,
+    /// The uncompressed size of the file.
+    size: u64,
+}
+
+#[cfg(feature = "archive")]
+impl ArchiveTransport {
+    /// The URL scheme that `ArchiveTransport` serves.
+    pub const SCHEME: &'static str = "archive";
+
+    /// Opens the archive at `path` and reads its index. The format of the archive is detected
+    /// from its content.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        let path = path.as_ref();
+        let (format, entries) =
+            Self::index(path).context(crate::error::ArchiveReadSnafu { path })?;
+        Ok(Self {
+            path: Arc::new(path.to_owned()),
+            format,
+            entries: Arc::new(entries),
+        })
+    }
+
+    fn index(path: &Path) -> io::Result<(ArchiveFormat, HashMap<String, ArchiveEntry>)> {
+        let mut file = std::fs::File::open(path)?;
+        let mut magic = [0; 4];
+        let magic_len = file.read(&mut magic)?;
+        file.rewind()?;
+        let format = match &magic[..magic_len] {
+            [0x1f, 0x8b, ..] => ArchiveFormat::TarGz,
+            b"PK\x03\x04" | b"PK\x05\x06" => ArchiveFormat::Zip,
+            _ => ArchiveFormat::Tar,
+        };
+
+        let entries = match format {
+            ArchiveFormat::Tar => Self::index_tar(file)?,
+            ArchiveFormat::TarGz => Self::index_tar(GzDecoder::new(BufReader::new(file)))?,
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(file)?;
+                let mut entries = HashMap::new();
+                for index in 0..archive.len() {
+                    let entry = archive.by_index(index)?;
+                    if entry.is_file() {
+                        entries.insert(
+                            entry.name().trim_start_matches("./").to_owned(),
+                            ArchiveEntry {
+                                position: index as u64,
+                                size: entry.size(),
+                            },
+                        );
+                    }
+                }
+                entries
+            }
+        };
+        Ok((format, entries))
+    }
+
+    fn index_tar<R: Read>(reader: R) -> io::Result<HashMap<String, ArchiveEntry>> {
+        let mut archive = tar::Archive::new(reader);
+        let mut entries = HashMap::new();
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if let Some(name) = entry.path()?.to_str() {
+                entries.insert(
+                    name.trim_start_matches("./").to_owned(),
+                    ArchiveEntry {
+                        position: entry.raw_file_position(),
+                        size: entry.size(),
+                    },
+                );
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Reads `entry` from the archive, starting at byte offset `start`, and sends its content to
+    /// `tx` in chunks. Stops early if the receiver is dropped.
+    fn read_entry(
+        path: &Path,
+        format: ArchiveFormat,
+        entry: ArchiveEntry,
+        start: u64,
+        tx: &Sender<io::Result<Bytes>>,
+    ) -> io::Result<()> {
+        let file = std::fs::File::open(path)?;
+        match format {
+            ArchiveFormat::Tar => {
+                let mut file = file;
+                file.seek(SeekFrom::Start(entry.position + start))?;
+                send_chunks(&mut file.take(entry.size - start), tx)
+            }
+            ArchiveFormat::TarGz => {
+                // A compressed archive cannot be seeked into, so decompress up to the file.
+                let mut reader = GzDecoder::new(BufReader::new(file));
+                io::copy(
+                    &mut (&mut reader).take(entry.position + start),
+                    &mut io::sink(),
+                )?;
+                send_chunks(&mut reader.take(entry.size - start), tx)
+            }
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(file)?;
+                let index = usize::try_from(entry.position)
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+                let mut reader = archive.by_index(index)?;
+                io::copy(&mut (&mut reader).take(start), &mut io::sink())?;
+                send_chunks(&mut reader, tx)
+            }
+        }
+    }
+}
+
+/// Sends the content of `reader` to `tx` in chunks, until the end of `reader` or until the
+/// receiver is dropped.
+#[cfg(feature = "archive")]
+fn send_chunks(reader: &mut dyn Read, tx: &Sender<io::Result<Bytes>>) -> io::Result<()> {
+    let mut buf = vec![0; ARCHIVE_CHUNK_SIZE];
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(len) => len,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if tx
+            .blocking_send(Ok(Bytes::copy_from_slice(&buf[..len])))
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(feature = "archive")]
+#[async_trait]
+impl Transport for ArchiveTransport {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        self.fetch_range(url, 0).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        if url.scheme() != Self::SCHEME {
+            return Err(TransportError::new(
+                TransportErrorKind::UnsupportedUrlScheme,
+                url,
+            ));
+        }
+        let Some(&entry) = self.entries.get(url.path().trim_start_matches('/')) else {
+            return Err(TransportError::new(TransportErrorKind::FileNotFound, url));
+        };
+
+        // Archive readers are blocking, so read the file on a blocking thread and stream its
+        // chunks back through a channel.
+        let start = start.min(entry.size);
+        let path = Arc::clone(&self.path);
+        let format = self.format;
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = Self::read_entry(&path, format, entry, start, &tx) {
+                // The receiver may already be gone, in which case nobody needs the error.
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
+        })
+        .map(move |chunk| {
+            chunk.map_err(|e| TransportError::new_with_cause(TransportErrorKind::Other, &url, e))
+        });
+        Ok(stream.boxed())
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// A file that is written with the content of a [`TransportStream`] as the stream is read.
+#[derive(Debug)]
+pub(crate) struct StreamFile {
+    file: tokio::fs::File,
+    temp_path: TempPath,
+    path: PathBuf,
+}
+
+impl StreamFile {
+    /// Starts writing a file that will replace `path`, beginning with `header`. The parent
+    /// directory of `path` is created if needed.
+    pub(crate) async fn create(path: PathBuf, header: &[u8]) -> io::Result<Self> {
+        let directory = path
+            .parent()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path has no parent"))?
+            .to_owned();
+        tokio::fs::create_dir_all(&directory).await?;
+        let (file, temp_path) =
+            tokio::task::spawn_blocking(move || tempfile::NamedTempFile::new_in(directory))
+                .await
+                // We do not cancel the task nor do we expect it to panic
+                .unwrap_or_else(|_| unreachable!())?
+                .into_parts();
+        let mut file = tokio::fs::File::from_std(file);
+        file.write_all(header).await?;
+        Ok(Self {
+            file,
+            temp_path,
+            path,
+        })
+    }
+
+    /// Passes `stream` through, writing its content to the file. The file replaces `path` only
+    /// if the whole stream is read without error; failures to write it are logged.
+    pub(crate) fn write_stream(self, stream: TransportStream) -> TransportStream {
+        futures::stream::unfold(
+            (stream, Some(self)),
+            |(mut stream, mut writer)| async move {
+                match stream.next().await {
+                    Some(Ok(bytes)) => {
+                        if let Some(w) = writer.as_mut() {
+                            if let Err(e) = w.file.write_all(&bytes).await {
+                                warn!("Failed to write '{}': {}", w.path.display(), e);
+                                writer = None;
+                            }
+                        }
+                        Some((Ok(bytes), (stream, writer)))
+                    }
+                    Some(Err(e)) => Some((Err(e), (stream, None))),
+                    None => {
+                        if let Some(w) = writer {
+                            let path = w.path.clone();
+                            if let Err(e) = w.finish().await {
+                                warn!("Failed to write '{}': {}", path.display(), e);
+                            }
+                        }
+                        None
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    async fn finish(mut self) -> io::Result<()> {
+        self.file.flush().await?;
+        self.temp_path.persist(&self.path)?;
+        Ok(())
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// A [`Transport`] that keeps the files fetched through another transport in a local directory,
+/// and asks the other transport whether they have changed before downloading them again.
+///
+/// Files are cached with the validators that the other transport returns from
+/// [`Transport::fetch_conditional`], such as the `ETag` and `Last-Modified` headers of
+/// [`HttpTransport`](crate::HttpTransport) responses. The next fetch of the file sends them, as
+/// `If-None-Match` and `If-Modified-Since` headers, and the cached file is returned if the file
+/// has not changed. Files without validators, and files that could not be fetched, are not cached.
+///
+/// The cached files are not trusted: the [`Repository`](crate::Repository) checks their versions
+/// and hashes like those of any other fetched file.
+///
+/// Files that are not found are not cached either. In particular, the `N+1.root.json` file that
+/// every load requests to look for a new root is requested again each time: remembering that it
+/// was not found would hide a root rotation from the repository until the cache was cleared.
+///
+/// A `CachingTransport` also answers [`Transport::fetch_conditional`], so that it can be wrapped
+/// in another caching or recording transport. It reports a file as not modified when the
+/// validators it is given are those of the cached file.
+///
+/// ```no_run
+/// # use tough::{CachingTransport, DefaultTransport, RepositoryLoader};
+/// # use url::Url;
+/// # async fn load() -> Result<(), Box<dyn std::error::Error>> {
+/// let root = std::fs::read("root.json")?;
+/// let repository = RepositoryLoader::new(
+///     &root,
+///     Url::parse("https://example.com/metadata/")?,
+///     Url::parse("https://example.com/targets/")?,
+/// )
+/// .transport(CachingTransport::new(DefaultTransport::new(), "/var/cache/tuf"))
+/// .load()
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachingTransport<T> {
+    transport: T,
+    directory: Arc<PathBuf>,
+}
+
+/// The first line of a cached file, followed by the content of the file.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheHeader {
+    url: String,
+    #[serde(flatten)]
+    validators: Validators,
+}
+
+impl<T: Transport + Clone> CachingTransport<T> {
+    /// Creates a `CachingTransport` which fetches files through `transport` and caches them in
+    /// `directory`. The directory is created when the first file is cached.
+    pub fn new<P: Into<PathBuf>>(transport: T, directory: P) -> Self {
+        Self {
+            transport,
+            directory: Arc::new(directory.into()),
+        }
+    }
+
+    /// The path of the cached file for `url`.
+    fn path(&self, url: &Url) -> PathBuf {
+        self.directory
+            .join(hex::encode(digest(&SHA256, url.as_str().as_bytes())))
+    }
+
+    /// Opens the cached file for `url`, returning its validators and a reader positioned at the
+    /// start of its content.
+    async fn open(
+        &self,
+        url: &Url,
+    ) -> io::Result<(Validators, tokio::io::BufReader<tokio::fs::File>)> {
+        let mut reader = tokio::io::BufReader::new(tokio::fs::File::open(self.path(url)).await?);
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        let header: CacheHeader = serde_json::from_str(&line)?;
+        if header.url != url.as_str() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "cached file is for another URL",
+            ));
+        }
+        Ok((header.validators, reader))
+    }
+
+    /// Starts writing the cached file for `url`. The file replaces the previously cached file
+    /// once it is complete.
+    async fn create(&self, url: &Url, validators: Validators) -> io::Result<StreamFile> {
+        let mut header = serde_json::to_vec(&CacheHeader {
+            url: url.to_string(),
+            validators,
+        })?;
+        header.push(b'\n');
+        StreamFile::create(self.path(url), &header).await
+    }
+
+    /// Returns `stream`, which caches the file fetched from `url` with `validators` once it is
+    /// read to the end. Files without validators are not cached.
+    async fn cache(
+        &self,
+        url: &Url,
+        stream: TransportStream,
+        validators: Validators,
+    ) -> TransportStream {
+        if validators.is_empty() {
+            return stream;
+        }
+        match self.create(url, validators).await {
+            Ok(file) => file.write_stream(stream),
+            Err(e) => {
+                warn!("Failed to cache '{}': {}", url, e);
+                stream
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Transport + Clone> Transport for CachingTransport<T> {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        match self
+            .fetch_conditional(url.clone(), &Validators::default())
+            .await?
+        {
+            ConditionalFetch::Modified { stream, .. } => Ok(stream),
+            // Only returned to callers that send validators.
+            ConditionalFetch::NotModified => self.transport.fetch(url).await,
+        }
+    }
+
+    /// Resumed downloads are fetched through the other transport, and are not cached.
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        self.transport.fetch_range(url, start).await
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        let cached = self.open(&url).await.ok();
+        let cached_validators = cached
+            .as_ref()
+            .map(|(validators, _)| validators.clone())
+            .unwrap_or_default();
+        match self
+            .transport
+            .fetch_conditional(url.clone(), &cached_validators)
+            .await?
+        {
+            ConditionalFetch::NotModified => match cached {
+                // The caller already has the cached version of the file.
+                Some(_) if !validators.is_empty() && *validators == cached_validators => {
+                    Ok(ConditionalFetch::NotModified)
+                }
+                Some((validators, reader)) => Ok(ConditionalFetch::Modified {
+                    stream: ReaderStream::new(reader)
+                        .map(move |chunk| {
+                            chunk.map_err(|e| {
+                                TransportError::new_with_cause(TransportErrorKind::Other, &url, e)
+                            })
+                        })
+                        .boxed(),
+                    validators,
+                }),
+                // There was nothing to validate, so the transport should not have said this.
+                None => Ok(ConditionalFetch::Modified {
+                    stream: self.transport.fetch(url).await?,
+                    validators: Validators::default(),
+                }),
+            },
+            ConditionalFetch::Modified { stream, validators } => Ok(ConditionalFetch::Modified {
+                stream: self.cache(&url, stream, validators.clone()).await,
+                validators,
+            }),
+        }
+    }
+
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.transport.set_observer(observer);
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
 /// A Transport that provides support for both local files and, if the `http` feature is enabled,
 /// HTTP-transported files.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct DefaultTransport {
     file: FilesystemTransport,
     #[cfg(feature = "http")]
     http: HttpTransport,
+    #[cfg(not(feature = "http"))]
+    _not_copy: NotCopy,
 }
 
+/// Keeps `DefaultTransport` from being `Copy` without the `http` feature, so that enabling the
+/// feature doesn't take away a trait implementation.
+#[cfg(not(feature = "http"))]
+#[derive(Debug, Clone)]
+struct NotCopy;
+
 impl Default for DefaultTransport {
     fn default() -> Self {
         Self {
             file: FilesystemTransport,
             #[cfg(feature = "http")]
             http: HttpTransport::default(),
+            #[cfg(not(feature = "http"))]
+            _not_copy: NotCopy,
         }
     }
 }
@@ -244,32 +895,70 @@ impl DefaultTransport {
 #[cfg(feature = "http")]
 impl DefaultTransport {
     /// Create a new `DefaultTransport` with potentially customized settings.
+    ///
+    /// If the HTTP client cannot be built, every HTTP fetch fails with that error. Use
+    /// [`DefaultTransport::try_new_with_http_settings`] to handle this case here instead.
     pub fn new_with_http_settings(builder: HttpTransportBuilder) -> Self {
         Self {
             file: FilesystemTransport,
             http: builder.build(),
         }
     }
+
+    /// Create a new `DefaultTransport` with potentially customized settings, returning an error
+    /// if the HTTP client cannot be built.
+    pub fn try_new_with_http_settings(
+        builder: HttpTransportBuilder,
+    ) -> Result<Self, crate::http::HttpError> {
+        Ok(Self {
+            file: FilesystemTransport,
+            http: builder.try_build()?,
+        })
+    }
 }
 
 #[async_trait]
 impl Transport for DefaultTransport {
     async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        self.fetch_range(url, 0).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
         match url.scheme() {
-            "file" => self.file.fetch(url).await,
-            "http" | "https" => self.handle_http(url).await,
+            "file" => self.file.fetch_range(url, start).await,
+            "http" | "https" => self.handle_http(url, start).await,
             _ => Err(TransportError::new(
                 TransportErrorKind::UnsupportedUrlScheme,
                 url,
             )),
         }
     }
+
+    #[cfg(feature = "http")]
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        match url.scheme() {
+            "http" | "https" => self.http.fetch_conditional(url, validators).await,
+            _ => Ok(ConditionalFetch::Modified {
+                stream: self.fetch(url).await?,
+                validators: Validators::default(),
+            }),
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.http.set_observer(observer);
+    }
 }
 
 impl DefaultTransport {
     #[cfg(not(feature = "http"))]
     #[allow(clippy::trivially_copy_pass_by_ref, clippy::unused_self)]
-    async fn handle_http(&self, url: Url) -> Result<TransportStream, TransportError> {
+    async fn handle_http(&self, url: Url, _start: u64) -> Result<TransportStream, TransportError> {
         Err(TransportError::new_with_cause(
             TransportErrorKind::UnsupportedUrlScheme,
             url,
@@ -278,7 +967,7 @@ impl DefaultTransport {
     }
 
     #[cfg(feature = "http")]
-    async fn handle_http(&self, url: Url) -> Result<TransportStream, TransportError> {
-        self.http.fetch(url).await
+    async fn handle_http(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        self.http.fetch_range(url, start).await
     }
 }
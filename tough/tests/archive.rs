@@ -0,0 +1,127 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "archive")]
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use test_utils::{read_to_end, test_data};
+use tough::{ArchiveTransport, RepositoryLoader, TargetName, Transport, TransportErrorKind};
+use url::Url;
+
+mod test_utils;
+
+fn reference_impl() -> PathBuf {
+    test_data().join("tuf-reference-impl")
+}
+
+/// Writes the TUF reference implementation repository as a tar archive to `writer`.
+fn write_tar<W: Write>(writer: W) -> W {
+    let mut builder = tar::Builder::new(writer);
+    builder
+        .append_dir_all("metadata", reference_impl().join("metadata"))
+        .unwrap();
+    builder
+        .append_dir_all("targets", reference_impl().join("targets"))
+        .unwrap();
+    builder.into_inner().unwrap()
+}
+
+/// Writes the TUF reference implementation repository to a zip archive at `path`.
+fn write_zip(path: &Path) {
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for dir in ["metadata", "targets"].iter().copied() {
+        for entry in std::fs::read_dir(reference_impl().join(dir)).unwrap() {
+            let entry = entry.unwrap();
+            let name = format!("{}/{}", dir, entry.file_name().to_str().unwrap());
+            writer
+                .start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            writer
+                .write_all(&std::fs::read(entry.path()).unwrap())
+                .unwrap();
+        }
+    }
+    writer.finish().unwrap();
+}
+
+/// Writes the TUF reference implementation repository to an archive named `name` in `dir`, in the
+/// format given by the extension of `name`.
+fn write_archive(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if name.ends_with(".zip") {
+        write_zip(&path);
+    } else if name.ends_with(".tar.gz") {
+        let file = std::fs::File::create(&path).unwrap();
+        write_tar(GzEncoder::new(file, Compression::default()))
+            .finish()
+            .unwrap();
+    } else {
+        write_tar(std::fs::File::create(&path).unwrap());
+    }
+    path
+}
+
+/// Test that a repository can be loaded, and its targets read, from each kind of archive.
+#[tokio::test]
+async fn archive_transport_load() {
+    let dir = TempDir::new().unwrap();
+    let root = std::fs::read(reference_impl().join("metadata").join("1.root.json")).unwrap();
+    for name in ["repo.tar", "repo.tar.gz", "repo.zip"].iter().copied() {
+        let transport = ArchiveTransport::open(write_archive(dir.path(), name)).unwrap();
+        let repo = RepositoryLoader::new(
+            &root,
+            Url::parse("archive:///metadata/").unwrap(),
+            Url::parse("archive:///targets/").unwrap(),
+        )
+        .transport(transport)
+        .load()
+        .await
+        .unwrap();
+        for target in ["file1.txt", "file2.txt", "file3.txt"].iter().copied() {
+            let target_name = TargetName::new(target).unwrap();
+            let read = repo.read_target(&target_name).await.unwrap().unwrap();
+            assert_eq!(
+                read_to_end(read).await,
+                std::fs::read(reference_impl().join("targets").join(target)).unwrap(),
+                "{} in {}",
+                target,
+                name
+            );
+        }
+    }
+}
+
+/// Test that an `ArchiveTransport` reports missing files and unsupported schemes, and resumes
+/// reads from an offset.
+#[tokio::test]
+async fn archive_transport_errors_and_range() {
+    let dir = TempDir::new().unwrap();
+    for name in ["repo.tar", "repo.tar.gz", "repo.zip"].iter().copied() {
+        let transport = ArchiveTransport::open(write_archive(dir.path(), name)).unwrap();
+
+        let url = Url::parse("archive:///metadata/2.root.json").unwrap();
+        let error = transport.fetch(url).await.err().unwrap();
+        assert_eq!(error.kind(), TransportErrorKind::FileNotFound, "{}", name);
+
+        let url = Url::parse("archive:///metadata/").unwrap();
+        let error = transport.fetch(url).await.err().unwrap();
+        assert_eq!(error.kind(), TransportErrorKind::FileNotFound, "{}", name);
+
+        let url = Url::from_file_path(reference_impl().join("targets").join("file1.txt")).unwrap();
+        let error = transport.fetch(url).await.err().unwrap();
+        assert_eq!(
+            error.kind(),
+            TransportErrorKind::UnsupportedUrlScheme,
+            "{}",
+            name
+        );
+
+        let url = Url::parse("archive:///targets/file1.txt").unwrap();
+        let read = transport.fetch_range(url, 11).await.unwrap();
+        assert_eq!(read_to_end(read).await, b"example target file.", "{}", name);
+    }
+}
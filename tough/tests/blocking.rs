@@ -0,0 +1,132 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "blocking")]
+
+use chrono::Utc;
+use std::convert::TryFrom;
+use std::io::Read;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use test_utils::{days, dir_url, test_data};
+use tough::blocking::{RepositoryEditor, RepositoryLoader};
+use tough::editor::signed::PathExists;
+use tough::key_source::{KeySource, LocalKeySource};
+use tough::{Prefix, TargetName};
+
+mod test_utils;
+
+fn reference_impl() -> PathBuf {
+    test_data().join("tuf-reference-impl")
+}
+
+/// Signs a repository for the `simple-rsa` root that lists `file3.txt` of the TUF reference
+/// implementation, and writes it to `metadata_dir` and `targets_dir`.
+fn write_repository(metadata_dir: &Path, targets_dir: &Path) {
+    let one = NonZeroU64::new(1).unwrap();
+    let keys: Vec<Box<dyn KeySource>> = vec![Box::new(LocalKeySource {
+        path: test_data().join("snakeoil.pem"),
+    })];
+    let mut editor =
+        RepositoryEditor::new(test_data().join("simple-rsa").join("root.json")).unwrap();
+    editor
+        .targets_version(one)
+        .unwrap()
+        .targets_expires(Utc::now() + days(7))
+        .unwrap()
+        .snapshot_version(one)
+        .snapshot_expires(Utc::now() + days(7))
+        .timestamp_version(one)
+        .timestamp_expires(Utc::now() + days(7))
+        .add_target_path(reference_impl().join("targets").join("file3.txt"))
+        .unwrap();
+    let signed = editor.sign(&keys).unwrap();
+    signed.write(metadata_dir).unwrap();
+    signed
+        .copy_targets(
+            reference_impl().join("targets"),
+            targets_dir,
+            PathExists::Fail,
+        )
+        .unwrap();
+}
+
+fn read_target(repo: &tough::blocking::Repository, name: &str) -> std::io::Result<Vec<u8>> {
+    let mut reader = repo
+        .read_target(&TargetName::new(name).unwrap())
+        .unwrap()
+        .unwrap();
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Test that a repository can be loaded and its targets read and saved without a runtime.
+#[test]
+fn blocking_load_and_read() {
+    let base = reference_impl();
+    let root = std::fs::read(base.join("metadata").join("1.root.json")).unwrap();
+    let repo = RepositoryLoader::new(
+        &root,
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .load()
+    .unwrap();
+
+    assert_eq!(
+        read_target(&repo, "file1.txt").unwrap(),
+        b"This is an example target file."
+    );
+    // file3.txt is listed by a delegated role, which is loaded on demand.
+    assert_eq!(
+        read_target(&repo, "file3.txt").unwrap(),
+        std::fs::read(base.join("targets").join("file3.txt")).unwrap()
+    );
+    let missing = TargetName::new("missing.txt").unwrap();
+    assert!(repo.read_target(&missing).unwrap().is_none());
+
+    let outdir = TempDir::new().unwrap();
+    let file2 = TargetName::new("file2.txt").unwrap();
+    repo.save_target(&file2, outdir.path(), Prefix::None)
+        .unwrap();
+    assert_eq!(
+        std::fs::read(outdir.path().join("file2.txt")).unwrap(),
+        std::fs::read(base.join("targets").join("file2.txt")).unwrap()
+    );
+}
+
+/// Test that a repository written by the blocking editor loads, and that targets which do not
+/// match their metadata fail to read.
+#[test]
+fn blocking_editor_and_verification() {
+    let dir = TempDir::new().unwrap();
+    let metadata_dir = dir.path().join("metadata");
+    let targets_dir = dir.path().join("targets");
+    write_repository(&metadata_dir, &targets_dir);
+    let root = std::fs::read(test_data().join("simple-rsa").join("root.json")).unwrap();
+    let repo = RepositoryLoader::new(&root, dir_url(&metadata_dir), dir_url(&targets_dir))
+        .load()
+        .unwrap();
+    assert_eq!(
+        read_target(&repo, "file3.txt").unwrap(),
+        std::fs::read(reference_impl().join("targets").join("file3.txt")).unwrap()
+    );
+
+    // Replace the contents of the copied target, keeping its length.
+    let target = std::fs::read_dir(&targets_dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let length = std::fs::metadata(&target).unwrap().len();
+    std::fs::write(&target, vec![b'x'; usize::try_from(length).unwrap()]).unwrap();
+    let error = read_target(&repo, "file3.txt").unwrap_err();
+    assert!(error
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<tough::error::Error>()
+        .is_some());
+}
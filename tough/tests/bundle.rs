@@ -0,0 +1,99 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "archive")]
+
+use std::path::Path;
+use tempfile::TempDir;
+use test_utils::{dir_url, read_to_end, test_data};
+use tough::{Repository, RepositoryLoader, TargetName};
+
+mod test_utils;
+
+fn root() -> Vec<u8> {
+    std::fs::read(
+        test_data()
+            .join("tuf-reference-impl")
+            .join("metadata")
+            .join("1.root.json"),
+    )
+    .unwrap()
+}
+
+/// Exports a bundle of the TUF reference implementation repository holding `targets_subset`.
+async fn export(path: &Path, targets_subset: Option<&[&str]>) {
+    let base = test_data().join("tuf-reference-impl");
+    let repo = RepositoryLoader::new(
+        &root(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .load()
+    .await
+    .unwrap();
+    repo.export_bundle(path, targets_subset).await.unwrap();
+}
+
+async fn read_target(repo: &Repository, name: &str) -> Vec<u8> {
+    let name = TargetName::new(name).unwrap();
+    read_to_end(repo.read_target(&name).await.unwrap().unwrap()).await
+}
+
+/// Test that a bundle holds everything needed to load the repository and read its targets,
+/// including targets listed by delegated roles.
+#[tokio::test]
+async fn bundle_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("repo.tar");
+    export(&bundle, None).await;
+
+    let root = root();
+    let repo = RepositoryLoader::from_bundle(&root, &bundle)
+        .unwrap()
+        .load()
+        .await
+        .unwrap();
+    let targets_dir = test_data().join("tuf-reference-impl").join("targets");
+    for name in ["file1.txt", "file2.txt", "file3.txt"].iter().copied() {
+        assert_eq!(
+            read_target(&repo, name).await,
+            std::fs::read(targets_dir.join(name)).unwrap()
+        );
+    }
+}
+
+/// Test that a bundle can hold a subset of the targets.
+#[tokio::test]
+async fn bundle_targets_subset() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("repo.tar");
+    export(&bundle, Some(&["file1.txt"])).await;
+
+    let root = root();
+    let repo = RepositoryLoader::from_bundle(&root, &bundle)
+        .unwrap()
+        .load()
+        .await
+        .unwrap();
+    assert_eq!(
+        read_target(&repo, "file1.txt").await,
+        b"This is an example target file."
+    );
+    let file2 = TargetName::new("file2.txt").unwrap();
+    assert!(repo.read_target(&file2).await.is_err());
+}
+
+/// Test that a bundle is not trusted without a matching root.
+#[tokio::test]
+async fn bundle_untrusted_root() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("repo.tar");
+    export(&bundle, Some(&[])).await;
+
+    let root = std::fs::read(test_data().join("simple-rsa").join("root.json")).unwrap();
+    assert!(RepositoryLoader::from_bundle(&root, &bundle)
+        .unwrap()
+        .load()
+        .await
+        .is_err());
+}
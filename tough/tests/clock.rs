@@ -0,0 +1,136 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::sync::{Arc, Mutex};
+use test_utils::{dir_url, test_data};
+use tough::clock::{Clock, TrustedTimePolicy};
+use tough::datastore::{Datastore, MemoryDatastore};
+use tough::error::{Error, Result};
+use tough::{Repository, RepositoryLoader, TargetName};
+
+mod test_utils;
+
+/// A `Clock` whose time is set by the test. Clones share the same time.
+#[derive(Debug, Clone)]
+struct TestClock(Arc<Mutex<DateTime<Utc>>>);
+
+impl TestClock {
+    fn new(time: DateTime<Utc>) -> Self {
+        Self(Arc::new(Mutex::new(time)))
+    }
+
+    fn set(&self, time: DateTime<Utc>) {
+        *self.0.lock().unwrap() = time;
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// The time at which all of the tuf-reference-impl metadata expires.
+fn expiry() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap()
+}
+
+async fn load(
+    clock: &TestClock,
+    datastore: &MemoryDatastore,
+    policy: TrustedTimePolicy,
+) -> Result<Repository> {
+    let base = test_data().join("tuf-reference-impl");
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .clock(clock.clone())
+    .datastore_backend(datastore.clone())
+    .trusted_time_policy(policy)
+    .load()
+    .await
+}
+
+/// Test that metadata is trusted up to and including its expiration time, and that targets can
+/// no longer be read once it has passed.
+#[tokio::test]
+async fn expires_at_clock_time() {
+    let clock = TestClock::new(expiry());
+    let datastore = MemoryDatastore::new();
+    let repo = load(&clock, &datastore, TrustedTimePolicy::Strict)
+        .await
+        .unwrap();
+
+    clock.set(expiry() + Duration::seconds(1));
+    let file1 = TargetName::new("file1.txt").unwrap();
+    assert!(matches!(
+        repo.read_target(&file1).await,
+        Err(Error::ExpiredMetadata { .. })
+    ));
+    assert!(matches!(
+        load(&clock, &datastore, TrustedTimePolicy::Strict).await,
+        Err(Error::ExpiredMetadata { .. })
+    ));
+}
+
+/// Test that a clock that is earlier than the latest known time is rejected by the `Strict`
+/// policy, and raised to the latest known time by the `LowerBound` policy.
+#[tokio::test]
+async fn clock_stepped_backward() {
+    let clock = TestClock::new(expiry() - Duration::days(1));
+    let datastore = MemoryDatastore::new();
+    load(&clock, &datastore, TrustedTimePolicy::Strict)
+        .await
+        .unwrap();
+
+    // A device without a real-time clock starts at the Unix epoch.
+    clock.set(Utc.timestamp_opt(0, 0).unwrap());
+    assert!(matches!(
+        load(&clock, &datastore, TrustedTimePolicy::Strict).await,
+        Err(Error::SystemTimeSteppedBackward { .. })
+    ));
+    let policy = TrustedTimePolicy::LowerBound {
+        timestamp_lifetime: Duration::days(365),
+    };
+    load(&clock, &datastore, policy).await.unwrap();
+    assert_eq!(
+        datastore.latest_known_time().await.unwrap(),
+        Some(expiry() - Duration::days(1))
+    );
+}
+
+/// Test that the `LowerBound` policy derives the latest known time from signed timestamp metadata
+/// when a device without a real-time clock starts at the Unix epoch with an empty datastore.
+#[tokio::test]
+async fn fresh_device_at_epoch() {
+    let epoch = Utc.timestamp_opt(0, 0).unwrap();
+    let clock = TestClock::new(epoch);
+    let datastore = MemoryDatastore::new();
+    let policy = TrustedTimePolicy::LowerBound {
+        timestamp_lifetime: Duration::days(7),
+    };
+    let repo = load(&clock, &datastore, policy).await.unwrap();
+    assert_eq!(
+        datastore.latest_known_time().await.unwrap(),
+        Some(expiry() - Duration::days(7))
+    );
+
+    // The clock is still at the epoch, but metadata is checked against the lower bound.
+    let file1 = TargetName::new("file1.txt").unwrap();
+    assert!(repo.read_target(&file1).await.unwrap().is_some());
+
+    // Metadata that expires before the lower bound is detected as expired.
+    let datastore = MemoryDatastore::new();
+    let policy = TrustedTimePolicy::LowerBound {
+        timestamp_lifetime: Duration::seconds(-1),
+    };
+    assert!(matches!(
+        load(&clock, &datastore, policy).await,
+        Err(Error::ExpiredMetadata { .. })
+    ));
+}
@@ -0,0 +1,138 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use chrono::{Duration, Utc};
+use fs2::FileExt;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use test_utils::{dir_url, test_data};
+use tough::datastore::{Datastore, FilesystemDatastore, MemoryDatastore};
+use tough::error::Error;
+use tough::{Repository, RepositoryLoader};
+
+mod test_utils;
+
+/// A `Datastore` that keeps files in memory and records each file that is written.
+#[derive(Debug, Clone, Default)]
+struct RecordingDatastore {
+    inner: MemoryDatastore,
+    written: Arc<Mutex<Vec<String>>>,
+}
+
+#[tough::async_trait]
+impl Datastore for RecordingDatastore {
+    async fn bytes(
+        &self,
+        file: &str,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.inner.bytes(file).await
+    }
+
+    async fn write(
+        &self,
+        file: &str,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.written.lock().unwrap().push(file.to_owned());
+        self.inner.write(file, bytes).await
+    }
+
+    async fn remove(
+        &self,
+        file: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.inner.remove(file).await
+    }
+}
+
+async fn load<D: Datastore + 'static>(datastore: D) -> tough::error::Result<Repository> {
+    let base = test_data().join("tuf-reference-impl");
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .datastore_backend(datastore)
+    .load()
+    .await
+}
+
+/// Test that trusted metadata is kept in a `MemoryDatastore`, and can be loaded again from it.
+#[tokio::test]
+async fn memory_datastore() {
+    let datastore = MemoryDatastore::new();
+    load(datastore.clone()).await.unwrap();
+    for file in ["timestamp.json", "snapshot.json", "targets.json"] {
+        assert!(datastore.bytes(file).await.unwrap().is_some(), "{}", file);
+    }
+    assert!(datastore.latest_known_time().await.unwrap().is_some());
+    load(datastore).await.unwrap();
+}
+
+/// Test that a custom `Datastore` is used for all metadata and the latest known time.
+#[tokio::test]
+async fn custom_datastore() {
+    let datastore = RecordingDatastore::default();
+    load(datastore.clone()).await.unwrap();
+    let written = datastore.written.lock().unwrap().clone();
+    for file in [
+        "timestamp.json",
+        "snapshot.json",
+        "targets.json",
+        "latest_known_time.json",
+    ] {
+        assert!(written.iter().any(|w| w == file), "{}", file);
+    }
+}
+
+/// Test that loading fails if the datastore knows of a later time than the system time.
+#[tokio::test]
+async fn system_time_stepped_backward() {
+    let datastore = MemoryDatastore::new();
+    datastore
+        .set_latest_known_time(Utc::now() + Duration::days(1))
+        .await
+        .unwrap();
+    assert!(matches!(
+        load(datastore).await,
+        Err(Error::SystemTimeSteppedBackward { .. })
+    ));
+}
+
+/// Test that a `FilesystemDatastore` leaves only complete files behind, that its lock is an
+/// exclusive lock on a file in the datastore directory, and that a load releases it.
+#[tokio::test]
+async fn filesystem_datastore_lock() {
+    let dir = TempDir::new().unwrap();
+    let datastore = FilesystemDatastore::new(dir.path()).locking(true);
+    let lock_file = || std::fs::File::open(dir.path().join(".lock")).unwrap();
+    let lock = datastore.lock().await.unwrap();
+    assert!(lock.is_some());
+    assert_eq!(
+        lock_file().try_lock_exclusive().unwrap_err().kind(),
+        fs2::lock_contended_error().kind()
+    );
+
+    let load = tokio::spawn(load(datastore.clone()));
+    drop(lock);
+    load.await.unwrap().unwrap();
+    lock_file().try_lock_exclusive().unwrap();
+
+    let mut files = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            ".lock",
+            "latest_known_time.json",
+            "snapshot.json",
+            "targets.json",
+            "timestamp.json",
+        ]
+    );
+}
@@ -0,0 +1,227 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use futures_core::Stream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use test_utils::{dir_url, read_to_end, test_data};
+use tough::datastore::{Datastore, MemoryDatastore};
+use tough::{
+    async_trait, Bytes, FilesystemTransport, Repository, RepositoryLoader, TargetName, Transport,
+    TransportError,
+};
+use url::Url;
+
+mod test_utils;
+
+/// A `Transport` that records the name of every file it is asked to fetch.
+#[derive(Debug, Clone, Default)]
+struct RecordingTransport {
+    fetched: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingTransport {
+    fn count(&self, filename: &str) -> usize {
+        self.fetched
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|fetched| fetched.as_str() == filename)
+            .count()
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn fetch(
+        &self,
+        url: Url,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>, TransportError>
+    {
+        let filename = url.path_segments().unwrap().next_back().unwrap().to_owned();
+        self.fetched.lock().unwrap().push(filename);
+        FilesystemTransport.fetch(url).await
+    }
+}
+
+/// A `Datastore` that keeps files in memory and records the name of every file that is read.
+#[derive(Debug, Clone, Default)]
+struct RecordingDatastore {
+    inner: MemoryDatastore,
+    read: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingDatastore {
+    fn count(&self, filename: &str) -> usize {
+        self.read
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|read| read.as_str() == filename)
+            .count()
+    }
+}
+
+#[async_trait]
+impl Datastore for RecordingDatastore {
+    async fn bytes(
+        &self,
+        file: &str,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.read.lock().unwrap().push(file.to_owned());
+        self.inner.bytes(file).await
+    }
+
+    async fn write(
+        &self,
+        file: &str,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.inner.write(file, bytes).await
+    }
+
+    async fn remove(
+        &self,
+        file: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.inner.remove(file).await
+    }
+}
+
+async fn load<D: Datastore + 'static>(
+    repo: &str,
+    transport: RecordingTransport,
+    datastore: D,
+) -> Repository {
+    let base = test_data().join(repo);
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .transport(transport)
+    .datastore_backend(datastore)
+    .load()
+    .await
+    .unwrap()
+}
+
+async fn load_tuf_reference_impl(transport: RecordingTransport, datastore: &TempDir) -> Repository {
+    let base = test_data().join("tuf-reference-impl");
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .transport(transport)
+    .datastore(datastore.path())
+    .load()
+    .await
+    .unwrap()
+}
+
+/// Test that delegated roles are only fetched when a target lookup walks into them.
+#[tokio::test]
+async fn delegated_roles_are_loaded_on_demand() {
+    let transport = RecordingTransport::default();
+    let datastore = TempDir::new().unwrap();
+    let repo = load_tuf_reference_impl(transport.clone(), &datastore).await;
+    assert_eq!(transport.count("role1.json"), 0);
+    assert_eq!(transport.count("role2.json"), 0);
+
+    // file1.txt is listed by the top-level targets role, so no delegated role is needed.
+    let file1 = TargetName::new("file1.txt").unwrap();
+    assert!(repo.read_target(&file1).await.unwrap().is_some());
+    assert_eq!(transport.count("role1.json"), 0);
+
+    // file3.txt is delegated to role1, which delegates nothing that matches to role2.
+    let file3 = TargetName::new("file3.txt").unwrap();
+    assert_eq!(
+        read_to_end(repo.read_target(&file3).await.unwrap().unwrap()).await,
+        &b"This is role1's target file."[..]
+    );
+    assert_eq!(transport.count("role1.json"), 1);
+    assert_eq!(transport.count("role2.json"), 0);
+    assert!(datastore.path().join("role1.json").is_file());
+
+    // The verified role is now kept in memory.
+    assert!(repo.read_target(&file3).await.unwrap().is_some());
+    assert_eq!(transport.count("role1.json"), 1);
+
+    // Targets that no role lists are not found.
+    let missing = TargetName::new("missing.txt").unwrap();
+    assert!(repo.read_target(&missing).await.unwrap().is_none());
+}
+
+/// Test that `all_targets` loads the entire delegation tree.
+#[tokio::test]
+async fn all_targets_loads_every_delegated_role() {
+    let transport = RecordingTransport::default();
+    let datastore = TempDir::new().unwrap();
+    let mut repo = load_tuf_reference_impl(transport.clone(), &datastore).await;
+    assert!(repo.delegated_role("role2").is_none());
+
+    let file3 = TargetName::new("file3.txt").unwrap();
+    assert!(repo
+        .all_targets()
+        .await
+        .unwrap()
+        .any(|(name, _)| name == &file3));
+    assert_eq!(transport.count("role1.json"), 1);
+    assert_eq!(transport.count("role2.json"), 1);
+    assert!(repo.delegated_role("role2").is_some());
+}
+
+/// Test that a delegated role is only fetched, read from the datastore and verified once per
+/// `Repository`, however many lookups walk into it, and that clones share it.
+#[tokio::test]
+async fn delegated_roles_are_verified_once() {
+    let transport = RecordingTransport::default();
+    let datastore = RecordingDatastore::default();
+    let repo = load("tuf-reference-impl", transport.clone(), datastore.clone()).await;
+
+    let file3 = TargetName::new("file3.txt").unwrap();
+    read_to_end(repo.read_target(&file3).await.unwrap().unwrap()).await;
+    assert!(repo.read_target(&file3).await.unwrap().is_some());
+    assert!(repo.clone().find_target(&file3).await.unwrap().is_some());
+    assert_eq!(transport.count("role1.json"), 1);
+    assert_eq!(datastore.count("role1.json"), 1);
+
+    // A new `Repository` trusts the copy in the datastore instead of fetching it again.
+    let repo = load("tuf-reference-impl", transport.clone(), datastore.clone()).await;
+    assert!(repo.read_target(&file3).await.unwrap().is_some());
+    assert_eq!(transport.count("role1.json"), 1);
+    assert_eq!(datastore.count("role1.json"), 2);
+}
+
+/// Test that a copy of a delegated role in the datastore is only trusted if it matches the length
+/// and hashes listed in snapshot metadata, even if its version and signatures are right.
+#[tokio::test]
+async fn datastore_roles_are_checked_against_snapshot() {
+    let transport = RecordingTransport::default();
+    let datastore = MemoryDatastore::new();
+    let data3 = TargetName::new("../delegated/foo/../subdir/data3.txt").unwrap();
+    let repo = load("safe-target-paths", transport.clone(), datastore.clone()).await;
+    assert!(repo.find_target(&data3).await.unwrap().is_some());
+    assert_eq!(transport.count("delegated.json"), 1);
+
+    let repo = load("safe-target-paths", transport.clone(), datastore.clone()).await;
+    assert!(repo.find_target(&data3).await.unwrap().is_some());
+    assert_eq!(transport.count("delegated.json"), 1);
+
+    // Reformatting the role keeps its signatures valid, but changes its hash.
+    let role: serde_json::Value =
+        serde_json::from_slice(&datastore.bytes("delegated.json").await.unwrap().unwrap()).unwrap();
+    datastore
+        .write("delegated.json", &serde_json::to_vec_pretty(&role).unwrap())
+        .await
+        .unwrap();
+    let repo = load("safe-target-paths", transport.clone(), datastore.clone()).await;
+    assert!(repo.find_target(&data3).await.unwrap().is_some());
+    assert_eq!(transport.count("delegated.json"), 2);
+}
@@ -0,0 +1,88 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use chrono::{DateTime, TimeZone, Utc};
+use test_utils::{days, dir_url, test_data};
+use tough::clock::Clock;
+use tough::schema::RoleType;
+use tough::{Repository, RepositoryLoader, RoleExpiration};
+
+mod test_utils;
+
+/// A `Clock` that is stopped at a fixed time.
+#[derive(Debug, Clone, Copy)]
+struct FixedClock(DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// The time at which all of the tuf-reference-impl metadata expires.
+fn expiry() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap()
+}
+
+async fn load(now: DateTime<Utc>) -> Repository {
+    let base = test_data().join("tuf-reference-impl");
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .clock(FixedClock(now))
+    .load()
+    .await
+    .unwrap()
+}
+
+fn summary(expirations: &[RoleExpiration]) -> Vec<(&str, RoleType, &str)> {
+    expirations
+        .iter()
+        .map(|e| (e.role.as_str(), e.role_type, e.signing_role.as_str()))
+        .collect()
+}
+
+/// Test that expirations are reported for the top-level roles and for delegated roles once they
+/// are loaded.
+#[tokio::test]
+async fn expirations() {
+    let mut repo = load(expiry() - days(30)).await;
+    let expirations = repo.expirations();
+    assert_eq!(
+        summary(&expirations),
+        [
+            ("root", RoleType::Root, "root"),
+            ("timestamp", RoleType::Timestamp, "root"),
+            ("snapshot", RoleType::Snapshot, "root"),
+            ("targets", RoleType::Targets, "root"),
+        ]
+    );
+    assert!(expirations
+        .iter()
+        .all(|e| e.expires == expiry() && e.version.get() == 1));
+
+    repo.load_delegated_targets().await.unwrap();
+    assert_eq!(
+        summary(&repo.expirations())[4..],
+        [
+            ("role1", RoleType::DelegatedTargets, "targets"),
+            ("role2", RoleType::DelegatedTargets, "role1"),
+        ]
+    );
+}
+
+/// Test that only roles which expire within the window are reported.
+#[tokio::test]
+async fn expiring_within() {
+    let mut repo = load(expiry() - days(30)).await;
+    repo.load_delegated_targets().await.unwrap();
+
+    assert!(repo.expiring_within(days(7)).await.unwrap().is_empty());
+    assert_eq!(repo.expiring_within(days(31)).await.unwrap().len(), 6);
+    assert!(repo.expiring_before(expiry()).is_empty());
+    assert_eq!(repo.expiring_before(expiry() + days(1)).len(), 6);
+}
@@ -0,0 +1,130 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::num::NonZeroU64;
+use std::path::Path;
+use tempfile::TempDir;
+use test_utils::{dir_url, later, read_to_end, test_data, write_repo};
+use tough::editor::targets::TargetsEditor;
+use tough::schema::{HashAlgorithm, Target};
+use tough::{Repository, RepositoryLoader, TargetName};
+
+mod test_utils;
+
+/// Writes a signed repository to `outdir` listing file4.txt with the digests for `algorithms`, and
+/// returns its target. The root uses consistent snapshots, so the target is written with its first
+/// digest prepended.
+async fn write_file4(outdir: &Path, algorithms: &[HashAlgorithm]) -> Target {
+    let targets_indir = test_data().join("targets");
+    write_repo(outdir, 1, &targets_indir, &["file4.txt"], algorithms).await;
+    Target::from_path_with_hashes(targets_indir.join("file4.txt"), algorithms)
+        .await
+        .unwrap()
+}
+
+async fn load(dir: &Path) -> Repository {
+    RepositoryLoader::new(
+        &tokio::fs::read(test_data().join("simple-rsa").join("root.json"))
+            .await
+            .unwrap(),
+        dir_url(dir.join("metadata")),
+        dir_url(dir.join("targets")),
+    )
+    .load()
+    .await
+    .unwrap()
+}
+
+async fn file4() -> Vec<u8> {
+    tokio::fs::read(test_data().join("targets").join("file4.txt"))
+        .await
+        .unwrap()
+}
+
+/// Test that a target listed only with a SHA 512 digest can be fetched.
+#[tokio::test]
+async fn sha512_only() {
+    let repo_dir = TempDir::new().unwrap();
+    let target = write_file4(repo_dir.path(), &[HashAlgorithm::Sha512]).await;
+    assert!(target.hashes.sha256.is_none());
+    let sha512 = hex::encode(target.hashes.sha512.as_ref().unwrap());
+    assert!(repo_dir
+        .path()
+        .join("targets")
+        .join(format!("{sha512}.file4.txt"))
+        .is_file());
+
+    let repo = load(repo_dir.path()).await;
+    let name = TargetName::new("file4.txt").unwrap();
+    assert_eq!(
+        repo.find_target(&name).await.unwrap().unwrap().hashes,
+        target.hashes
+    );
+    assert_eq!(
+        read_to_end(repo.read_target(&name).await.unwrap().unwrap()).await,
+        file4().await
+    );
+}
+
+/// Test that a consistent snapshot target can be stored under any of its listed digests.
+#[tokio::test]
+async fn consistent_filename_uses_any_hash() {
+    let repo_dir = TempDir::new().unwrap();
+    let target = write_file4(
+        repo_dir.path(),
+        &[HashAlgorithm::Sha256, HashAlgorithm::Sha512],
+    )
+    .await;
+    let targets_dir = repo_dir.path().join("targets");
+    let sha256 = hex::encode(target.hashes.sha256.as_ref().unwrap());
+    let sha512 = hex::encode(target.hashes.sha512.as_ref().unwrap());
+    tokio::fs::rename(
+        targets_dir.join(format!("{sha256}.file4.txt")),
+        targets_dir.join(format!("{sha512}.file4.txt")),
+    )
+    .await
+    .unwrap();
+
+    let repo = load(repo_dir.path()).await;
+    let name = TargetName::new("file4.txt").unwrap();
+    assert_eq!(
+        read_to_end(repo.read_target(&name).await.unwrap().unwrap()).await,
+        file4().await
+    );
+}
+
+/// Test that a target can't be created without any hash algorithms, since no client could verify
+/// it.
+#[tokio::test]
+async fn no_hash_algorithms() {
+    let path = test_data().join("targets").join("file4.txt");
+    assert!(Target::from_path_with_hashes(&path, &[]).await.is_err());
+
+    let mut editor = TargetsEditor::new("targets");
+    editor.hash_algorithms(&[]);
+    assert!(editor.add_target_path(&path).await.is_err());
+}
+
+/// Test that `TargetsEditor` lists the chosen hash algorithms for targets added by path.
+#[tokio::test]
+async fn targets_editor_hash_algorithms() {
+    let path = test_data().join("targets").join("file4.txt");
+    let mut editor = TargetsEditor::new("targets");
+    editor
+        .version(NonZeroU64::new(1).unwrap())
+        .expires(later())
+        .hash_algorithms(&[HashAlgorithm::Sha512])
+        .add_target_path(&path)
+        .await
+        .unwrap();
+    let targets = editor.build_targets().unwrap();
+    let target = &targets.targets.targets[&TargetName::new("file4.txt").unwrap()];
+    assert!(target.hashes.sha256.is_none());
+    assert_eq!(
+        target.hashes,
+        Target::from_path_with_hashes(&path, &[HashAlgorithm::Sha512])
+            .await
+            .unwrap()
+            .hashes
+    );
+}
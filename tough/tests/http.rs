@@ -5,8 +5,21 @@ mod test_utils;
 mod http_happy {
     use crate::test_utils::{read_to_end, test_data};
     use httptest::{matchers::*, responders::*, Expectation, Server};
+    use reqwest::header::{HeaderName, HeaderValue};
+    use std::collections::VecDeque;
     use std::str::FromStr;
-    use tough::{DefaultTransport, HttpTransport, RepositoryLoader, TargetName, Transport};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    #[cfg(feature = "rustls-tls")]
+    use tough::TlsBackend;
+    use tough::{
+        CachingTransport, ConditionalFetch, CredentialProvider, Credentials, DefaultTransport,
+        HttpTransport, HttpTransportBuilder, RepositoryLoader, TargetName, Transport,
+        TransportError, Validators,
+    };
     use url::Url;
 
     /// Set an expectation in a test HTTP server which serves a file from `tuf-reference-impl`.
@@ -44,6 +57,466 @@ mod http_happy {
         run_http_test(DefaultTransport::default()).await;
     }
 
+    /// A local HTTP server that answers requests with the queued `responses`, or with `hello` once
+    /// they run out, keeping connections open, and records the connections it accepts and the
+    /// requests it receives.
+    struct KeepAliveServer {
+        addr: std::net::SocketAddr,
+        connections: Arc<AtomicUsize>,
+        requests: Arc<Mutex<Vec<String>>>,
+        responses: Arc<Mutex<VecDeque<&'static str>>>,
+    }
+
+    impl KeepAliveServer {
+        async fn bind() -> (Self, TcpListener) {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let server = Self {
+                addr: listener.local_addr().unwrap(),
+                connections: Arc::default(),
+                requests: Arc::default(),
+                responses: Arc::default(),
+            };
+            (server, listener)
+        }
+
+        async fn run() -> Self {
+            let (server, listener) = Self::bind().await;
+            let connections = Arc::clone(&server.connections);
+            let requests = Arc::clone(&server.requests);
+            let responses = Arc::clone(&server.responses);
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    connections.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(Self::serve(
+                        stream,
+                        Arc::clone(&requests),
+                        Arc::clone(&responses),
+                    ));
+                }
+            });
+            server
+        }
+
+        /// Runs the server over TLS with the certificate in `tests/data/tls`, requiring a client
+        /// certificate if `mutual` is set.
+        #[cfg(feature = "rustls-tls")]
+        async fn run_tls(mutual: bool) -> Self {
+            use tokio_rustls::rustls::server::AllowAnyAuthenticatedClient;
+            use tokio_rustls::rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+
+            let tls = test_data().join("tls");
+            let read_certs = |name: &str| {
+                let pem = std::fs::read(tls.join(name)).unwrap();
+                rustls_pemfile::certs(&mut pem.as_slice())
+                    .unwrap()
+                    .into_iter()
+                    .map(Certificate)
+                    .collect::<Vec<_>>()
+            };
+            let key = std::fs::read(tls.join("server.key")).unwrap();
+            let key = rustls_pemfile::pkcs8_private_keys(&mut key.as_slice())
+                .unwrap()
+                .remove(0);
+            let config = ServerConfig::builder().with_safe_defaults();
+            let config = if mutual {
+                let mut roots = RootCertStore::empty();
+                for certificate in read_certs("ca.pem") {
+                    roots.add(&certificate).unwrap();
+                }
+                config.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            } else {
+                config.with_no_client_auth()
+            };
+            let config = config
+                .with_single_cert(read_certs("server.pem"), PrivateKey(key))
+                .unwrap();
+            let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+            let (server, listener) = Self::bind().await;
+            let connections = Arc::clone(&server.connections);
+            let requests = Arc::clone(&server.requests);
+            let responses = Arc::clone(&server.responses);
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    connections.fetch_add(1, Ordering::SeqCst);
+                    let acceptor = acceptor.clone();
+                    let requests = Arc::clone(&requests);
+                    let responses = Arc::clone(&responses);
+                    tokio::spawn(async move {
+                        // A failed handshake closes the connection.
+                        if let Ok(stream) = acceptor.accept(stream).await {
+                            Self::serve(stream, requests, responses).await;
+                        }
+                    });
+                }
+            });
+            server
+        }
+
+        async fn serve<S>(
+            stream: S,
+            requests: Arc<Mutex<Vec<String>>>,
+            responses: Arc<Mutex<VecDeque<&'static str>>>,
+        ) where
+            S: AsyncRead + AsyncWrite + Unpin,
+        {
+            let mut stream = BufReader::new(stream);
+            let mut line = String::new();
+            loop {
+                // Read the request head, up to the blank line that ends it.
+                let mut head = String::new();
+                loop {
+                    line.clear();
+                    if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line.to_lowercase());
+                }
+                requests.lock().unwrap().push(head);
+                let response = responses
+                    .lock()
+                    .unwrap()
+                    .pop_front()
+                    .unwrap_or("HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello");
+                if stream.write_all(response.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        async fn fetch(&self, transport: &dyn Transport, host: &str, path: &str) -> String {
+            let url = Url::parse(&format!("http://{host}:{}/{path}", self.addr.port())).unwrap();
+            let stream = transport.fetch(url).await.unwrap();
+            assert_eq!(read_to_end(stream).await, &b"hello"[..]);
+            self.requests.lock().unwrap().pop().unwrap()
+        }
+
+        fn respond(&self, response: &'static str) {
+            self.responses.lock().unwrap().push_back(response);
+        }
+
+        fn request_count(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+
+        /// Fetches a file that fails to download, returning the error.
+        async fn fetch_err(&self, transport: &HttpTransport) -> TransportError {
+            use futures::StreamExt;
+
+            let url =
+                Url::parse(&format!("http://127.0.0.1:{}/file.txt", self.addr.port())).unwrap();
+            let mut stream = transport.fetch(url).await.unwrap();
+            match stream.next().await {
+                Some(Err(error)) => error,
+                _ => panic!("fetch succeeded"),
+            }
+        }
+    }
+
+    /// Test that fetches through an `HttpTransport`, and its clones, reuse one connection.
+    #[tokio::test]
+    async fn test_http_transport_reuses_connections() {
+        let server = KeepAliveServer::run().await;
+        let transport = HttpTransportBuilder::new()
+            .pool_max_idle_per_host(1)
+            .build();
+        let clone = transport.clone();
+        for transport in &[&transport, &transport, &clone] {
+            server.fetch(*transport, "127.0.0.1", "file.txt").await;
+        }
+        assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+    }
+
+    /// A `CredentialProvider` that returns a new token each time it is asked.
+    #[derive(Debug, Default)]
+    struct RotatingToken(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl CredentialProvider for RotatingToken {
+        async fn credentials(
+            &self,
+            _url: &Url,
+        ) -> Result<Credentials, Box<dyn std::error::Error + Send + Sync>> {
+            let count = self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Credentials::Bearer(format!("token-{count}")))
+        }
+    }
+
+    /// Test that headers and credentials are only sent to the host they are scoped to.
+    #[tokio::test]
+    async fn test_http_transport_host_scoped_authentication() {
+        let server = KeepAliveServer::run().await;
+        let port = server.addr.port();
+        let transport = HttpTransportBuilder::new()
+            .header(
+                "127.0.0.1",
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_static("secret"),
+            )
+            .bearer_auth(format!("127.0.0.1:{port}"), "static-token")
+            .basic_auth(format!("127.0.0.1:{}", port + 1), "user", None)
+            .credential_provider("localhost", Arc::new(RotatingToken::default()))
+            .build();
+
+        let request = server.fetch(&transport, "127.0.0.1", "file.txt").await;
+        assert!(request.contains("x-api-key: secret"), "{}", request);
+        assert!(
+            request.contains("authorization: bearer static-token"),
+            "{}",
+            request
+        );
+        assert!(!request.contains("basic"), "{}", request);
+
+        for count in 0..2 {
+            let request = server.fetch(&transport, "localhost", "file.txt").await;
+            assert!(!request.contains("x-api-key"), "{}", request);
+            assert!(!request.contains("static-token"), "{}", request);
+            assert!(
+                request.contains(&format!("authorization: bearer token-{count}")),
+                "{}",
+                request
+            );
+        }
+    }
+
+    /// Test that requests go through the proxy, except to hosts in the no-proxy list.
+    #[tokio::test]
+    async fn test_http_transport_proxy() {
+        let proxy = KeepAliveServer::run().await;
+        let transport = HttpTransportBuilder::new()
+            .proxy(Url::parse(&format!("http://{}", proxy.addr)).unwrap())
+            .no_proxy(["localhost"])
+            .build();
+
+        let request = proxy.fetch(&transport, "tuf.invalid", "file.txt").await;
+        let proxied = format!("get http://tuf.invalid:{}/file.txt ", proxy.addr.port());
+        assert!(request.starts_with(&proxied), "{}", request);
+        let request = proxy.fetch(&transport, "localhost", "file.txt").await;
+        assert!(request.starts_with("get /file.txt "), "{}", request);
+    }
+
+    /// Test that 429 responses are retried after the time in their `Retry-After` header.
+    #[tokio::test]
+    async fn test_http_transport_retry_after() {
+        let server = KeepAliveServer::run().await;
+        server.respond(
+            "HTTP/1.1 429 Too Many Requests\r\nretry-after: 1\r\ncontent-length: 0\r\n\r\n",
+        );
+        let transport = HttpTransportBuilder::new()
+            .initial_backoff(Duration::from_millis(10))
+            .build();
+        let start = Instant::now();
+        server.fetch(&transport, "127.0.0.1", "file.txt").await;
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.request_count(), 1);
+    }
+
+    /// Test that a `Retry-After` header asking for a wait longer than `max_retry_after` fails the
+    /// fetch instead of sleeping, even when the wait does not fit in a `Duration`.
+    #[tokio::test]
+    async fn test_http_transport_retry_after_too_long() {
+        const DAY: &str =
+            "HTTP/1.1 429 Too Many Requests\r\nretry-after: 86400\r\ncontent-length: 0\r\n\r\n";
+        const HUGE: &str = "HTTP/1.1 429 Too Many Requests\r\nretry-after: 18446744073709551615\r\ncontent-length: 0\r\n\r\n";
+        for (response, deadline) in [
+            (DAY, None),
+            (HUGE, None),
+            (HUGE, Some(Duration::from_secs(5))),
+        ] {
+            let server = KeepAliveServer::run().await;
+            server.respond(response);
+            let mut builder = HttpTransportBuilder::new().max_retry_after(Duration::from_secs(10));
+            if let Some(deadline) = deadline {
+                builder = builder.retry_deadline(deadline);
+            }
+            let transport = builder.build();
+            let start = Instant::now();
+            server.fetch_err(&transport).await;
+            assert!(start.elapsed() < Duration::from_secs(5));
+            assert_eq!(server.request_count(), 1);
+        }
+    }
+
+    /// Test that the retry deadline stops retries that would wait too long.
+    #[tokio::test]
+    async fn test_http_transport_retry_deadline() {
+        let server = KeepAliveServer::run().await;
+        server.respond(
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 60\r\ncontent-length: 0\r\n\r\n",
+        );
+        let transport = HttpTransportBuilder::new()
+            .retry_deadline(Duration::from_secs(5))
+            .build();
+        let start = Instant::now();
+        server.fetch_err(&transport).await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.request_count(), 1);
+    }
+
+    /// Test that a host which keeps failing is not sent requests, through any clone of the
+    /// transport, until the circuit breaker closes again.
+    #[tokio::test]
+    async fn test_http_transport_circuit_breaker() {
+        let server = KeepAliveServer::run().await;
+        for _ in 0..2 {
+            server.respond("HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n");
+        }
+        let transport = HttpTransportBuilder::new()
+            .tries(0)
+            .circuit_breaker(2, Duration::from_millis(500))
+            .build();
+        let clone = transport.clone();
+        server.fetch_err(&transport).await;
+        server.fetch_err(&clone).await;
+        let error = server.fetch_err(&transport).await;
+        assert!(format!("{error:?}").contains("CircuitOpen"), "{:?}", error);
+        assert_eq!(server.request_count(), 2);
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        server.fetch(&clone, "127.0.0.1", "file.txt").await;
+    }
+
+    /// Test that conditional fetches send the validators of the previous response, and that a
+    /// `CachingTransport` serves its cached file when the server responds `304 Not Modified`.
+    #[tokio::test]
+    async fn test_http_transport_conditional_fetch() {
+        let server = KeepAliveServer::run().await;
+        server.respond(
+            "HTTP/1.1 200 OK\r\netag: \"v1\"\r\nlast-modified: Wed, 21 Oct 2015 07:28:00 GMT\r\ncontent-length: 5\r\n\r\nhello",
+        );
+        server.respond("HTTP/1.1 304 Not Modified\r\n\r\n");
+        let url = Url::parse(&format!("http://127.0.0.1:{}/file.txt", server.addr.port())).unwrap();
+        let transport = HttpTransport::default();
+
+        let validators = match transport
+            .fetch_conditional(url.clone(), &Validators::default())
+            .await
+            .unwrap()
+        {
+            ConditionalFetch::Modified { stream, validators } => {
+                assert_eq!(read_to_end(stream).await, &b"hello"[..]);
+                validators
+            }
+            ConditionalFetch::NotModified => panic!("not modified"),
+        };
+        assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+        let request = server.requests.lock().unwrap().pop().unwrap();
+        assert!(!request.contains("if-none-match"), "{}", request);
+
+        let result = transport.fetch_conditional(url, &validators).await.unwrap();
+        assert!(
+            matches!(result, ConditionalFetch::NotModified),
+            "{:?}",
+            result
+        );
+        let request = server.requests.lock().unwrap().pop().unwrap();
+        assert!(request.contains("if-none-match: \"v1\""), "{}", request);
+        assert!(
+            request.contains("if-modified-since: wed, 21 oct 2015 07:28:00 gmt"),
+            "{}",
+            request
+        );
+
+        // Cache a response with an `ETag`, then serve it from the cache on `304 Not Modified`.
+        let cache = tempfile::TempDir::new().unwrap();
+        let transport = CachingTransport::new(transport, cache.path());
+        server.respond("HTTP/1.1 200 OK\r\netag: \"v2\"\r\ncontent-length: 5\r\n\r\nhello");
+        server.respond("HTTP/1.1 304 Not Modified\r\n\r\n");
+        server.fetch(&transport, "127.0.0.1", "file.txt").await;
+        let request = server.fetch(&transport, "127.0.0.1", "file.txt").await;
+        assert!(request.contains("if-none-match: \"v2\""), "{}", request);
+    }
+
+    /// The TLS backends enabled by the features under test.
+    #[cfg(feature = "rustls-tls")]
+    fn tls_backends() -> Vec<TlsBackend> {
+        #[cfg_attr(not(feature = "native-tls"), allow(unused_mut))]
+        let mut backends = vec![TlsBackend::Rustls];
+        #[cfg(feature = "native-tls")]
+        backends.push(TlsBackend::NativeTls);
+        backends
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    fn read_tls_data(name: &str) -> Vec<u8> {
+        std::fs::read(test_data().join("tls").join(name)).unwrap()
+    }
+
+    /// Fetches a file from a TLS `server` without retries, returning whether it succeeded.
+    #[cfg(feature = "rustls-tls")]
+    async fn fetch_tls(server: &KeepAliveServer, builder: HttpTransportBuilder) -> bool {
+        use futures::StreamExt;
+
+        let url = Url::parse(&format!(
+            "https://localhost:{}/file.txt",
+            server.addr.port()
+        ))
+        .unwrap();
+        let mut stream = builder.tries(1).build().fetch(url).await.unwrap();
+        match stream.next().await {
+            Some(Ok(bytes)) => bytes == b"hello"[..],
+            _ => false,
+        }
+    }
+
+    /// Test that a server whose certificate is signed by a private CA is only trusted once the
+    /// CA is added.
+    #[cfg(feature = "rustls-tls")]
+    #[tokio::test]
+    async fn test_http_transport_root_certificates() {
+        let server = KeepAliveServer::run_tls(false).await;
+        for backend in tls_backends() {
+            let builder = HttpTransportBuilder::new().tls_backend(backend);
+            assert!(!fetch_tls(&server, builder.clone()).await, "{:?}", backend);
+            let builder = builder.add_root_certificates(read_tls_data("ca.pem"));
+            assert!(fetch_tls(&server, builder).await, "{:?}", backend);
+        }
+    }
+
+    /// Test that a server which requires a client certificate accepts the configured identity.
+    #[cfg(feature = "rustls-tls")]
+    #[tokio::test]
+    async fn test_http_transport_mutual_tls() {
+        let server = KeepAliveServer::run_tls(true).await;
+        for backend in tls_backends() {
+            let builder = HttpTransportBuilder::new()
+                .tls_backend(backend)
+                .add_root_certificates(read_tls_data("ca.pem"));
+            assert!(!fetch_tls(&server, builder.clone()).await, "{:?}", backend);
+            let builder =
+                builder.client_identity(read_tls_data("client.pem"), read_tls_data("client.key"));
+            assert!(fetch_tls(&server, builder).await, "{:?}", backend);
+        }
+    }
+
+    /// Test that invalid certificates and keys fail to build a transport, and that a transport
+    /// built without checking fails every fetch instead of panicking.
+    #[cfg(feature = "rustls-tls")]
+    #[tokio::test]
+    async fn test_http_transport_invalid_tls_settings() {
+        for backend in tls_backends() {
+            let builder = HttpTransportBuilder::new().tls_backend(backend);
+            let transport = builder
+                .clone()
+                .add_root_certificates("not a certificate")
+                .build();
+            let url = Url::parse("https://example.com/").unwrap();
+            assert!(transport.fetch(url).await.is_err());
+            assert!(builder
+                .clone()
+                .add_root_certificates("not a certificate")
+                .try_build()
+                .is_err());
+            assert!(builder
+                .client_identity(read_tls_data("client.pem"), "not a key")
+                .try_build()
+                .is_err());
+        }
+    }
+
     async fn run_http_test<T: Transport + Send + Sync + 'static>(transport: T) {
         let server = Server::run();
         let repo_dir = test_data().join("tuf-reference-impl");
@@ -51,9 +524,9 @@ mod http_happy {
         server.expect(create_successful_get("metadata/snapshot.json").await);
         server.expect(create_successful_get("metadata/targets.json").await);
         server.expect(create_successful_get("metadata/role1.json").await);
-        server.expect(create_successful_get("metadata/role2.json").await);
         server.expect(create_successful_get("targets/file1.txt").await);
         server.expect(create_successful_get("targets/file2.txt").await);
+        server.expect(create_successful_get("targets/file3.txt").await);
         server.expect(create_unsuccessful_get("metadata/2.root.json"));
         let metadata_base_url = Url::from_str(server.url_str("/metadata").as_str()).unwrap();
         let targets_base_url = Url::from_str(server.url_str("/targets").as_str()).unwrap();
@@ -79,6 +552,12 @@ mod http_happy {
             read_to_end(repo.read_target(&file2).await.unwrap().unwrap()).await,
             &b"This is an another example target file."[..]
         );
+        // file3.txt is listed by role1, which is only fetched when the target is looked up.
+        let file3 = TargetName::new("file3.txt").unwrap();
+        assert_eq!(
+            read_to_end(repo.read_target(&file3).await.unwrap().unwrap()).await,
+            &b"This is role1's target file."[..]
+        );
         assert_eq!(
             repo.targets()
                 .signed
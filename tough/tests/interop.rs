@@ -98,7 +98,7 @@ async fn test_dubious_role_name() {
     let base = test_data().join("dubious-role-names");
     let datastore = TempDir::new().unwrap();
 
-    let repo = RepositoryLoader::new(
+    let mut repo = RepositoryLoader::new(
         &tokio::fs::read(base.join("metadata").join("1.root.json"))
             .await
             .unwrap(),
@@ -109,6 +109,7 @@ async fn test_dubious_role_name() {
     .load()
     .await
     .unwrap();
+    repo.load_delegated_targets().await.unwrap();
 
     // Prove that the role name has path traversal characters.
     let expected_rolename = "../../path/like/dubious";
@@ -0,0 +1,139 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::path::Path;
+use tempfile::TempDir;
+use test_utils::{dir_url, read_to_end, test_data, write_repo};
+use tough::schema::{HashAlgorithm, PathPattern};
+use tough::{Mirror, Prefix, Repository, RepositoryLoader, TargetName};
+
+mod test_utils;
+
+/// Creates a "good" repository and a "broken" copy of it that is missing its timestamp and serves
+/// a corrupted file4.txt.
+async fn create_repos() -> TempDir {
+    let tempdir = TempDir::new().unwrap();
+    let good = tempdir.path().join("good");
+    let broken = tempdir.path().join("broken");
+    write_repo(
+        &good,
+        1,
+        &test_data().join("targets"),
+        &["file4.txt", "file5.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    for dir in ["metadata", "targets"] {
+        tokio::fs::create_dir_all(broken.join(dir)).await.unwrap();
+        let mut entries = tokio::fs::read_dir(good.join(dir)).await.unwrap();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            tokio::fs::copy(entry.path(), broken.join(dir).join(entry.file_name()))
+                .await
+                .unwrap();
+        }
+    }
+    tokio::fs::remove_file(broken.join("metadata").join("timestamp.json"))
+        .await
+        .unwrap();
+
+    // The root uses consistent snapshots, so targets are served with their digest prepended.
+    let mut entries = tokio::fs::read_dir(broken.join("targets")).await.unwrap();
+    while let Some(entry) = entries.next_entry().await.unwrap() {
+        if entry.file_name().to_string_lossy().ends_with(".file4.txt") {
+            tokio::fs::write(entry.path(), "corrupted").await.unwrap();
+        }
+    }
+    tempdir
+}
+
+async fn load(dir: &Path, mirror: Mirror) -> Repository {
+    RepositoryLoader::new(
+        &tokio::fs::read(test_data().join("simple-rsa").join("root.json"))
+            .await
+            .unwrap(),
+        dir_url(dir.join("broken").join("metadata")),
+        dir_url(dir.join("broken").join("targets")),
+    )
+    .mirror(mirror)
+    .load()
+    .await
+    .unwrap()
+}
+
+/// Test that metadata and targets that a mirror fails to serve are fetched from the next mirror,
+/// and that the mirror that served each file is recorded.
+#[tokio::test]
+async fn mirror_fallback() {
+    let tempdir = create_repos().await;
+    let good = tempdir.path().join("good");
+    let repo = load(
+        tempdir.path(),
+        Mirror::new()
+            .metadata_base_url(dir_url(good.join("metadata")))
+            .targets_base_url(dir_url(good.join("targets"))),
+    )
+    .await;
+    assert_eq!(
+        repo.metadata_mirror("timestamp.json"),
+        Some(dir_url(good.join("metadata")))
+    );
+    assert_eq!(
+        repo.metadata_mirror("1.snapshot.json"),
+        Some(dir_url(tempdir.path().join("broken").join("metadata")))
+    );
+
+    // file4.txt fails its hash check on the first mirror, so it is saved from the second.
+    let file4 = TargetName::new("file4.txt").unwrap();
+    let outdir = TempDir::new().unwrap();
+    repo.save_target(&file4, outdir.path(), Prefix::None)
+        .await
+        .unwrap();
+    assert_eq!(
+        tokio::fs::read(outdir.path().join("file4.txt"))
+            .await
+            .unwrap(),
+        tokio::fs::read(test_data().join("targets").join("file4.txt"))
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        repo.target_mirror(&file4),
+        Some(dir_url(good.join("targets")))
+    );
+
+    // file5.txt is intact on the first mirror.
+    let file5 = TargetName::new("file5.txt").unwrap();
+    assert_eq!(
+        read_to_end(repo.read_target(&file5).await.unwrap().unwrap()).await,
+        tokio::fs::read(test_data().join("targets").join("file5.txt"))
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        repo.target_mirror(&file5),
+        Some(dir_url(tempdir.path().join("broken").join("targets")))
+    );
+}
+
+/// Test that a mirror is only used for the targets that match its target paths.
+#[tokio::test]
+async fn mirror_target_paths() {
+    let tempdir = create_repos().await;
+    let good = tempdir.path().join("good");
+    let repo = load(
+        tempdir.path(),
+        Mirror::new()
+            .metadata_base_url(dir_url(good.join("metadata")))
+            .targets_base_url(dir_url(good.join("targets")))
+            .target_paths(vec![PathPattern::new("file5.txt").unwrap()]),
+    )
+    .await;
+
+    let file4 = TargetName::new("file4.txt").unwrap();
+    let outdir = TempDir::new().unwrap();
+    assert!(repo
+        .save_target(&file4, outdir.path(), Prefix::None)
+        .await
+        .is_err());
+    assert!(!outdir.path().join("file4.txt").exists());
+}
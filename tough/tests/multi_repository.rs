@@ -0,0 +1,364 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_json::json;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use test_utils::{dir_url, later, read_to_end, test_data, write_repo};
+use tough::editor::RepositoryEditor;
+use tough::error::Error;
+use tough::key_source::{KeySource, LocalKeySource};
+use tough::multi::MultiRepositoryLoader;
+use tough::schema::{HashAlgorithm, PathPattern, PathSet};
+use tough::TargetName;
+
+mod test_utils;
+
+fn root_path() -> PathBuf {
+    test_data().join("simple-rsa").join("root.json")
+}
+
+/// Creates a "primary" repository with file4.txt, file5.txt and file6.txt, and a "mirror"
+/// repository with the same file4.txt, a different file5.txt, and no file6.txt. Returns the
+/// directory holding both repositories and the trusted metadata directory for the client.
+async fn create_repos() -> (TempDir, PathBuf) {
+    let tempdir = TempDir::new().unwrap();
+    write_repo(
+        &tempdir.path().join("primary"),
+        1,
+        &test_data().join("targets"),
+        &["file4.txt", "file5.txt", "file6.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+
+    let mirror_targets = tempdir.path().join("mirror-targets");
+    tokio::fs::create_dir(&mirror_targets).await.unwrap();
+    tokio::fs::copy(
+        test_data().join("targets").join("file4.txt"),
+        mirror_targets.join("file4.txt"),
+    )
+    .await
+    .unwrap();
+    tokio::fs::write(mirror_targets.join("file5.txt"), "not the same file5")
+        .await
+        .unwrap();
+    write_repo(
+        &tempdir.path().join("mirror"),
+        1,
+        &mirror_targets,
+        &["file4.txt", "file5.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+
+    let metadata_dir = tempdir.path().join("trusted");
+    for name in ["primary", "mirror"] {
+        tokio::fs::create_dir_all(metadata_dir.join(name))
+            .await
+            .unwrap();
+        tokio::fs::copy(root_path(), metadata_dir.join(name).join("root.json"))
+            .await
+            .unwrap();
+    }
+    (tempdir, metadata_dir)
+}
+
+/// Test that targets are only resolved when the mapped repositories agree on them.
+#[tokio::test]
+async fn multi_repository_consensus() {
+    let (tempdir, metadata_dir) = create_repos().await;
+    let map_file = json!({
+        "repositories": {
+            // The first URL for the primary repository doesn't exist, so the second is used.
+            "primary": [
+                dir_url(tempdir.path().join("missing")).as_str(),
+                dir_url(tempdir.path().join("primary")).as_str(),
+            ],
+            "mirror": [dir_url(tempdir.path().join("mirror")).as_str()],
+        },
+        "mapping": [
+            {
+                "paths": ["file5.txt"],
+                "repositories": ["primary", "mirror"],
+                "threshold": 2,
+                "terminating": true,
+            },
+            {
+                "paths": ["*"],
+                "repositories": ["primary", "mirror"],
+                "threshold": 2,
+                "terminating": false,
+            },
+            {
+                "paths": ["*"],
+                "repositories": ["primary"],
+                "threshold": 1,
+                "terminating": false,
+            },
+        ],
+    })
+    .to_string();
+    let repo = MultiRepositoryLoader::new(&map_file, &metadata_dir)
+        .load()
+        .await
+        .unwrap();
+
+    // Both repositories agree on file4.txt.
+    let file4 = TargetName::new("file4.txt").unwrap();
+    assert_eq!(
+        read_to_end(repo.read_target(&file4).await.unwrap().unwrap()).await,
+        tokio::fs::read(test_data().join("targets").join("file4.txt"))
+            .await
+            .unwrap()
+    );
+
+    // The repositories disagree on file5.txt, and its mapping is terminating.
+    let file5 = TargetName::new("file5.txt").unwrap();
+    match repo.find_target(&file5).await {
+        Err(Error::TargetConsensus {
+            agreeing,
+            threshold,
+            disagreeing,
+            ..
+        }) => {
+            assert_eq!(agreeing, 1);
+            assert_eq!(threshold, 2);
+            assert_eq!(disagreeing, vec!["mirror".to_string()]);
+        }
+        result => panic!("expected a consensus error, got {:?}", result),
+    }
+
+    // Only the primary repository has file6.txt, which is enough for the last mapping.
+    let file6 = TargetName::new("file6.txt").unwrap();
+    assert!(repo.find_target(&file6).await.unwrap().is_some());
+
+    let missing = TargetName::new("missing.txt").unwrap();
+    assert!(repo.find_target(&missing).await.unwrap().is_none());
+}
+
+/// Test that a mapping that refers to an unknown repository is rejected.
+#[tokio::test]
+async fn multi_repository_unknown_repository() {
+    let (tempdir, metadata_dir) = create_repos().await;
+    let map_file = json!({
+        "repositories": {
+            "primary": [dir_url(tempdir.path().join("primary")).as_str()],
+        },
+        "mapping": [
+            {
+                "paths": ["*"],
+                "repositories": ["primary", "unknown"],
+                "threshold": 1,
+                "terminating": false,
+            },
+        ],
+    })
+    .to_string();
+    assert!(matches!(
+        MultiRepositoryLoader::new(&map_file, &metadata_dir)
+            .load()
+            .await,
+        Err(Error::MapFileRepositoryMissing { .. })
+    ));
+}
+
+/// Test that a mapping that lists a repository more than once, or requires more repositories
+/// than it lists, is rejected.
+#[tokio::test]
+async fn multi_repository_invalid_threshold() {
+    let (tempdir, metadata_dir) = create_repos().await;
+    let load = |repositories: serde_json::Value, threshold: u64| {
+        let map_file = json!({
+            "repositories": {
+                "primary": [dir_url(tempdir.path().join("primary")).as_str()],
+                "mirror": [dir_url(tempdir.path().join("mirror")).as_str()],
+            },
+            "mapping": [
+                {
+                    "paths": ["*"],
+                    "repositories": repositories,
+                    "threshold": threshold,
+                    "terminating": false,
+                },
+            ],
+        })
+        .to_string();
+        let metadata_dir = metadata_dir.clone();
+        async move {
+            MultiRepositoryLoader::new(&map_file, &metadata_dir)
+                .load()
+                .await
+        }
+    };
+
+    assert!(matches!(
+        load(json!(["primary", "primary"]), 2).await,
+        Err(Error::MapFileDuplicateRepository { .. })
+    ));
+    assert!(matches!(
+        load(json!(["primary", "mirror"]), 3).await,
+        Err(Error::MapFileThresholdUnreachable {
+            threshold: 3,
+            repositories: 2,
+            ..
+        })
+    ));
+}
+
+/// Writes a repository to `outdir` whose targets role delegates every path to a role, and then
+/// removes that role's metadata, so that looking up any target fails.
+async fn write_broken_repo(outdir: &Path) {
+    let keys: Vec<Box<dyn KeySource>> = vec![Box::new(LocalKeySource {
+        path: test_data().join("snakeoil.pem"),
+    })];
+    let one = NonZeroU64::new(1).unwrap();
+    let mut editor = RepositoryEditor::new(root_path()).await.unwrap();
+    editor
+        .targets_version(one)
+        .unwrap()
+        .targets_expires(later())
+        .unwrap()
+        .snapshot_version(one)
+        .snapshot_expires(later())
+        .timestamp_version(one)
+        .timestamp_expires(later())
+        .delegate_role(
+            "delegated",
+            &keys,
+            PathSet::Paths(vec![PathPattern::new("*").unwrap()]),
+            one,
+            later(),
+            one,
+        )
+        .await
+        .unwrap();
+    let metadata_dir = outdir.join("metadata");
+    editor
+        .sign(&keys)
+        .await
+        .unwrap()
+        .write(&metadata_dir)
+        .await
+        .unwrap();
+    tokio::fs::remove_file(metadata_dir.join("1.delegated.json"))
+        .await
+        .unwrap();
+}
+
+/// Test that repositories listing different hash algorithms agree on a target if the digests for
+/// their common algorithms match, that every repository in the agreeing group must agree with
+/// every other one, and that a repository which fails to look up the target only counts as
+/// disagreeing.
+#[tokio::test]
+async fn multi_repository_mixed_hashes_and_errors() {
+    let tempdir = TempDir::new().unwrap();
+    let targets_indir = test_data().join("targets");
+    for (name, algorithms) in [
+        ("sha256", &[HashAlgorithm::Sha256][..]),
+        ("both", &[HashAlgorithm::Sha256, HashAlgorithm::Sha512][..]),
+        ("sha512", &[HashAlgorithm::Sha512][..]),
+    ] {
+        write_repo(
+            &tempdir.path().join(name),
+            1,
+            &targets_indir,
+            &["file4.txt"],
+            algorithms,
+        )
+        .await;
+    }
+    write_broken_repo(&tempdir.path().join("broken")).await;
+    let metadata_dir = tempdir.path().join("trusted");
+    for name in ["sha256", "both", "sha512", "broken"] {
+        tokio::fs::create_dir_all(metadata_dir.join(name))
+            .await
+            .unwrap();
+        tokio::fs::copy(root_path(), metadata_dir.join(name).join("root.json"))
+            .await
+            .unwrap();
+    }
+    let load = |mapping: serde_json::Value| {
+        let map_file = json!({
+            "repositories": {
+                "sha256": [dir_url(tempdir.path().join("sha256")).as_str()],
+                "both": [dir_url(tempdir.path().join("both")).as_str()],
+                "sha512": [dir_url(tempdir.path().join("sha512")).as_str()],
+                "broken": [dir_url(tempdir.path().join("broken")).as_str()],
+            },
+            "mapping": [mapping],
+        })
+        .to_string();
+        let metadata_dir = metadata_dir.clone();
+        async move {
+            MultiRepositoryLoader::new(&map_file, &metadata_dir)
+                .load()
+                .await
+                .unwrap()
+        }
+    };
+    let file4 = TargetName::new("file4.txt").unwrap();
+
+    // The SHA 256 and SHA 512 only repositories each agree with the repository listing both, and
+    // the broken repository doesn't prevent consensus.
+    let repo = load(json!({
+        "paths": ["*"],
+        "repositories": ["broken", "sha256", "sha512", "both"],
+        "threshold": 2,
+        "terminating": false,
+    }))
+    .await;
+    let target = repo.find_target(&file4).await.unwrap().unwrap();
+    assert!(target.hashes.sha256.is_some());
+    assert_eq!(
+        read_to_end(repo.read_target(&file4).await.unwrap().unwrap()).await,
+        tokio::fs::read(targets_indir.join("file4.txt"))
+            .await
+            .unwrap()
+    );
+
+    // The SHA 256 and SHA 512 only repositories have no algorithm in common, so they don't agree
+    // with each other, and no group of three agrees.
+    let repo = load(json!({
+        "paths": ["*"],
+        "repositories": ["sha256", "sha512", "both"],
+        "threshold": 3,
+        "terminating": false,
+    }))
+    .await;
+    match repo.find_target(&file4).await {
+        Err(Error::TargetConsensus { agreeing, .. }) => assert_eq!(agreeing, 2),
+        result => panic!("expected a consensus error, got {:?}", result),
+    }
+
+    // The broken repository counts as disagreeing when every repository is required.
+    let repo = load(json!({
+        "paths": ["*"],
+        "repositories": ["sha256", "both", "broken"],
+        "threshold": 3,
+        "terminating": false,
+    }))
+    .await;
+    match repo.find_target(&file4).await {
+        Err(Error::TargetConsensus {
+            agreeing,
+            disagreeing,
+            ..
+        }) => {
+            assert_eq!(agreeing, 2);
+            assert_eq!(disagreeing, vec!["broken".to_string()]);
+        }
+        result => panic!("expected a consensus error, got {:?}", result),
+    }
+
+    // With no other repositories to consult, the broken repository's error is returned.
+    let repo = load(json!({
+        "paths": ["*"],
+        "repositories": ["broken"],
+        "threshold": 1,
+        "terminating": false,
+    }))
+    .await;
+    assert!(repo.find_target(&file4).await.is_err());
+}
@@ -0,0 +1,125 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use test_utils::{dir_url, read_to_end, test_data};
+use tough::observer::{Event, Observer};
+use tough::schema::RoleType;
+use tough::{IntoVec, Repository, RepositoryLoader, TargetName};
+
+mod test_utils;
+
+/// An `Observer` that records a description of each event.
+#[derive(Debug, Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+impl Observer for Recorder {
+    fn notify(&self, event: &Event<'_>) {
+        let description = match event {
+            Event::RoleVerified {
+                role,
+                name,
+                version,
+                ..
+            } => format!("verified {role} {name} {version}"),
+            Event::RootRotated { from, to } => format!("rotated {from} {to}"),
+            Event::TargetProgress {
+                name,
+                received,
+                total,
+            } => format!("progress {} {received}/{total}", name.raw()),
+            Event::VerificationFailed { file, .. } => format!("failed {file}"),
+            _ => return,
+        };
+        self.events.lock().unwrap().push(description);
+    }
+}
+
+impl Recorder {
+    fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+async fn load_tuf_reference_impl(targets_dir: &Path, recorder: &Arc<Recorder>) -> Repository {
+    let base = test_data().join("tuf-reference-impl");
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(targets_dir),
+    )
+    .observer(Arc::clone(recorder))
+    .load()
+    .await
+    .unwrap()
+}
+
+/// Test that observers are notified of each role that is verified, and of root rotation.
+#[tokio::test]
+async fn role_events() {
+    let base = test_data().join("rotated-root");
+    let recorder = Arc::new(Recorder::default());
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("1.root.json")).await.unwrap(),
+        dir_url(&base),
+        dir_url(base.join("targets")),
+    )
+    .observer(Arc::clone(&recorder))
+    .load()
+    .await
+    .unwrap();
+
+    assert_eq!(
+        recorder.events(),
+        vec![
+            "verified root root 2",
+            "rotated 1 2",
+            "verified timestamp timestamp 1",
+            "verified snapshot snapshot 1",
+            "verified targets targets 1",
+        ]
+    );
+}
+
+/// Test that observers are notified of the progress of a target, and of delegated roles that are
+/// loaded to find it.
+#[tokio::test]
+async fn target_events() {
+    let recorder = Arc::new(Recorder::default());
+    let repo = load_tuf_reference_impl(
+        &test_data().join("tuf-reference-impl").join("targets"),
+        &recorder,
+    )
+    .await;
+    recorder.events.lock().unwrap().clear();
+
+    let file3 = TargetName::new("file3.txt").unwrap();
+    read_to_end(repo.read_target(&file3).await.unwrap().unwrap()).await;
+    let events = recorder.events();
+    assert!(events
+        .iter()
+        .any(|event| event.starts_with(&format!("verified {}", RoleType::DelegatedTargets))));
+    assert_eq!(events.last().unwrap(), "progress file3.txt 28/28");
+}
+
+/// Test that observers are notified when a target does not match its hashes.
+#[tokio::test]
+async fn verification_failed_event() {
+    let targets_dir = TempDir::new().unwrap();
+    tokio::fs::write(targets_dir.path().join("file1.txt"), "corrupted")
+        .await
+        .unwrap();
+    let recorder = Arc::new(Recorder::default());
+    let repo = load_tuf_reference_impl(targets_dir.path(), &recorder).await;
+
+    let file1 = TargetName::new("file1.txt").unwrap();
+    let stream = repo.read_target(&file1).await.unwrap().unwrap();
+    assert!(stream.into_vec().await.is_err());
+    assert_eq!(recorder.events().last().unwrap(), "failed file1.txt");
+}
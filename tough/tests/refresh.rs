@@ -0,0 +1,202 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use futures_core::Stream;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use test_utils::{dir_url, test_data, write_repo};
+use tough::schema::HashAlgorithm;
+use tough::{
+    async_trait, Bytes, FilesystemTransport, Repository, RepositoryLoader, TargetName, Transport,
+    TransportError,
+};
+use url::Url;
+
+mod test_utils;
+
+/// A `Transport` that records the name of every file it is asked to fetch.
+#[derive(Debug, Clone, Default)]
+struct RecordingTransport {
+    fetched: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingTransport {
+    fn take_fetched(&self) -> Vec<String> {
+        let mut fetched = std::mem::take(&mut *self.fetched.lock().unwrap());
+        fetched.sort();
+        fetched
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn fetch(
+        &self,
+        url: Url,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>, TransportError>
+    {
+        let filename = url.path_segments().unwrap().next_back().unwrap().to_owned();
+        self.fetched.lock().unwrap().push(filename);
+        FilesystemTransport.fetch(url).await
+    }
+}
+
+async fn load(repo_dir: &Path, datastore: &Path) -> Repository {
+    load_with_transport(repo_dir, datastore, RecordingTransport::default()).await
+}
+
+async fn load_with_transport(
+    repo_dir: &Path,
+    datastore: &Path,
+    transport: RecordingTransport,
+) -> Repository {
+    RepositoryLoader::new(
+        &tokio::fs::read(test_data().join("simple-rsa").join("root.json"))
+            .await
+            .unwrap(),
+        dir_url(repo_dir.join("metadata")),
+        dir_url(repo_dir.join("targets")),
+    )
+    .transport(transport)
+    .datastore(datastore)
+    .load()
+    .await
+    .unwrap()
+}
+
+/// Test that `refresh` picks up new metadata published after the repository was loaded.
+#[tokio::test]
+async fn refresh_finds_new_metadata() {
+    let repo_dir = TempDir::new().unwrap();
+    let datastore = TempDir::new().unwrap();
+    write_repo(
+        repo_dir.path(),
+        1,
+        &test_data().join("targets"),
+        &["file4.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    let mut repo = load(repo_dir.path(), datastore.path()).await;
+    let file5 = TargetName::new("file5.txt").unwrap();
+    assert!(repo.read_target(&file5).await.unwrap().is_none());
+
+    // Nothing has changed yet.
+    repo.refresh().await.unwrap();
+    assert_eq!(repo.timestamp().signed.version.get(), 1);
+
+    write_repo(
+        repo_dir.path(),
+        2,
+        &test_data().join("targets"),
+        &["file4.txt", "file5.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    repo.refresh().await.unwrap();
+    assert_eq!(repo.timestamp().signed.version.get(), 2);
+    assert_eq!(repo.snapshot().signed.version.get(), 2);
+    assert_eq!(repo.targets().signed.version.get(), 2);
+    assert!(repo.read_target(&file5).await.unwrap().is_some());
+}
+
+/// Test that `refresh` only fetches the timestamp metadata (and probes for a new root) when
+/// nothing has changed, and only fetches snapshot and targets metadata when their versions change.
+#[tokio::test]
+async fn refresh_fetches_only_changed_metadata() {
+    let repo_dir = TempDir::new().unwrap();
+    let datastore = TempDir::new().unwrap();
+    write_repo(
+        repo_dir.path(),
+        1,
+        &test_data().join("targets"),
+        &["file4.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    let transport = RecordingTransport::default();
+    let mut repo = load_with_transport(repo_dir.path(), datastore.path(), transport.clone()).await;
+    transport.take_fetched();
+
+    repo.refresh().await.unwrap();
+    repo.refresh().await.unwrap();
+    assert_eq!(
+        transport.take_fetched(),
+        [
+            "2.root.json",
+            "2.root.json",
+            "timestamp.json",
+            "timestamp.json"
+        ]
+    );
+
+    write_repo(
+        repo_dir.path(),
+        2,
+        &test_data().join("targets"),
+        &["file4.txt", "file5.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    repo.refresh().await.unwrap();
+    let fetched = transport.take_fetched();
+    assert_eq!(fetched.len(), 4, "{:?}", fetched);
+    assert!(
+        fetched.contains(&"timestamp.json".to_owned()),
+        "{:?}",
+        fetched
+    );
+    assert!(
+        fetched.iter().any(|file| file.ends_with("snapshot.json")),
+        "{:?}",
+        fetched
+    );
+    assert!(
+        fetched.iter().any(|file| file.ends_with("targets.json")),
+        "{:?}",
+        fetched
+    );
+}
+
+/// Test that `refresh` rejects metadata older than what it already trusts, and leaves the
+/// repository unchanged when it does.
+#[tokio::test]
+async fn refresh_detects_rollback() {
+    let old_dir = TempDir::new().unwrap();
+    let repo_dir = TempDir::new().unwrap();
+    let datastore = TempDir::new().unwrap();
+    write_repo(
+        old_dir.path(),
+        1,
+        &test_data().join("targets"),
+        &["file4.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    write_repo(
+        repo_dir.path(),
+        2,
+        &test_data().join("targets"),
+        &["file4.txt", "file5.txt"],
+        &[HashAlgorithm::Sha256],
+    )
+    .await;
+    let mut repo = load(repo_dir.path(), datastore.path()).await;
+
+    // Serve the old timestamp, which still verifies against the trusted root.
+    tokio::fs::copy(
+        old_dir.path().join("metadata").join("timestamp.json"),
+        repo_dir.path().join("metadata").join("timestamp.json"),
+    )
+    .await
+    .unwrap();
+    assert!(repo.refresh().await.is_err());
+    assert_eq!(repo.timestamp().signed.version.get(), 2);
+    assert!(repo
+        .read_target(&TargetName::new("file5.txt").unwrap())
+        .await
+        .unwrap()
+        .is_some());
+}
@@ -1,10 +1,11 @@
 // Copyright 2019 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use tempfile::TempDir;
 use test_utils::{dir_url, read_to_end, test_data, DATA_1, DATA_2};
-use tough::{Repository, RepositoryLoader, TargetName};
+use tough::{Prefix, Repository, RepositoryLoader, TargetName};
 use url::Url;
 
 mod test_utils;
@@ -122,6 +123,79 @@ async fn test_repo_cache_list_of_two_targets() {
     assert_eq!(39, file_data.len());
 }
 
+/// Test that repo.save_targets() saves every target it can, and reports each result in order.
+#[tokio::test]
+async fn test_repo_save_targets() {
+    let repo_paths = RepoPaths::new();
+    let repo = load_tuf_reference_impl(&repo_paths).await;
+    let names = ["file1.txt", "missing.txt", "file2.txt", "file3.txt"]
+        .iter()
+        .map(|name| TargetName::new(*name).unwrap())
+        .collect::<Vec<_>>();
+
+    let destination = TempDir::new().unwrap();
+    let results = repo
+        .save_targets(
+            &names,
+            destination.path(),
+            Prefix::None,
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .await;
+    assert_eq!(
+        results.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        names.iter().collect::<Vec<_>>()
+    );
+    assert!(results[0].1.is_ok());
+    assert!(results[1].1.is_err());
+    assert!(results[2].1.is_ok());
+    assert!(results[3].1.is_ok());
+
+    for (name, len) in [("file1.txt", 31), ("file2.txt", 39), ("file3.txt", 28)] {
+        assert_eq!(
+            tokio::fs::read(destination.path().join(name))
+                .await
+                .unwrap()
+                .len(),
+            len
+        );
+    }
+}
+
+/// Test that the repo.cache_with_jobs() function caches every target.
+#[tokio::test]
+async fn test_repo_cache_with_jobs() {
+    let repo_paths = RepoPaths::new();
+    let repo = load_tuf_reference_impl(&repo_paths).await;
+
+    let destination = TempDir::new().unwrap();
+    let metadata_destination = destination.as_ref().join("metadata");
+    let targets_destination = destination.as_ref().join("targets");
+    repo.cache_with_jobs(
+        &metadata_destination,
+        &targets_destination,
+        None::<&[&str]>,
+        true,
+        NonZeroUsize::new(4).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let copied_repo = RepositoryLoader::new(
+        &repo_paths.root().await,
+        dir_url(&metadata_destination),
+        dir_url(&targets_destination),
+    )
+    .load()
+    .await
+    .unwrap();
+    for (name, len) in [("file1.txt", 31), ("file2.txt", 39), ("file3.txt", 28)] {
+        let name = TargetName::new(name).unwrap();
+        let file_data = read_to_end(copied_repo.read_target(&name).await.unwrap().unwrap()).await;
+        assert_eq!(len, file_data.len());
+    }
+}
+
 /// Test that the repo.cache() function works when given a list of only one of the targets.
 #[tokio::test]
 async fn test_repo_cache_some() {
@@ -204,7 +204,10 @@ async fn create_sign_write_reload_repo() {
             NonZeroU64::new(1).unwrap(),
         )
         .await
+        .unwrap()
+        .terminating("role3", true)
         .unwrap();
+    assert!(editor.terminating("missing", true).is_err());
     editor
         .targets_version(targets_version)
         .unwrap()
@@ -241,7 +244,7 @@ async fn create_sign_write_reload_repo() {
         .await
         .is_ok());
     // Load the repo we just created
-    let _new_repo = RepositoryLoader::new(
+    let mut new_repo = RepositoryLoader::new(
         &tokio::fs::read(&root).await.unwrap(),
         dir_url(&metadata_destination),
         dir_url(&targets_destination),
@@ -249,6 +252,9 @@ async fn create_sign_write_reload_repo() {
     .load()
     .await
     .unwrap();
+    new_repo.load_delegated_targets().await.unwrap();
+    assert!(new_repo.delegated_role("role3").unwrap().terminating);
+    assert!(!new_repo.delegated_role("role2").unwrap().terminating);
 }
 
 #[tokio::test]
@@ -388,7 +394,7 @@ async fn create_role_flow() {
     let metadata_base_url_out = dir_url(&metadata_destination_out);
 
     // create a new editor with the repo
-    let mut editor = TargetsEditor::from_repo(new_repo, "A").unwrap();
+    let mut editor = TargetsEditor::from_repo(new_repo, "A").await.unwrap();
 
     // add B metadata to role A (without resigning targets)
     editor
@@ -460,7 +466,7 @@ async fn create_role_flow() {
 
     // reload repo and verify that A and B role are included
     let root = root_path();
-    let new_repo = RepositoryLoader::new(
+    let mut new_repo = RepositoryLoader::new(
         &tokio::fs::read(root).await.unwrap(),
         dir_url(metadata_destination),
         dir_url(targets_destination),
@@ -468,6 +474,7 @@ async fn create_role_flow() {
     .load()
     .await
     .unwrap();
+    new_repo.load_delegated_targets().await.unwrap();
 
     // verify that role A and B are included
     new_repo.delegated_role("A").unwrap();
@@ -612,7 +619,7 @@ async fn update_targets_flow() {
     let metadata_base_url_out = dir_url(&metadata_destination_out);
 
     // create a new editor with the repo
-    let mut editor = TargetsEditor::from_repo(new_repo, "A").unwrap();
+    let mut editor = TargetsEditor::from_repo(new_repo, "A").await.unwrap();
 
     // add B metadata to role A (without resigning targets)
     editor
@@ -684,7 +691,7 @@ async fn update_targets_flow() {
 
     // reload repo and verify that A and B role are included
     let root = root_path();
-    let new_repo = RepositoryLoader::new(
+    let mut new_repo = RepositoryLoader::new(
         &tokio::fs::read(root).await.unwrap(),
         dir_url(&metadata_destination),
         dir_url(&targets_destination),
@@ -692,6 +699,7 @@ async fn update_targets_flow() {
     .load()
     .await
     .unwrap();
+    new_repo.load_delegated_targets().await.unwrap();
 
     // verify that role A and B are included
     new_repo.delegated_role("A").unwrap();
@@ -702,7 +710,7 @@ async fn update_targets_flow() {
     // -------------------------------------------------------
 
     // Add target file1.txt to A
-    let mut editor = TargetsEditor::from_repo(new_repo, "A").unwrap();
+    let mut editor = TargetsEditor::from_repo(new_repo, "A").await.unwrap();
     let file1 = targets_path().join("file1.txt");
     let targets = vec![file1];
     editor
@@ -788,7 +796,7 @@ async fn update_targets_flow() {
     );
 
     // Edit target "file1.txt"
-    let mut editor = TargetsEditor::from_repo(new_repo, "A").unwrap();
+    let mut editor = TargetsEditor::from_repo(new_repo, "A").await.unwrap();
     File::create(targets_destination_out.join("file1.txt"))
         .await
         .unwrap()
@@ -0,0 +1,158 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use futures_core::Stream;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use test_utils::{dir_url, read_to_end, test_data};
+use tough::{
+    FilesystemTransport, Prefix, Repository, RepositoryLoader, TargetName, Transport,
+    TransportError, TransportErrorKind,
+};
+use url::Url;
+
+mod test_utils;
+
+/// The number of bytes of a target that are served before an interrupted download fails.
+const INTERRUPT_AFTER: usize = 10;
+
+/// A `Transport` that serves local files, optionally failing partway through targets, and records
+/// the offsets of ranged fetches.
+#[derive(Debug, Clone, Default)]
+struct TestTransport {
+    interrupt: bool,
+    ranges: Arc<Mutex<Vec<u64>>>,
+}
+
+#[tough::async_trait]
+impl Transport for TestTransport {
+    async fn fetch(
+        &self,
+        url: Url,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>, TransportError>
+    {
+        if !self.interrupt || !url.path().contains("/targets/") {
+            return FilesystemTransport.fetch(url).await;
+        }
+        let data = read_to_end(FilesystemTransport.fetch(url.clone()).await?).await;
+        let chunks = vec![
+            Ok(Bytes::copy_from_slice(&data[..INTERRUPT_AFTER])),
+            Err(TransportError::new(TransportErrorKind::Other, url)),
+        ];
+        Ok(stream::iter(chunks).boxed())
+    }
+
+    async fn fetch_range(
+        &self,
+        url: Url,
+        start: u64,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>, TransportError>
+    {
+        self.ranges.lock().unwrap().push(start);
+        FilesystemTransport.fetch_range(url, start).await
+    }
+}
+
+async fn load(transport: TestTransport) -> Repository {
+    let base = test_data().join("tuf-reference-impl");
+    RepositoryLoader::new(
+        &tokio::fs::read(base.join("metadata").join("1.root.json"))
+            .await
+            .unwrap(),
+        dir_url(base.join("metadata")),
+        dir_url(base.join("targets")),
+    )
+    .transport(transport)
+    .load()
+    .await
+    .unwrap()
+}
+
+/// Starts a download of file1.txt into `outdir` that is interrupted partway through.
+async fn interrupted_download(outdir: &Path) {
+    let repo = load(TestTransport {
+        interrupt: true,
+        ..TestTransport::default()
+    })
+    .await;
+    let name = TargetName::new("file1.txt").unwrap();
+    assert!(repo
+        .save_target_resumable(&name, outdir, Prefix::None)
+        .await
+        .is_err());
+    assert!(!outdir.join("file1.txt").exists());
+    assert!(outdir.join("file1.txt.part.json").is_file());
+    assert_eq!(
+        tokio::fs::read(outdir.join("file1.txt.part"))
+            .await
+            .unwrap()
+            .len(),
+        INTERRUPT_AFTER
+    );
+}
+
+async fn file1() -> Vec<u8> {
+    tokio::fs::read(
+        test_data()
+            .join("tuf-reference-impl")
+            .join("targets")
+            .join("file1.txt"),
+    )
+    .await
+    .unwrap()
+}
+
+/// Test that an interrupted download is continued from where it stopped.
+#[tokio::test]
+async fn resume_interrupted_download() {
+    let outdir = TempDir::new().unwrap();
+    interrupted_download(outdir.path()).await;
+
+    let transport = TestTransport::default();
+    let repo = load(transport.clone()).await;
+    let name = TargetName::new("file1.txt").unwrap();
+    repo.save_target_resumable(&name, outdir.path(), Prefix::None)
+        .await
+        .unwrap();
+    assert_eq!(
+        *transport.ranges.lock().unwrap(),
+        vec![INTERRUPT_AFTER as u64]
+    );
+    assert_eq!(
+        tokio::fs::read(outdir.path().join("file1.txt"))
+            .await
+            .unwrap(),
+        file1().await
+    );
+    assert!(!outdir.path().join("file1.txt.part").exists());
+    assert!(!outdir.path().join("file1.txt.part.json").exists());
+}
+
+/// Test that a partial download that was corrupted is downloaded again from the start.
+#[tokio::test]
+async fn resume_corrupted_download() {
+    let outdir = TempDir::new().unwrap();
+    interrupted_download(outdir.path()).await;
+    tokio::fs::write(
+        outdir.path().join("file1.txt.part"),
+        [0_u8; INTERRUPT_AFTER],
+    )
+    .await
+    .unwrap();
+
+    let repo = load(TestTransport::default()).await;
+    let name = TargetName::new("file1.txt").unwrap();
+    repo.save_target_resumable(&name, outdir.path(), Prefix::None)
+        .await
+        .unwrap();
+    assert_eq!(
+        tokio::fs::read(outdir.path().join("file1.txt"))
+            .await
+            .unwrap(),
+        file1().await
+    );
+}
@@ -0,0 +1,123 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, ECDSA_P384_SHA384_ASN1_SIGNING};
+use std::num::NonZeroU64;
+use test_utils::test_data;
+use tough::editor::signed::SignedRole;
+use tough::key_source::KeySource;
+use tough::schema::key::RsaScheme;
+use tough::schema::{KeyHolder, Root, Signed};
+use tough::sign::{parse_keypair_with_rsa_scheme, Sign};
+
+mod test_utils;
+
+/// A `KeySource` for a private key held in memory.
+#[derive(Debug)]
+struct MemoryKeySource {
+    key: Vec<u8>,
+    rsa_scheme: RsaScheme,
+}
+
+#[tough::async_trait]
+impl KeySource for MemoryKeySource {
+    async fn as_sign(
+        &self,
+    ) -> Result<Box<dyn Sign>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(Box::new(parse_keypair_with_rsa_scheme(
+            &self.key,
+            self.rsa_scheme,
+        )?))
+    }
+
+    async fn write(
+        &self,
+        _value: &str,
+        _key_id_hex: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Err("read-only key source".into())
+    }
+}
+
+/// Signs a root whose roles all use `key`, then checks that the written metadata parses and
+/// verifies, and that it names `scheme`.
+async fn round_trip(key: MemoryKeySource, scheme: &str) {
+    let tuf_key = key.as_sign().await.unwrap().tuf_key();
+    assert_eq!(serde_json::to_value(&tuf_key).unwrap()["scheme"], scheme);
+
+    let original: Signed<Root> = serde_json::from_slice(
+        &std::fs::read(test_data().join("simple-rsa").join("root.json")).unwrap(),
+    )
+    .unwrap();
+    let mut root = original.signed;
+    let key_id = tuf_key.key_id().unwrap();
+    root.keys.clear();
+    root.keys.insert(key_id.clone(), tuf_key);
+    for role_keys in root.roles.values_mut() {
+        role_keys.keyids = vec![key_id.clone()];
+        role_keys.threshold = NonZeroU64::new(1).unwrap();
+    }
+
+    let keys: Vec<Box<dyn KeySource>> = vec![Box::new(key)];
+    let signed_role = SignedRole::new(
+        root.clone(),
+        &KeyHolder::Root(root),
+        &keys,
+        &SystemRandom::new(),
+    )
+    .await
+    .unwrap();
+
+    let mut parsed: Signed<Root> = serde_json::from_slice(signed_role.buffer()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&parsed.signed.keys[&key_id]).unwrap()["scheme"],
+        scheme
+    );
+    parsed.signed.verify_role(&parsed).unwrap();
+
+    // A modified role no longer verifies.
+    parsed.signed.version = NonZeroU64::new(parsed.signed.version.get() + 1).unwrap();
+    assert!(parsed.signed.verify_role(&parsed).is_err());
+}
+
+fn rsa_key(scheme: RsaScheme) -> MemoryKeySource {
+    MemoryKeySource {
+        key: std::fs::read(test_data().join("snakeoil.pem")).unwrap(),
+        rsa_scheme: scheme,
+    }
+}
+
+#[tokio::test]
+async fn rsassa_pss_sha256() {
+    round_trip(rsa_key(RsaScheme::RsassaPssSha256), "rsassa-pss-sha256").await;
+}
+
+#[tokio::test]
+async fn rsassa_pss_sha384() {
+    round_trip(rsa_key(RsaScheme::RsassaPssSha384), "rsassa-pss-sha384").await;
+}
+
+#[tokio::test]
+async fn rsassa_pss_sha512() {
+    round_trip(rsa_key(RsaScheme::RsassaPssSha512), "rsassa-pss-sha512").await;
+}
+
+#[tokio::test]
+async fn rsa_pkcs1v15_sha256() {
+    round_trip(rsa_key(RsaScheme::RsaPkcs1v15Sha256), "rsa-pkcs1v15-sha256").await;
+}
+
+#[tokio::test]
+async fn ecdsa_sha2_nistp384() {
+    let key = EcdsaKeyPair::generate_pkcs8(&ECDSA_P384_SHA384_ASN1_SIGNING, &SystemRandom::new())
+        .unwrap();
+    round_trip(
+        MemoryKeySource {
+            key: key.as_ref().to_vec(),
+            rsa_scheme: RsaScheme::RsassaPssSha256,
+        },
+        "ecdsa-sha2-nistp384",
+    )
+    .await;
+}
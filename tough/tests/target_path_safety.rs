@@ -1,79 +1,13 @@
 mod test_utils;
 
-use chrono::{DateTime, TimeZone, Utc};
-use maplit::hashmap;
-use ring::rand::SystemRandom;
-use std::collections::HashMap;
 use std::num::NonZeroU64;
-use std::path::Path;
 use tempfile::TempDir;
-use test_utils::{dir_url, test_data, DATA_1, DATA_2, DATA_3};
+use test_utils::{create_root, dir_url, later, DATA_1, DATA_2, DATA_3};
 use tokio::fs;
-use tough::editor::signed::SignedRole;
 use tough::editor::RepositoryEditor;
-use tough::key_source::{KeySource, LocalKeySource};
-use tough::schema::{KeyHolder, PathPattern, PathSet, RoleKeys, RoleType, Root, Signed, Target};
+use tough::schema::{PathPattern, PathSet, Target};
 use tough::{Prefix, RepositoryLoader, TargetName};
 
-/// Returns a date in the future when Rust programs will no longer exist. `MAX_DATETIME` is so huge
-/// that it serializes to something weird-looking, so we use something that is recognizable to
-/// humans as a date.
-fn later() -> DateTime<Utc> {
-    Utc.with_ymd_and_hms(2999, 1, 1, 0, 0, 0).unwrap()
-}
-
-/// This test ensures that we can safely handle path-like target names with ../'s in them.
-async fn create_root(root_path: &Path, consistent_snapshot: bool) -> Vec<Box<dyn KeySource>> {
-    let keys: Vec<Box<dyn KeySource>> = vec![Box::new(LocalKeySource {
-        path: test_data().join("snakeoil.pem"),
-    })];
-
-    let key_pair = keys.first().unwrap().as_sign().await.unwrap().tuf_key();
-    let key_id = key_pair.key_id().unwrap();
-
-    let empty_keys = RoleKeys {
-        keyids: vec![key_id.clone()],
-        threshold: NonZeroU64::new(1).unwrap(),
-        _extra: Default::default(),
-    };
-
-    let mut root = Signed {
-        signed: Root {
-            spec_version: "1.0.0".into(),
-            consistent_snapshot,
-            version: NonZeroU64::new(1).unwrap(),
-            expires: later(),
-            keys: HashMap::new(),
-            roles: hashmap! {
-                RoleType::Root => empty_keys.clone(),
-                RoleType::Snapshot => empty_keys.clone(),
-                RoleType::Targets => empty_keys.clone(),
-                RoleType::Timestamp => empty_keys,
-                // RoleType::DelegatedTargets => empty_keys.clone(),
-            },
-            _extra: HashMap::new(),
-        },
-        signatures: Vec::new(),
-    };
-
-    root.signed.keys.insert(key_id, key_pair);
-
-    let signed_root = SignedRole::new(
-        root.signed.clone(),
-        &KeyHolder::Root(root.signed.clone()),
-        &keys,
-        &SystemRandom::new(),
-    )
-    .await
-    .unwrap();
-
-    tokio::fs::write(root_path, signed_root.buffer())
-        .await
-        .unwrap();
-
-    keys
-}
-
 #[tokio::test]
 async fn safe_target_paths() {
     let tempdir = TempDir::new().unwrap();
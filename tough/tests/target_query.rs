@@ -0,0 +1,214 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod test_utils;
+
+use serde::Deserialize;
+use serde_json::json;
+use std::num::NonZeroU64;
+use std::path::Path;
+use tempfile::TempDir;
+use test_utils::{create_root, dir_url, later, DATA_1};
+use tough::editor::RepositoryEditor;
+use tough::error::Error;
+use tough::schema::{PathPattern, PathSet, Target};
+use tough::{Repository, RepositoryLoader, TargetName, TargetQuery};
+
+#[derive(Debug, Deserialize)]
+struct Custom {
+    arch: String,
+    version: String,
+}
+
+/// Returns a target for `file` with the given custom metadata.
+async fn target(file: &Path, arch: &str, version: &str) -> Target {
+    let mut target = Target::from_path(file).await.unwrap();
+    target.custom.insert("arch".to_owned(), json!(arch));
+    target.custom.insert("version".to_owned(), json!(version));
+    target
+}
+
+/// Creates and loads a repository whose top-level targets role lists `a.img`, `b.img` and
+/// `notes.txt`, which has no custom metadata, and delegates to two roles:
+/// * `delegated`, a terminating role for `c*`, lists `c.img`.
+/// * `other`, for every path, lists `e.img`, as well as `b.img`, which is shadowed by the
+///   top-level targets role, and `c2.img`, which is cut off by `delegated`.
+async fn create_repository(dir: &Path) -> Repository {
+    let root_path = dir.join("root.json");
+    let keys = create_root(&root_path, false).await;
+    let one = NonZeroU64::new(1).unwrap();
+    let file = dir.join("data.txt");
+    tokio::fs::write(&file, DATA_1).await.unwrap();
+
+    let mut editor = RepositoryEditor::new(&root_path).await.unwrap();
+    editor
+        .snapshot_version(one)
+        .snapshot_expires(later())
+        .timestamp_version(one)
+        .timestamp_expires(later())
+        .delegate_role(
+            "delegated",
+            &keys,
+            PathSet::Paths(vec![PathPattern::new("c*").unwrap()]),
+            one,
+            later(),
+            one,
+        )
+        .await
+        .unwrap()
+        .terminating("delegated", true)
+        .unwrap()
+        .delegate_role(
+            "other",
+            &keys,
+            PathSet::Paths(vec![PathPattern::new("*").unwrap()]),
+            one,
+            later(),
+            one,
+        )
+        .await
+        .unwrap();
+    editor
+        .add_target(
+            TargetName::new("a.img").unwrap(),
+            target(&file, "x86_64", "1.9.2").await,
+        )
+        .unwrap()
+        .add_target(
+            TargetName::new("b.img").unwrap(),
+            target(&file, "aarch64", "1.10.0").await,
+        )
+        .unwrap()
+        .add_target(
+            TargetName::new("notes.txt").unwrap(),
+            Target::from_path(&file).await.unwrap(),
+        )
+        .unwrap()
+        .targets_version(one)
+        .unwrap()
+        .targets_expires(later())
+        .unwrap()
+        .sign_targets_editor(&keys)
+        .await
+        .unwrap()
+        .change_delegated_targets("delegated")
+        .unwrap()
+        .add_target(
+            TargetName::new("c.img").unwrap(),
+            target(&file, "x86_64", "1.10.0").await,
+        )
+        .unwrap()
+        .targets_version(one)
+        .unwrap()
+        .targets_expires(later())
+        .unwrap()
+        .sign_targets_editor(&keys)
+        .await
+        .unwrap()
+        .change_delegated_targets("other")
+        .unwrap()
+        .add_target(
+            TargetName::new("e.img").unwrap(),
+            target(&file, "aarch64", "2.0.0-rc1").await,
+        )
+        .unwrap()
+        .add_target(
+            TargetName::new("b.img").unwrap(),
+            target(&file, "x86_64", "0.1.0").await,
+        )
+        .unwrap()
+        .add_target(
+            TargetName::new("c2.img").unwrap(),
+            target(&file, "x86_64", "0.1.0").await,
+        )
+        .unwrap()
+        .targets_version(one)
+        .unwrap()
+        .targets_expires(later())
+        .unwrap()
+        .sign_targets_editor(&keys)
+        .await
+        .unwrap();
+    let metadata_dir = dir.join("metadata");
+    editor
+        .sign(&keys)
+        .await
+        .unwrap()
+        .write(&metadata_dir)
+        .await
+        .unwrap();
+
+    RepositoryLoader::new(
+        &tokio::fs::read(&root_path).await.unwrap(),
+        dir_url(&metadata_dir),
+        dir_url(dir),
+    )
+    .load()
+    .await
+    .unwrap()
+}
+
+fn names<T>(matches: &[tough::TargetMatch<T>]) -> Vec<&str> {
+    matches.iter().map(|found| found.name.raw()).collect()
+}
+
+/// Test that targets, including delegated targets, can be found by name, custom metadata and role,
+/// and sorted by a version in their custom metadata.
+#[tokio::test]
+async fn query_targets() {
+    let dir = TempDir::new().unwrap();
+    let mut repo = create_repository(dir.path()).await;
+
+    let query = TargetQuery::<Custom>::new()
+        .name(PathPattern::new("*.img").unwrap())
+        .sort_by_version(|custom| custom.version.clone());
+    let matches = repo.query_targets(&query).await.unwrap();
+    assert_eq!(names(&matches), ["a.img", "b.img", "c.img", "e.img"]);
+    assert_eq!(matches[0].custom.version, "1.9.2");
+    assert_eq!(matches[2].role, "delegated");
+    assert_eq!(matches[3].role, "other");
+
+    let query = query.filter(|custom| custom.arch == "x86_64");
+    let matches = repo.query_targets(&query).await.unwrap();
+    assert_eq!(names(&matches), ["a.img", "c.img"]);
+
+    let query = query.role("targets");
+    let matches = repo.query_targets(&query).await.unwrap();
+    assert_eq!(names(&matches), ["a.img"]);
+
+    let query = TargetQuery::<serde_json::Value>::new();
+    let matches = repo.query_targets(&query).await.unwrap();
+    assert_eq!(
+        names(&matches),
+        ["a.img", "b.img", "c.img", "e.img", "notes.txt"]
+    );
+}
+
+/// Test that entries which a lookup of their name would not find are not matched: `b.img` is
+/// shadowed by the top-level targets role, and `c2.img` is cut off by the terminating role
+/// `delegated`.
+#[tokio::test]
+async fn query_targets_unreachable() {
+    let dir = TempDir::new().unwrap();
+    let mut repo = create_repository(dir.path()).await;
+
+    let query = TargetQuery::<Custom>::new().role("other");
+    let matches = repo.query_targets(&query).await.unwrap();
+    assert_eq!(names(&matches), ["e.img"]);
+}
+
+/// Test that custom metadata which does not match the requested type is reported.
+#[tokio::test]
+async fn query_targets_bad_custom_metadata() {
+    let dir = TempDir::new().unwrap();
+    let mut repo = create_repository(dir.path()).await;
+
+    let query = TargetQuery::<Custom>::new();
+    match repo.query_targets(&query).await.unwrap_err() {
+        Error::TargetCustomMetadata { name, role, .. } => {
+            assert_eq!(name.raw(), "notes.txt");
+            assert_eq!(role, "targets");
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+}
@@ -5,11 +5,19 @@
 // cause compiler warnings for unused code, so we suppress them.
 #![allow(unused)]
 
-use chrono::TimeDelta;
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
 use futures::TryStreamExt;
 use futures_core::Stream;
+use maplit::hashmap;
+use ring::rand::SystemRandom;
+use std::collections::HashMap;
 use std::io::Read;
+use std::num::NonZeroU64;
 use std::path::{Path, PathBuf};
+use tough::editor::signed::{PathExists, SignedRole};
+use tough::editor::RepositoryEditor;
+use tough::key_source::{KeySource, LocalKeySource};
+use tough::schema::{HashAlgorithm, KeyHolder, RoleKeys, RoleType, Root, Signed, Target};
 use tough::IntoVec;
 use url::Url;
 
@@ -43,3 +51,106 @@ where
 pub fn days(value: i64) -> TimeDelta {
     TimeDelta::try_days(value).unwrap()
 }
+
+/// Returns a date in the future when Rust programs will no longer exist. `MAX_DATETIME` is so huge
+/// that it serializes to something weird-looking, so we use something that is recognizable to
+/// humans as a date.
+pub fn later() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2999, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Writes a repository for the `simple-rsa` root, signed by `snakeoil.pem`, to `outdir`. Every
+/// top-level role has `version`, and `targets` lists `target_names` from `targets_indir` with a
+/// digest for each of `algorithms`. Targets are copied rather than linked, so they can be modified.
+pub async fn write_repo(
+    outdir: &Path,
+    version: u64,
+    targets_indir: &Path,
+    target_names: &[&str],
+    algorithms: &[HashAlgorithm],
+) {
+    let keys: Vec<Box<dyn KeySource>> = vec![Box::new(LocalKeySource {
+        path: test_data().join("snakeoil.pem"),
+    })];
+    let version = NonZeroU64::new(version).unwrap();
+
+    let mut editor = RepositoryEditor::new(test_data().join("simple-rsa").join("root.json"))
+        .await
+        .unwrap();
+    editor
+        .targets_version(version)
+        .unwrap()
+        .targets_expires(later())
+        .unwrap()
+        .snapshot_version(version)
+        .snapshot_expires(later())
+        .timestamp_version(version)
+        .timestamp_expires(later())
+        .target_hash_algorithms(algorithms)
+        .add_target_paths(
+            target_names
+                .iter()
+                .map(|name| targets_indir.join(name))
+                .collect(),
+        )
+        .await
+        .unwrap();
+    let signed_repo = editor.sign(&keys).await.unwrap();
+    signed_repo.write(outdir.join("metadata")).await.unwrap();
+    signed_repo
+        .copy_targets(targets_indir, outdir.join("targets"), PathExists::Skip)
+        .await
+        .unwrap();
+}
+
+/// Writes a root to `root_path` that trusts `snakeoil.pem` for every role, and returns its key.
+pub async fn create_root(root_path: &Path, consistent_snapshot: bool) -> Vec<Box<dyn KeySource>> {
+    let keys: Vec<Box<dyn KeySource>> = vec![Box::new(LocalKeySource {
+        path: test_data().join("snakeoil.pem"),
+    })];
+
+    let key_pair = keys.first().unwrap().as_sign().await.unwrap().tuf_key();
+    let key_id = key_pair.key_id().unwrap();
+
+    let empty_keys = RoleKeys {
+        keyids: vec![key_id.clone()],
+        threshold: NonZeroU64::new(1).unwrap(),
+        _extra: Default::default(),
+    };
+
+    let mut root = Signed {
+        signed: Root {
+            spec_version: "1.0.0".into(),
+            consistent_snapshot,
+            version: NonZeroU64::new(1).unwrap(),
+            expires: later(),
+            keys: HashMap::new(),
+            roles: hashmap! {
+                RoleType::Root => empty_keys.clone(),
+                RoleType::Snapshot => empty_keys.clone(),
+                RoleType::Targets => empty_keys.clone(),
+                RoleType::Timestamp => empty_keys,
+                // RoleType::DelegatedTargets => empty_keys.clone(),
+            },
+            _extra: HashMap::new(),
+        },
+        signatures: Vec::new(),
+    };
+
+    root.signed.keys.insert(key_id, key_pair);
+
+    let signed_root = SignedRole::new(
+        root.signed.clone(),
+        &KeyHolder::Root(root.signed.clone()),
+        &keys,
+        &SystemRandom::new(),
+    )
+    .await
+    .unwrap();
+
+    tokio::fs::write(root_path, signed_root.buffer())
+        .await
+        .unwrap();
+
+    keys
+}
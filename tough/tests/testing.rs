@@ -0,0 +1,226 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "testing")]
+
+use futures::{Stream, StreamExt};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use test_utils::{dir_url, read_to_end, test_data};
+use tough::schema::PathPattern;
+use tough::testing::{Fault, FaultTransport, RecordingTransport, ReplayTransport};
+use tough::{
+    async_trait, Bytes, ConditionalFetch, FilesystemTransport, IntoVec, Repository,
+    RepositoryLoader, TargetName, Transport, TransportError, TransportErrorKind, Validators,
+};
+use url::Url;
+
+mod test_utils;
+
+fn reference_impl() -> PathBuf {
+    test_data().join("tuf-reference-impl")
+}
+
+async fn load<T: Transport + 'static>(transport: T) -> tough::error::Result<Repository> {
+    let root = std::fs::read(reference_impl().join("metadata").join("1.root.json")).unwrap();
+    RepositoryLoader::new(
+        &root,
+        dir_url(reference_impl().join("metadata")),
+        dir_url(reference_impl().join("targets")),
+    )
+    .transport(transport)
+    .load()
+    .await
+}
+
+fn pattern(pattern: &str) -> PathPattern {
+    pattern.parse().unwrap()
+}
+
+/// Test that a repository loaded through a `RecordingTransport` can be loaded again from the
+/// recording, and that files which were not fetched are not found.
+#[tokio::test]
+async fn record_and_replay() {
+    let recording = TempDir::new().unwrap();
+    let file1 = TargetName::new("file1.txt").unwrap();
+    let repo = load(RecordingTransport::new(
+        FilesystemTransport,
+        recording.path(),
+    ))
+    .await
+    .unwrap();
+    read_to_end(repo.read_target(&file1).await.unwrap().unwrap()).await;
+
+    let timestamp = reference_impl().join("metadata").join("timestamp.json");
+    let recorded = recording
+        .path()
+        .join("_")
+        .join(timestamp.strip_prefix("/").unwrap());
+    assert_eq!(
+        std::fs::read(recorded).unwrap(),
+        std::fs::read(&timestamp).unwrap()
+    );
+
+    let repo = load(ReplayTransport::new(recording.path())).await.unwrap();
+    assert_eq!(
+        read_to_end(repo.read_target(&file1).await.unwrap().unwrap()).await,
+        b"This is an example target file."
+    );
+    let file2 = TargetName::new("file2.txt").unwrap();
+    let error = repo.read_target(&file2).await.err().unwrap();
+    assert!(format!("{error:?}").contains("FileNotFound"), "{:?}", error);
+}
+
+/// Test that each kind of fault is injected into the fetches of matching URLs.
+#[tokio::test]
+async fn fault_injection() {
+    let url = Url::from_file_path(reference_impl().join("targets").join("file1.txt")).unwrap();
+    let fetch = |fault: Fault| {
+        let transport = FaultTransport::new(FilesystemTransport)
+            .fault(pattern("*/file2.txt"), Fault::Error)
+            .fault(pattern("*/file1.txt"), fault);
+        let url = url.clone();
+        async move {
+            match transport.fetch(url).await {
+                Ok(stream) => stream.collect::<Vec<_>>().await,
+                Err(e) => vec![Err(e)],
+            }
+        }
+    };
+
+    let chunks = fetch(Fault::Truncate(4)).await;
+    assert_eq!(
+        chunks.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+        [&b"This"[..]]
+    );
+    let chunks = fetch(Fault::Interrupt(4)).await;
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].as_ref().unwrap(), &b"This"[..]);
+    assert_eq!(
+        chunks[1].as_ref().unwrap_err().kind(),
+        TransportErrorKind::Other
+    );
+    let chunks = fetch(Fault::Corrupt(1)).await;
+    assert_eq!(chunks[0].as_ref().unwrap()[..3], [b'T', !b'h', b'i']);
+    let chunks = fetch(Fault::FileNotFound).await;
+    assert_eq!(
+        chunks[0].as_ref().unwrap_err().kind(),
+        TransportErrorKind::FileNotFound
+    );
+    let start = Instant::now();
+    let chunks = fetch(Fault::Delay(Duration::from_millis(200))).await;
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(chunks[0].as_ref().unwrap()[..4], b"This"[..]);
+
+    let file1 = TargetName::new("file1.txt").unwrap();
+    let repo = load(
+        FaultTransport::new(FilesystemTransport).fault(pattern("*/file1.txt"), Fault::Corrupt(0)),
+    )
+    .await
+    .unwrap();
+    let stream = repo.read_target(&file1).await.unwrap().unwrap();
+    assert!(stream.into_vec().await.is_err());
+}
+
+/// Test that a fault added with `fault_times` stops once it has been injected, across clones.
+#[tokio::test]
+async fn fault_injection_times() {
+    let transport = FaultTransport::new(FilesystemTransport).fault_times(
+        pattern("*/timestamp.json"),
+        Fault::Error,
+        2,
+    );
+    assert!(load(transport.clone()).await.is_err());
+    assert!(load(transport.clone()).await.is_err());
+    assert!(load(transport).await.is_ok());
+}
+
+/// A transport that serves `file1.txt` with an `ETag`, and reports it unmodified when fetched with
+/// that `ETag`.
+#[derive(Debug, Clone)]
+struct EtagTransport;
+
+type TransportStream = Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>;
+
+#[async_trait]
+impl Transport for EtagTransport {
+    async fn fetch(&self, url: Url) -> Result<TransportStream, TransportError> {
+        FilesystemTransport.fetch(url).await
+    }
+
+    async fn fetch_range(&self, url: Url, start: u64) -> Result<TransportStream, TransportError> {
+        FilesystemTransport.fetch_range(url, start).await
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        if validators.etag.as_deref() == Some("v1") {
+            return Ok(ConditionalFetch::NotModified);
+        }
+        Ok(ConditionalFetch::Modified {
+            stream: self.fetch(url).await?,
+            validators: Validators {
+                etag: Some("v1".to_owned()),
+                ..Validators::default()
+            },
+        })
+    }
+}
+
+/// Test that `RecordingTransport` and `FaultTransport` forward conditional fetches, keeping the
+/// validators, and that faults are injected into modified files.
+#[tokio::test]
+async fn conditional_fetch() {
+    let url = Url::from_file_path(reference_impl().join("targets").join("file1.txt")).unwrap();
+    let unmodified = Validators {
+        etag: Some("v1".to_owned()),
+        ..Validators::default()
+    };
+
+    let recording = TempDir::new().unwrap();
+    let transport = RecordingTransport::new(EtagTransport, recording.path());
+    let ConditionalFetch::Modified { stream, validators } = transport
+        .fetch_conditional(url.clone(), &Validators::default())
+        .await
+        .unwrap()
+    else {
+        panic!("expected a modified file");
+    };
+    assert_eq!(validators, unmodified);
+    read_to_end(stream).await;
+    let replayed = ReplayTransport::new(recording.path())
+        .fetch(url.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        read_to_end(replayed).await,
+        b"This is an example target file."
+    );
+    assert!(matches!(
+        transport.fetch_conditional(url.clone(), &unmodified).await,
+        Ok(ConditionalFetch::NotModified)
+    ));
+
+    let transport =
+        FaultTransport::new(EtagTransport).fault(pattern("*/file1.txt"), Fault::Truncate(4));
+    let ConditionalFetch::Modified { stream, validators } = transport
+        .fetch_conditional(url.clone(), &Validators::default())
+        .await
+        .unwrap()
+    else {
+        panic!("expected a modified file");
+    };
+    assert_eq!(validators, unmodified);
+    assert_eq!(read_to_end(stream).await, b"This");
+    assert!(matches!(
+        transport.fetch_conditional(url.clone(), &unmodified).await,
+        Ok(ConditionalFetch::NotModified)
+    ));
+    let transport = FaultTransport::new(EtagTransport).fault(pattern("*/file1.txt"), Fault::Error);
+    assert!(transport.fetch_conditional(url, &unmodified).await.is_err());
+}
@@ -1,8 +1,18 @@
+use futures_core::Stream;
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
-use test_utils::read_to_end;
+use test_utils::{dir_url, read_to_end, test_data};
 use tokio::fs;
-use tough::{DefaultTransport, Transport, TransportErrorKind};
+use tough::{
+    async_trait, Bytes, CachingTransport, ConditionalFetch, DefaultTransport, FilesystemTransport,
+    RateLimit, RepositoryLoader, TargetName, ThrottledTransport, Transport, TransportError,
+    TransportErrorKind, Validators,
+};
 use url::Url;
 
 mod test_utils;
@@ -47,3 +57,221 @@ async fn default_transport_file() {
     let contents = String::from_utf8_lossy(&temp_vec);
     assert_eq!(contents, "123123987");
 }
+
+#[tokio::test]
+async fn default_transport_file_range() {
+    let dir = TempDir::new().unwrap();
+    let filepath = dir.path().join("file.txt");
+    fs::write(&filepath, "123123987").await.unwrap();
+    let transport = DefaultTransport::new();
+    let url = Url::from_file_path(filepath).unwrap();
+    let read = transport.fetch_range(url, 6).await.unwrap();
+    assert_eq!(read_to_end(read).await, b"987");
+}
+
+fn reference_impl() -> PathBuf {
+    test_data().join("tuf-reference-impl")
+}
+
+/// A `Transport` that serves local files with their SHA-256 digest as an `ETag`, and records the
+/// names of the files it sends.
+#[derive(Debug, Clone, Default)]
+struct VersionedTransport {
+    sent: Arc<Mutex<Vec<String>>>,
+}
+
+impl VersionedTransport {
+    fn take_sent(&self) -> Vec<String> {
+        std::mem::take(&mut self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait]
+impl Transport for VersionedTransport {
+    async fn fetch(
+        &self,
+        url: Url,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, TransportError>> + Send>>, TransportError>
+    {
+        FilesystemTransport.fetch(url).await
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: Url,
+        validators: &Validators,
+    ) -> Result<ConditionalFetch, TransportError> {
+        let etag = fs::read(url.to_file_path().unwrap())
+            .await
+            .ok()
+            .map(|content| hex::encode(ring::digest::digest(&ring::digest::SHA256, &content)));
+        if etag.is_some() && etag == validators.etag {
+            return Ok(ConditionalFetch::NotModified);
+        }
+        let stream = self.fetch(url.clone()).await?;
+        let filename = url.path_segments().unwrap().next_back().unwrap();
+        self.sent.lock().unwrap().push(filename.to_owned());
+        Ok(ConditionalFetch::Modified {
+            stream,
+            validators: Validators {
+                etag,
+                last_modified: None,
+            },
+        })
+    }
+}
+
+/// Test that a repository loaded again through a `CachingTransport` is served unchanged metadata
+/// and targets from the cache.
+#[tokio::test]
+async fn caching_transport_load() {
+    let cache = TempDir::new().unwrap();
+    let transport = VersionedTransport::default();
+    let root = std::fs::read(reference_impl().join("metadata").join("1.root.json")).unwrap();
+    let file1 = TargetName::new("file1.txt").unwrap();
+    let mut sent = Vec::new();
+    for _ in 0..2 {
+        // A new datastore each time, so that the metadata is fetched again.
+        let datastore = TempDir::new().unwrap();
+        let repo = RepositoryLoader::new(
+            &root,
+            dir_url(reference_impl().join("metadata")),
+            dir_url(reference_impl().join("targets")),
+        )
+        .transport(CachingTransport::new(transport.clone(), cache.path()))
+        .datastore(datastore.path())
+        .load()
+        .await
+        .unwrap();
+        let read = repo.read_target(&file1).await.unwrap().unwrap();
+        assert_eq!(read_to_end(read).await, b"This is an example target file.");
+        sent.push(transport.take_sent());
+    }
+    assert!(sent[0].contains(&"timestamp.json".to_owned()), "{:?}", sent);
+    assert!(sent[0].contains(&"file1.txt".to_owned()), "{:?}", sent);
+    assert!(sent[1].is_empty(), "{:?}", sent);
+}
+
+/// Test that a `CachingTransport` fetches files that have changed, and does not cache files
+/// without validators.
+#[tokio::test]
+async fn caching_transport_changed_files() {
+    let dir = TempDir::new().unwrap();
+    let cache = dir.path().join("cache");
+    let path = dir.path().join("file.txt");
+    let url = Url::from_file_path(&path).unwrap();
+    let fetch = |transport: CachingTransport<VersionedTransport>| {
+        let url = url.clone();
+        async move { read_to_end(transport.fetch(url).await.unwrap()).await }
+    };
+
+    let versioned = VersionedTransport::default();
+    let transport = CachingTransport::new(versioned.clone(), &cache);
+    fs::write(&path, "first").await.unwrap();
+    assert_eq!(fetch(transport.clone()).await, b"first");
+    assert_eq!(fetch(transport.clone()).await, b"first");
+    assert_eq!(versioned.take_sent().len(), 1);
+    fs::write(&path, "second").await.unwrap();
+    assert_eq!(fetch(transport.clone()).await, b"second");
+    assert_eq!(fetch(transport).await, b"second");
+    assert_eq!(versioned.take_sent().len(), 1);
+
+    let transport = CachingTransport::new(FilesystemTransport, dir.path().join("uncached"));
+    assert_eq!(
+        read_to_end(transport.fetch(url).await.unwrap()).await,
+        b"second"
+    );
+    assert!(!dir.path().join("uncached").exists());
+
+    let missing = Url::from_file_path(dir.path().join("missing.txt")).unwrap();
+    let transport = CachingTransport::new(versioned, &cache);
+    let error = transport.fetch(missing).await.err().unwrap();
+    assert_eq!(error.kind(), TransportErrorKind::FileNotFound);
+}
+
+/// Test that a `CachingTransport` answers conditional fetches, so that it can be wrapped in
+/// another `CachingTransport`.
+#[tokio::test]
+async fn caching_transport_conditional_fetch() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("file.txt");
+    let url = Url::from_file_path(&path).unwrap();
+    fs::write(&path, "first").await.unwrap();
+
+    let versioned = VersionedTransport::default();
+    let inner = CachingTransport::new(versioned.clone(), dir.path().join("inner"));
+    let outer = CachingTransport::new(inner.clone(), dir.path().join("outer"));
+    for _ in 0..2 {
+        assert_eq!(
+            read_to_end(outer.fetch(url.clone()).await.unwrap()).await,
+            b"first"
+        );
+    }
+    assert_eq!(versioned.take_sent().len(), 1);
+
+    let ConditionalFetch::Modified { stream, validators } = inner
+        .fetch_conditional(url.clone(), &Validators::default())
+        .await
+        .unwrap()
+    else {
+        panic!("expected the cached file");
+    };
+    assert_eq!(read_to_end(stream).await, b"first");
+    assert!(validators.etag.is_some());
+    assert!(matches!(
+        inner.fetch_conditional(url.clone(), &validators).await,
+        Ok(ConditionalFetch::NotModified)
+    ));
+
+    fs::write(&path, "second").await.unwrap();
+    assert_eq!(
+        read_to_end(outer.fetch(url).await.unwrap()).await,
+        b"second"
+    );
+    assert_eq!(versioned.take_sent().len(), 1);
+}
+
+/// Test that concurrent downloads through a `ThrottledTransport` share its limit, and that the
+/// limit can be removed while it is in use.
+#[tokio::test]
+async fn throttled_transport_shares_limit() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("file.bin");
+    fs::write(&path, vec![0; 2000]).await.unwrap();
+    let url = Url::from_file_path(&path).unwrap();
+    let rate_limit = RateLimit::new(NonZeroU64::new(8000).unwrap());
+    let transport = ThrottledTransport::new(FilesystemTransport, rate_limit.clone());
+
+    // Two files of 2000 bytes at 8000 bytes per second take at least half a second.
+    let clone = transport.clone();
+    let start = Instant::now();
+    let (first, second) = futures::join!(transport.fetch(url.clone()), clone.fetch(url.clone()));
+    let (first, second) = futures::join!(read_to_end(first.unwrap()), read_to_end(second.unwrap()));
+    assert!(start.elapsed() >= Duration::from_millis(500));
+    assert_eq!(first.len() + second.len(), 4000);
+
+    rate_limit.set_bytes_per_second(None);
+    assert_eq!(transport.rate_limit().bytes_per_second(), None);
+    let start = Instant::now();
+    read_to_end(transport.fetch(url).await.unwrap()).await;
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+/// Test that a repository loaded with a rate limit is verified as usual.
+#[tokio::test]
+async fn repository_loader_rate_limit() {
+    let root = std::fs::read(reference_impl().join("metadata").join("1.root.json")).unwrap();
+    let repo = RepositoryLoader::new(
+        &root,
+        dir_url(reference_impl().join("metadata")),
+        dir_url(reference_impl().join("targets")),
+    )
+    .transport(FilesystemTransport)
+    .rate_limit(RateLimit::new(NonZeroU64::new(64 * 1024).unwrap()))
+    .load()
+    .await
+    .unwrap();
+    let file1 = TargetName::new("file1.txt").unwrap();
+    let read = repo.read_target(&file1).await.unwrap().unwrap();
+    assert_eq!(read_to_end(read).await, b"This is an example target file.");
+}
@@ -12,6 +12,7 @@ use std::collections::HashMap;
 use std::num::NonZeroU64;
 use std::path::PathBuf;
 use tough::editor::targets::TargetsEditor;
+use tough::HttpTransportBuilder;
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -51,12 +52,14 @@ pub(crate) struct AddKeyArgs {
 }
 
 impl AddKeyArgs {
-    pub(crate) async fn run(&self, role: &str) -> Result<()> {
+    pub(crate) async fn run(&self, role: &str, connection: &HttpTransportBuilder) -> Result<()> {
         // load the repo
-        let repository = load_metadata_repo(&self.root, self.metadata_base_url.clone()).await?;
+        let repository =
+            load_metadata_repo(&self.root, self.metadata_base_url.clone(), connection).await?;
         self.add_key(
             role,
             TargetsEditor::from_repo(repository, role)
+                .await
                 .context(error::EditorFromRepoSnafu { path: &self.root })?,
         )
         .await
@@ -12,6 +12,7 @@ use std::num::NonZeroU64;
 use std::path::PathBuf;
 use tough::editor::{targets::TargetsEditor, RepositoryEditor};
 use tough::schema::{PathHashPrefix, PathPattern, PathSet};
+use tough::HttpTransportBuilder;
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -61,6 +62,11 @@ pub(crate) struct AddRoleArgs {
     #[arg(long)]
     snapshot_version: Option<NonZeroU64>,
 
+    /// Marks the delegation as terminating; lookups for matching paths will not consider any
+    /// delegations after this one
+    #[arg(long)]
+    terminating: bool,
+
     /// threshold of signatures to sign delegatee
     #[arg(short, long)]
     threshold: NonZeroU64,
@@ -83,9 +89,10 @@ pub(crate) struct AddRoleArgs {
 }
 
 impl AddRoleArgs {
-    pub(crate) async fn run(&self, role: &str) -> Result<()> {
+    pub(crate) async fn run(&self, role: &str, connection: &HttpTransportBuilder) -> Result<()> {
         // load the repo
-        let repository = load_metadata_repo(&self.root, self.metadata_base_url.clone()).await?;
+        let repository =
+            load_metadata_repo(&self.root, self.metadata_base_url.clone(), connection).await?;
         // if sign_all use Repository Editor to sign the entire repo if not use targets editor
         if self.sign_all {
             // Add a role using a `RepositoryEditor`
@@ -101,6 +108,7 @@ impl AddRoleArgs {
             self.add_role(
                 role,
                 TargetsEditor::from_repo(repository, role)
+                    .await
                     .context(error::EditorFromRepoSnafu { path: &self.root })?,
             )
             .await
@@ -135,6 +143,8 @@ impl AddRoleArgs {
             )
             .await
             .context(error::LoadMetadataSnafu)?
+            .terminating(&self.delegatee, self.terminating)
+            .context(error::DelegationStructureSnafu)?
             .version(self.version)
             .expires(self.expires)
             .sign(&keys)
@@ -210,6 +220,8 @@ impl AddRoleArgs {
             )
             .await
             .context(error::LoadMetadataSnafu)?
+            .terminating(&self.delegatee, self.terminating)
+            .context(error::DelegationStructureSnafu)?
             .targets_version(self.version)
             .context(error::DelegationStructureSnafu)?
             .targets_expires(self.expires)
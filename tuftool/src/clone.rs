@@ -4,11 +4,12 @@
 use crate::common::UNUSED_URL;
 use crate::download_root::download_root;
 use crate::error::{self, Result};
+use crate::http::HttpArgs;
 use clap::Parser;
 use snafu::ResultExt;
-use std::num::NonZeroU64;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
-use tough::{ExpirationEnforcement, RepositoryLoader};
+use tough::{ExpirationEnforcement, HttpTransportBuilder, RepositoryLoader};
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -41,6 +42,10 @@ pub(crate) struct CloneArgs {
     #[arg(short = 'n', long, conflicts_with = "metadata_only")]
     target_names: Vec<String>,
 
+    /// Number of targets to download at once
+    #[arg(short, long, default_value = "1", conflicts_with = "metadata_only")]
+    jobs: NonZeroUsize,
+
     /// Output directory of targets
     #[arg(long, required_unless_present = "metadata_only")]
     targets_dir: Option<PathBuf>,
@@ -52,6 +57,9 @@ pub(crate) struct CloneArgs {
     /// Remote root.json version number
     #[arg(short = 'v', long, default_value = "1")]
     root_version: NonZeroU64,
+
+    #[command(flatten)]
+    http: HttpArgs,
 }
 
 #[rustfmt::skip]
@@ -63,13 +71,21 @@ WARNING: repo metadata is expired, meaning the owner hasn't verified its content
 }
 
 impl CloneArgs {
-    pub(crate) async fn run(&self) -> Result<()> {
+    pub(crate) async fn run(&self, connection: &HttpTransportBuilder) -> Result<()> {
+        let transport = self.http.transport(connection)?;
+
         // Use local root.json or download from repository
         let root_path = if let Some(path) = &self.root {
             PathBuf::from(path)
         } else if self.allow_root_download {
             let outdir = std::env::current_dir().context(error::CurrentDirSnafu)?;
-            download_root(&self.metadata_base_url, self.root_version, outdir).await?
+            download_root(
+                &transport,
+                &self.metadata_base_url,
+                self.root_version,
+                outdir,
+            )
+            .await?
         } else {
             eprintln!("No root.json available");
             std::process::exit(1);
@@ -102,6 +118,7 @@ impl CloneArgs {
             targets_base_url,
         )
         .expiration_enforcement(expiration_enforcement)
+        .transport(transport)
         .load()
         .await
         .context(error::RepoLoadSnafu)?;
@@ -127,16 +144,23 @@ impl CloneArgs {
             );
             if self.target_names.is_empty() {
                 repository
-                    .cache(&self.metadata_dir, targets_dir, None::<&[&str]>, true)
+                    .cache_with_jobs(
+                        &self.metadata_dir,
+                        targets_dir,
+                        None::<&[&str]>,
+                        true,
+                        self.jobs,
+                    )
                     .await
                     .context(error::CloneRepositorySnafu)?;
             } else {
                 repository
-                    .cache(
+                    .cache_with_jobs(
                         &self.metadata_dir,
                         targets_dir,
                         Some(self.target_names.as_slice()),
                         true,
+                        self.jobs,
                     )
                     .await
                     .context(error::CloneRepositorySnafu)?;
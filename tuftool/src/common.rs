@@ -2,7 +2,7 @@
 use crate::error::{self, Result};
 use snafu::ResultExt;
 use std::path::Path;
-use tough::{Repository, RepositoryLoader};
+use tough::{HttpTransportBuilder, Repository, RepositoryLoader};
 use url::Url;
 
 /// Some commands only deal with metadata and never use a targets directory.
@@ -15,8 +15,13 @@ pub(crate) const UNUSED_URL: &str = "file:///unused/url";
 ///
 /// - `root` must be a path to a file that can be opened with `File::open`.
 /// - `metadata_url` can be local or remote.
+/// - `connection` holds the settings for fetching remote metadata.
 ///
-pub(crate) async fn load_metadata_repo<P>(root: P, metadata_url: Url) -> Result<Repository>
+pub(crate) async fn load_metadata_repo<P>(
+    root: P,
+    metadata_url: Url,
+    connection: &HttpTransportBuilder,
+) -> Result<Repository>
 where
     P: AsRef<Path>,
 {
@@ -31,6 +36,7 @@ where
             url: UNUSED_URL.to_owned(),
         })?,
     )
+    .transport(crate::http::transport(connection)?)
     .load()
     .await
     .context(error::RepoLoadSnafu)
@@ -12,6 +12,7 @@ use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use tough::editor::signed::PathExists;
 use tough::editor::RepositoryEditor;
+use tough::schema::HashAlgorithm;
 
 #[derive(Debug, Parser)]
 pub(crate) struct CreateArgs {
@@ -19,6 +20,11 @@ pub(crate) struct CreateArgs {
     #[arg(short, long)]
     follow: bool,
 
+    /// Hash algorithm to list for each added target; may be given more than once. Options are
+    /// "sha256" and "sha512"
+    #[arg(long = "hash-algorithm", default_value = "sha256")]
+    hash_algorithms: Vec<HashAlgorithm>,
+
     /// Number of target hashing threads to run when adding targets
     /// (default: number of cores)
     // No default is specified in structopt here. This is because rayon
@@ -94,7 +100,8 @@ impl CreateArgs {
                 .context(error::InitializeThreadPoolSnafu)?;
         }
 
-        let targets = build_targets(&self.targets_indir, self.follow).await?;
+        let targets =
+            build_targets(&self.targets_indir, self.follow, &self.hash_algorithms).await?;
         let mut editor = RepositoryEditor::new(&self.root)
             .await
             .context(error::EditorCreateSnafu { path: &self.root })?;
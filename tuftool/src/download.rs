@@ -3,11 +3,15 @@
 
 use crate::download_root::download_root;
 use crate::error::{self, Result};
+use crate::http::HttpArgs;
 use clap::Parser;
 use snafu::{ensure, ResultExt};
-use std::num::NonZeroU64;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::{Path, PathBuf};
-use tough::{ExpirationEnforcement, Prefix, Repository, RepositoryLoader, TargetName};
+use tough::{
+    ExpirationEnforcement, HttpTransportBuilder, Prefix, RateLimit, Repository, RepositoryLoader,
+    TargetName,
+};
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -28,6 +32,15 @@ pub(crate) struct DownloadArgs {
     #[arg(short = 'n', long = "target-name")]
     target_names: Vec<String>,
 
+    /// Number of targets to download at once
+    #[arg(short, long, default_value = "1")]
+    jobs: NonZeroUsize,
+
+    /// Limit the combined download rate, in bytes per second; a K, M or G suffix multiplies it by
+    /// 1024, 1024^2 or 1024^3
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    limit_rate: Option<NonZeroU64>,
+
     /// Path to root.json file for the repository
     #[arg(short, long)]
     root: Option<PathBuf>,
@@ -42,6 +55,9 @@ pub(crate) struct DownloadArgs {
     /// Remote root.json version number
     #[arg(short = 'v', long, default_value = "1")]
     root_version: NonZeroU64,
+
+    #[command(flatten)]
+    http: HttpArgs,
 }
 
 fn expired_repo_warning<P: AsRef<Path>>(path: P) {
@@ -55,19 +71,27 @@ WARNING: `--allow-expired-repo` was passed; this is unsafe and will not establis
 }
 
 impl DownloadArgs {
-    pub(crate) async fn run(&self) -> Result<()> {
+    pub(crate) async fn run(&self, connection: &HttpTransportBuilder) -> Result<()> {
         // To help ensure that downloads are safe, we require that the outdir does not exist.
         ensure!(
             !self.outdir.exists(),
             error::DownloadOutdirExistsSnafu { path: &self.outdir }
         );
 
+        let transport = self.http.transport(connection)?;
+
         // use local root.json or download from repository
         let root_path = if let Some(path) = &self.root {
             PathBuf::from(path)
         } else if self.allow_root_download {
             let outdir = std::env::current_dir().context(error::CurrentDirSnafu)?;
-            download_root(&self.metadata_base_url, self.root_version, outdir).await?
+            download_root(
+                &transport,
+                &self.metadata_base_url,
+                self.root_version,
+                outdir,
+            )
+            .await?
         } else {
             eprintln!("No root.json available");
             std::process::exit(1);
@@ -80,41 +104,52 @@ impl DownloadArgs {
         } else {
             ExpirationEnforcement::Safe
         };
-        let repository = RepositoryLoader::new(
-            &tokio::fs::read(&root_path)
-                .await
-                .context(error::OpenRootSnafu { path: &root_path })?,
+        let root = tokio::fs::read(&root_path)
+            .await
+            .context(error::OpenRootSnafu { path: &root_path })?;
+        let mut loader = RepositoryLoader::new(
+            &root,
             self.metadata_base_url.clone(),
             self.targets_base_url.clone(),
         )
         .expiration_enforcement(expiration_enforcement)
-        .load()
-        .await
-        .context(error::RepoLoadSnafu)?;
+        .transport(transport);
+        if let Some(limit_rate) = self.limit_rate {
+            loader = loader.rate_limit(RateLimit::new(limit_rate));
+        }
+        let repository = loader.load().await.context(error::RepoLoadSnafu)?;
 
         // download targets
-        handle_download(&repository, &self.outdir, &self.target_names).await
+        handle_download(&repository, &self.outdir, &self.target_names, self.jobs).await
     }
 }
 
+/// Parses a rate in bytes per second, such as `500K`.
+fn parse_rate(input: &str) -> std::result::Result<NonZeroU64, String> {
+    let (number, multiplier) = match input.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&input[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&input[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&input[..i], 1 << 30),
+        _ => (input, 1),
+    };
+    number
+        .parse::<NonZeroU64>()
+        .ok()
+        .and_then(|number| number.checked_mul(NonZeroU64::new(multiplier)?))
+        .ok_or_else(|| format!("expected a rate in bytes per second, such as 500K, got '{input}'"))
+}
+
 async fn handle_download(
     repository: &Repository,
     outdir: &Path,
     raw_names: &[String],
+    jobs: NonZeroUsize,
 ) -> Result<()> {
     let target_names: Result<Vec<TargetName>> = raw_names
         .iter()
         .map(|s| TargetName::new(s).context(error::InvalidTargetNameSnafu))
         .collect();
     let target_names = target_names?;
-    let download_target = |name: TargetName| async move {
-        println!("\t-> {}", name.raw());
-        repository
-            .save_target(&name, outdir, Prefix::None)
-            .await
-            .context(error::MetadataSnafu)?;
-        Ok(())
-    };
 
     // copy requested targets, or all available targets if not specified
     let targets: Vec<TargetName> = if target_names.is_empty() {
@@ -133,12 +168,40 @@ async fn handle_download(
     tokio::fs::create_dir_all(outdir)
         .await
         .context(error::DirCreateSnafu { path: outdir })?;
-    for target in targets {
-        download_target(target).await?;
+    let results = repository
+        .save_targets(&targets, outdir, Prefix::None, jobs)
+        .await;
+    let mut failed = 0_usize;
+    for (name, result) in &results {
+        match result {
+            Ok(()) => println!("\t-> {}", name.raw()),
+            Err(e) => {
+                eprintln!("\t-> {}: {e}", name.raw());
+                failed += 1;
+            }
+        }
     }
+    ensure!(
+        failed == 0,
+        error::DownloadTargetsSnafu {
+            failed,
+            total: results.len()
+        }
+    );
     Ok(())
 }
 
+#[test]
+fn parse_limit_rate() {
+    assert_eq!(parse_rate("1500").unwrap().get(), 1500);
+    assert_eq!(parse_rate("500K").unwrap().get(), 500 * 1024);
+    assert_eq!(parse_rate("2m").unwrap().get(), 2 * 1024 * 1024);
+    assert_eq!(parse_rate("1G").unwrap().get(), 1024 * 1024 * 1024);
+    for invalid in ["", "0", "0K", "K", "1.5M", "-1", "10T"] {
+        assert!(parse_rate(invalid).is_err(), "{}", invalid);
+    }
+}
+
 #[test]
 fn verify_download_args_cli() {
     use clap::CommandFactory;
@@ -9,11 +9,13 @@ use std::num::NonZeroU64;
 use std::path::{Path, PathBuf};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
+use tough::Transport;
 use url::Url;
 
 /// Download the given version of `root.json`
 /// This is an unsafe operation, and doesn't establish trust. It should only be used for testing!
 pub(crate) async fn download_root<P>(
+    transport: &dyn Transport,
     metadata_base_url: &Url,
     version: NonZeroU64,
     outdir: P,
@@ -31,19 +33,17 @@ where
         })?;
     root_warning(&path);
 
-    let root_request = reqwest::get(url.as_str())
+    let mut bytes_stream = transport
+        .fetch(url.clone())
         .await
-        .context(error::ReqwestGetSnafu)?
-        .error_for_status()
-        .context(error::BadResponseSnafu { url })?;
+        .context(error::DownloadRootSnafu { url: url.as_str() })?;
 
     let mut f = File::create(&path)
         .await
         .context(error::OpenFileSnafu { path: &path })?;
 
-    let bytes_stream = &mut root_request.bytes_stream();
     while let Some(bytes) = bytes_stream.next().await {
-        let bytes = bytes.context(error::ReqwestCopySnafu)?;
+        let bytes = bytes.context(error::DownloadRootSnafu { url: url.as_str() })?;
         f.write_all(&bytes)
             .await
             .with_context(|_| error::FileWriteSnafu { path: path.clone() })?;
@@ -79,9 +79,37 @@ pub(crate) enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Failed to download '{}': {}", url, source))]
+    DownloadRoot {
+        url: String,
+        source: tough::TransportError,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("A file or directory already exists at '{}'", path.display()))]
     DownloadOutdirExists { path: PathBuf, backtrace: Backtrace },
 
+    #[snafu(display("Failed to download {} of {} targets", failed, total))]
+    DownloadTargets {
+        failed: usize,
+        total: usize,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("The metadata of roles {} expires before {}", roles, time.to_rfc3339()))]
+    ExpiringRoles {
+        roles: String,
+        time: chrono::DateTime<chrono::Utc>,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to export bundle to '{}': {}", path.display(), source))]
+    ExportBundle {
+        path: PathBuf,
+        source: tough::error::Error,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display(
         "Failed to create a Repository Editor with root.json '{}': {}",
         path.display(),
@@ -141,6 +169,26 @@ pub(crate) enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Invalid HTTP settings: {}", source))]
+    HttpTransport {
+        source: tough::http::HttpError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to open bundle '{}': {}", path.display(), source))]
+    ImportBundle {
+        path: PathBuf,
+        source: tough::error::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to import {} of {} targets", failed, total))]
+    ImportTargets {
+        failed: usize,
+        total: usize,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Failed to initialize global thread pool: {}", source))]
     InitializeThreadPool {
         source: rayon::ThreadPoolBuildError,
@@ -205,12 +253,6 @@ pub(crate) enum Error {
         backtrace: Backtrace,
     },
 
-    #[snafu(display("Metadata error: {}", source))]
-    Metadata {
-        source: tough::error::Error,
-        backtrace: Backtrace,
-    },
-
     #[snafu(display("Missing: {}", what))]
     Missing { what: String, backtrace: Backtrace },
 
@@ -243,25 +285,6 @@ pub(crate) enum Error {
         backtrace: Backtrace,
     },
 
-    #[snafu(display("Failed to copy from response: {}", source))]
-    ReqwestCopy {
-        source: reqwest::Error,
-        backtrace: Backtrace,
-    },
-
-    #[snafu(display("Error making request: {}", source))]
-    ReqwestGet {
-        source: reqwest::Error,
-        backtrace: Backtrace,
-    },
-
-    #[snafu(display("Response '{}' from '{}': {}", get_status_code(source), url, source))]
-    BadResponse {
-        url: String,
-        source: reqwest::Error,
-        backtrace: Backtrace,
-    },
-
     #[snafu(display("Failed to sign repository: {}", source))]
     SignRepo {
         source: tough::error::Error,
@@ -357,12 +380,3 @@ pub(crate) enum Error {
         backtrace: Backtrace,
     },
 }
-
-// Extracts the status code from a reqwest::Error and converts it to a string to be displayed
-fn get_status_code(source: &reqwest::Error) -> String {
-    source
-        .status()
-        .as_ref()
-        .map_or("Unknown", reqwest::StatusCode::as_str)
-        .to_string()
-}
@@ -0,0 +1,92 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::common::UNUSED_URL;
+use crate::error::{self, Result};
+use clap::Parser;
+use snafu::ResultExt;
+use std::path::PathBuf;
+use tough::{ExpirationEnforcement, HttpTransportBuilder, RepositoryLoader};
+use url::Url;
+
+#[derive(Debug, Parser)]
+pub(crate) struct ExportArgs {
+    /// Allow exporting a repository with expired metadata (unsafe)
+    #[arg(long)]
+    allow_expired_repo: bool,
+
+    /// Only export the repository metadata, not the targets
+    #[arg(long, conflicts_with_all(&["target_names", "targets_base_url"]))]
+    metadata_only: bool,
+
+    /// TUF repository metadata base URL
+    #[arg(short, long = "metadata-url")]
+    metadata_base_url: Url,
+
+    /// Path to root.json file for the repository
+    #[arg(short, long)]
+    root: PathBuf,
+
+    /// Export only these targets, if specified
+    #[arg(short = 'n', long = "target-name")]
+    target_names: Vec<String>,
+
+    /// TUF repository targets base URL
+    #[arg(short, long = "targets-url", required_unless_present = "metadata_only")]
+    targets_base_url: Option<Url>,
+
+    /// Path of the bundle to write
+    outfile: PathBuf,
+}
+
+impl ExportArgs {
+    pub(crate) async fn run(&self, connection: &HttpTransportBuilder) -> Result<()> {
+        // When only exporting metadata, we don't ever need to access the targets URL, so we use a
+        // fake URL to satisfy the library.
+        let targets_base_url = match &self.targets_base_url {
+            Some(url) => url.clone(),
+            None => Url::parse(UNUSED_URL).context(error::UrlParseSnafu {
+                url: UNUSED_URL.to_owned(),
+            })?,
+        };
+
+        let expiration_enforcement = if self.allow_expired_repo {
+            ExpirationEnforcement::Unsafe
+        } else {
+            ExpirationEnforcement::Safe
+        };
+        let repository = RepositoryLoader::new(
+            &tokio::fs::read(&self.root)
+                .await
+                .context(error::OpenRootSnafu { path: &self.root })?,
+            self.metadata_base_url.clone(),
+            targets_base_url,
+        )
+        .expiration_enforcement(expiration_enforcement)
+        .transport(crate::http::transport(connection)?)
+        .load()
+        .await
+        .context(error::RepoLoadSnafu)?;
+
+        let targets_subset = if self.metadata_only {
+            Some(&[][..])
+        } else if self.target_names.is_empty() {
+            None
+        } else {
+            Some(self.target_names.as_slice())
+        };
+        println!("Exporting repository to {}", self.outfile.display());
+        repository
+            .export_bundle(&self.outfile, targets_subset)
+            .await
+            .context(error::ExportBundleSnafu {
+                path: &self.outfile,
+            })
+    }
+}
+
+#[test]
+fn verify_export_args_cli() {
+    use clap::CommandFactory;
+    ExportArgs::command().debug_assert();
+}
@@ -0,0 +1,235 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! The `http` module holds the options that configure how `tuftool` fetches repositories over
+//! HTTP, such as the proxy, the certificates to trust, and the credentials to send to each host.
+
+use crate::error::{self, Result};
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+use reqwest::header::{HeaderName, HeaderValue};
+use snafu::ResultExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tough::{CredentialProvider, Credentials, DefaultTransport, HttpTransportBuilder, TlsBackend};
+use url::Url;
+
+/// Options for how `tuftool` connects to repositories over HTTP(S), for every subcommand.
+#[derive(Debug, Args)]
+pub(crate) struct ConnectionArgs {
+    /// Send HTTP(S) requests through this proxy, instead of one from the environment
+    #[arg(long, global = true)]
+    proxy: Option<Url>,
+
+    /// Hosts to reach without the proxy, as a comma-separated list
+    #[arg(long, global = true, value_delimiter = ',', requires = "proxy")]
+    no_proxy: Vec<String>,
+
+    /// Path to a PEM file of root certificates to trust, in addition to the built-in ones
+    #[arg(long, global = true)]
+    cacert: Vec<PathBuf>,
+
+    /// Path to a PEM file of the client certificate chain, for servers that require mutual TLS
+    #[arg(long, global = true, requires = "client_key")]
+    client_cert: Option<PathBuf>,
+
+    /// Path to a PEM file of the client certificate's private key
+    #[arg(long, global = true, requires = "client_cert")]
+    client_key: Option<PathBuf>,
+
+    /// TLS implementation
+    #[arg(long, global = true, value_enum, default_value = "rustls")]
+    tls_backend: TlsBackendArg,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TlsBackendArg {
+    Rustls,
+    #[cfg(feature = "native-tls")]
+    NativeTls,
+}
+
+impl From<TlsBackendArg> for TlsBackend {
+    fn from(backend: TlsBackendArg) -> Self {
+        match backend {
+            TlsBackendArg::Rustls => TlsBackend::Rustls,
+            #[cfg(feature = "native-tls")]
+            TlsBackendArg::NativeTls => TlsBackend::NativeTls,
+        }
+    }
+}
+
+impl ConnectionArgs {
+    /// Reads the certificates and keys, and returns the settings that every HTTP transport starts
+    /// from. Only subcommands that fetch repositories need these settings.
+    pub(crate) async fn builder(&self) -> Result<HttpTransportBuilder> {
+        let mut builder = HttpTransportBuilder::new().tls_backend(self.tls_backend.into());
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone()).no_proxy(self.no_proxy.clone());
+        }
+        for path in &self.cacert {
+            builder = builder.add_root_certificates(read(path).await?);
+        }
+        if let (Some(certificate), Some(key)) = (&self.client_cert, &self.client_key) {
+            builder = builder.client_identity(read(certificate).await?, read(key).await?);
+        }
+        Ok(builder)
+    }
+}
+
+async fn read(path: &Path) -> Result<Vec<u8>> {
+    tokio::fs::read(path)
+        .await
+        .context(error::FileOpenSnafu { path })
+}
+
+/// Builds a transport for `file` and `http(s)` URLs with the `connection` settings.
+pub(crate) fn transport(connection: &HttpTransportBuilder) -> Result<DefaultTransport> {
+    DefaultTransport::try_new_with_http_settings(connection.clone())
+        .context(error::HttpTransportSnafu)
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct HttpArgs {
+    /// Send a header with requests to a host, as HOST=NAME:VALUE; HOST may include a port
+    #[arg(long = "http-header", value_name = "HOST=NAME:VALUE", value_parser = parse_header)]
+    headers: Vec<(String, HeaderName, HeaderValue)>,
+
+    /// Authenticate requests to a host with a bearer token, as HOST=TOKEN; other users may see
+    /// command-line arguments, so prefer --bearer-token-file
+    #[arg(long = "bearer-token", value_name = "HOST=TOKEN", value_parser = parse_host_value)]
+    bearer_tokens: Vec<(String, String)>,
+
+    /// Authenticate requests to a host with a bearer token read from a file, as HOST=PATH; the
+    /// file is read before each request, so the token may be rotated
+    #[arg(long = "bearer-token-file", value_name = "HOST=PATH", value_parser = parse_host_value)]
+    bearer_token_files: Vec<(String, String)>,
+
+    /// Authenticate requests to a host with HTTP basic authentication, as
+    /// HOST=USERNAME[:PASSWORD]; other users may see command-line arguments, so prefer
+    /// --basic-auth-file for passwords
+    #[arg(long = "basic-auth", value_name = "HOST=USERNAME[:PASSWORD]", value_parser = parse_host_value)]
+    basic_auth: Vec<(String, String)>,
+
+    /// Authenticate requests to a host with HTTP basic authentication, reading USERNAME[:PASSWORD]
+    /// from a file, as HOST=PATH; the file is read before each request, so the password may be
+    /// rotated
+    #[arg(long = "basic-auth-file", value_name = "HOST=PATH", value_parser = parse_host_value)]
+    basic_auth_files: Vec<(String, String)>,
+}
+
+impl HttpArgs {
+    /// Builds a transport for `file` and `http(s)` URLs with the `connection` settings, that also
+    /// sends the configured credentials.
+    pub(crate) fn transport(&self, connection: &HttpTransportBuilder) -> Result<DefaultTransport> {
+        let mut builder = connection.clone();
+        for (host, name, value) in &self.headers {
+            builder = builder.header(host.clone(), name.clone(), value.clone());
+        }
+        for (host, token) in &self.bearer_tokens {
+            builder = builder.bearer_auth(host.clone(), token.clone());
+        }
+        for (host, path) in &self.bearer_token_files {
+            builder = builder.credential_provider(
+                host.clone(),
+                Arc::new(CredentialsFile::Bearer(PathBuf::from(path))),
+            );
+        }
+        for (host, credentials) in &self.basic_auth {
+            let (username, password) = split_basic_auth(credentials);
+            builder = builder.basic_auth(host.clone(), username, password);
+        }
+        for (host, path) in &self.basic_auth_files {
+            builder = builder.credential_provider(
+                host.clone(),
+                Arc::new(CredentialsFile::Basic(PathBuf::from(path))),
+            );
+        }
+        DefaultTransport::try_new_with_http_settings(builder).context(error::HttpTransportSnafu)
+    }
+}
+
+/// Reads credentials from a file each time they are needed.
+#[derive(Debug)]
+enum CredentialsFile {
+    /// The file holds a bearer token.
+    Bearer(PathBuf),
+    /// The file holds `USERNAME[:PASSWORD]` for basic authentication.
+    Basic(PathBuf),
+}
+
+#[async_trait]
+impl CredentialProvider for CredentialsFile {
+    async fn credentials(
+        &self,
+        _url: &Url,
+    ) -> std::result::Result<Credentials, Box<dyn std::error::Error + Send + Sync>> {
+        let (CredentialsFile::Bearer(path) | CredentialsFile::Basic(path)) = self;
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .context(error::FileOpenSnafu { path })?;
+        let content = content.trim();
+        Ok(match self {
+            CredentialsFile::Bearer(_) => Credentials::Bearer(content.to_owned()),
+            CredentialsFile::Basic(_) => {
+                let (username, password) = split_basic_auth(content);
+                Credentials::Basic {
+                    username: username.to_owned(),
+                    password,
+                }
+            }
+        })
+    }
+}
+
+/// Splits `USERNAME[:PASSWORD]` into the username and password.
+fn split_basic_auth(credentials: &str) -> (&str, Option<String>) {
+    match credentials.split_once(':') {
+        Some((username, password)) => (username, Some(password.to_owned())),
+        None => (credentials, None),
+    }
+}
+
+/// Splits `HOST=VALUE` into the host and value.
+fn parse_host_value(input: &str) -> std::result::Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((host, value)) if !host.is_empty() => Ok((host.to_owned(), value.to_owned())),
+        _ => Err(format!("expected HOST=VALUE, got '{input}'")),
+    }
+}
+
+/// Parses `HOST=NAME:VALUE` into the host and header.
+fn parse_header(input: &str) -> std::result::Result<(String, HeaderName, HeaderValue), String> {
+    let (host, header) = parse_host_value(input)?;
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("expected HOST=NAME:VALUE, got '{input}'"))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| format!("invalid header name '{name}': {e}"))?;
+    let mut value = HeaderValue::from_str(value.trim())
+        .map_err(|e| format!("invalid value for header '{name}': {e}"))?;
+    value.set_sensitive(true);
+    Ok((host, name, value))
+}
+
+#[test]
+fn parse_http_args() {
+    assert_eq!(
+        parse_host_value("example.com:8443=user:password").unwrap(),
+        ("example.com:8443".to_owned(), "user:password".to_owned())
+    );
+    assert!(parse_host_value("=token").is_err());
+    assert!(parse_host_value("token").is_err());
+    assert_eq!(
+        split_basic_auth("user:pass:word"),
+        ("user", Some("pass:word".to_owned()))
+    );
+    assert_eq!(split_basic_auth("user"), ("user", None));
+
+    let (host, name, value) = parse_header("example.com=X-Api-Key: secret").unwrap();
+    assert_eq!(host, "example.com");
+    assert_eq!(name, "x-api-key");
+    assert_eq!(value, "secret");
+    assert!(value.is_sensitive());
+    assert!(parse_header("example.com=X-Api-Key").is_err());
+    assert!(parse_header("example.com=Bad Name: value").is_err());
+}
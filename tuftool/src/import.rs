@@ -0,0 +1,117 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::error::{self, Result};
+use clap::Parser;
+use snafu::{ensure, ResultExt};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use tough::error::Error;
+use tough::{ExpirationEnforcement, Prefix, RepositoryLoader, TargetName, TransportErrorKind};
+
+#[derive(Debug, Parser)]
+pub(crate) struct ImportArgs {
+    /// Allow importing a bundle with expired metadata (unsafe)
+    #[arg(long)]
+    allow_expired_repo: bool,
+
+    /// Import only these targets, if specified
+    #[arg(short = 'n', long = "target-name")]
+    target_names: Vec<String>,
+
+    /// Number of targets to import at once
+    #[arg(short, long, default_value = "1")]
+    jobs: NonZeroUsize,
+
+    /// Path to root.json file for the repository
+    #[arg(short, long)]
+    root: PathBuf,
+
+    /// Path of the bundle to import
+    bundle: PathBuf,
+
+    /// Output directory for targets (will be created and must not already exist)
+    outdir: PathBuf,
+}
+
+impl ImportArgs {
+    pub(crate) async fn run(&self) -> Result<()> {
+        // To help ensure that imports are safe, we require that the outdir does not exist.
+        ensure!(
+            !self.outdir.exists(),
+            error::DownloadOutdirExistsSnafu { path: &self.outdir }
+        );
+
+        let expiration_enforcement = if self.allow_expired_repo {
+            ExpirationEnforcement::Unsafe
+        } else {
+            ExpirationEnforcement::Safe
+        };
+        let root = tokio::fs::read(&self.root)
+            .await
+            .context(error::OpenRootSnafu { path: &self.root })?;
+        let mut repository = RepositoryLoader::from_bundle(&root, &self.bundle)
+            .context(error::ImportBundleSnafu { path: &self.bundle })?
+            .expiration_enforcement(expiration_enforcement)
+            .load()
+            .await
+            .context(error::RepoLoadSnafu)?;
+
+        // Import the requested targets, or every target that the bundle holds if not specified.
+        let all_targets = self.target_names.is_empty();
+        let targets = if all_targets {
+            repository
+                .load_delegated_targets()
+                .await
+                .context(error::RepoLoadSnafu)?;
+            repository
+                .targets()
+                .signed
+                .targets_map()
+                .into_keys()
+                .collect()
+        } else {
+            self.target_names
+                .iter()
+                .map(|s| TargetName::new(s).context(error::InvalidTargetNameSnafu))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        println!("Importing targets to {}", self.outdir.display());
+        tokio::fs::create_dir_all(&self.outdir)
+            .await
+            .context(error::DirCreateSnafu { path: &self.outdir })?;
+        let results = repository
+            .save_targets(&targets, &self.outdir, Prefix::None, self.jobs)
+            .await;
+        let mut failed = 0_usize;
+        for (name, result) in &results {
+            match result {
+                Ok(()) => println!("\t-> {}", name.raw()),
+                Err(Error::Transport { source, .. })
+                    if all_targets && source.kind() == TransportErrorKind::FileNotFound =>
+                {
+                    println!("\t-> {}: not in bundle, skipping", name.raw());
+                }
+                Err(e) => {
+                    eprintln!("\t-> {}: {e}", name.raw());
+                    failed += 1;
+                }
+            }
+        }
+        ensure!(
+            failed == 0,
+            error::ImportTargetsSnafu {
+                failed,
+                total: results.len()
+            }
+        );
+        Ok(())
+    }
+}
+
+#[test]
+fn verify_import_args_cli() {
+    use clap::CommandFactory;
+    ImportArgs::command().debug_assert();
+}
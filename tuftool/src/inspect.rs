@@ -0,0 +1,103 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::common::UNUSED_URL;
+use crate::datetime::parse_datetime;
+use crate::error::{self, Result};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use snafu::{ensure, ResultExt};
+use std::path::PathBuf;
+use tough::{ExpirationEnforcement, HttpTransportBuilder, RepositoryLoader};
+use url::Url;
+
+#[derive(Debug, Parser)]
+pub(crate) enum Command {
+    /// Report when the metadata of each role expires, including delegated roles
+    Expirations(ExpirationsArgs),
+}
+
+impl Command {
+    pub(crate) async fn run(self, connection: &HttpTransportBuilder) -> Result<()> {
+        match self {
+            Command::Expirations(args) => args.run(connection).await,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct ExpirationsArgs {
+    /// Fail if the metadata of any role expires before this time; can be in full RFC 3339 format,
+    /// or something like 'in 7 days'
+    #[arg(long, value_parser = parse_datetime, default_value = "in 7 days")]
+    expires_before: DateTime<Utc>,
+
+    /// TUF repository metadata base URL
+    #[arg(short, long = "metadata-url")]
+    metadata_base_url: Url,
+
+    /// Path to root.json file for the repository
+    #[arg(short, long)]
+    root: PathBuf,
+}
+
+impl ExpirationsArgs {
+    pub(crate) async fn run(&self, connection: &HttpTransportBuilder) -> Result<()> {
+        // We never access the targets URL, so we use a fake URL to satisfy the library. Expired
+        // metadata is loaded so that it can be reported, rather than failing the load.
+        let targets_base_url = Url::parse(UNUSED_URL).context(error::UrlParseSnafu {
+            url: UNUSED_URL.to_owned(),
+        })?;
+        let mut repository = RepositoryLoader::new(
+            &tokio::fs::read(&self.root)
+                .await
+                .context(error::OpenRootSnafu { path: &self.root })?,
+            self.metadata_base_url.clone(),
+            targets_base_url,
+        )
+        .expiration_enforcement(ExpirationEnforcement::Unsafe)
+        .transport(crate::http::transport(connection)?)
+        .load()
+        .await
+        .context(error::RepoLoadSnafu)?;
+        repository
+            .load_delegated_targets()
+            .await
+            .context(error::RepoLoadSnafu)?;
+
+        for expiration in repository.expirations() {
+            println!(
+                "{}{}: version {}, expires {} (signing role: {})",
+                if expiration.expires < self.expires_before {
+                    "EXPIRING "
+                } else {
+                    ""
+                },
+                expiration.role,
+                expiration.version,
+                expiration.expires.to_rfc3339(),
+                expiration.signing_role,
+            );
+        }
+
+        let expiring = repository.expiring_before(self.expires_before);
+        ensure!(
+            expiring.is_empty(),
+            error::ExpiringRolesSnafu {
+                roles: expiring
+                    .iter()
+                    .map(|expiration| expiration.role.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                time: self.expires_before,
+            }
+        );
+        Ok(())
+    }
+}
+
+#[test]
+fn verify_inspect_args_cli() {
+    use clap::CommandFactory;
+    ExpirationsArgs::command().debug_assert();
+}
@@ -22,6 +22,10 @@ mod datetime;
 mod download;
 mod download_root;
 mod error;
+mod export;
+mod http;
+mod import;
+mod inspect;
 mod remove_key_role;
 mod remove_role;
 mod root;
@@ -38,9 +42,10 @@ use snafu::{ErrorCompat, OptionExt, ResultExt};
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::Path;
+use std::sync::Arc;
 use tempfile::NamedTempFile;
 use tokio::runtime::Handle;
-use tough::schema::Target;
+use tough::schema::{HashAlgorithm, Target};
 use tough::TargetName;
 use walkdir::WalkDir;
 
@@ -53,6 +58,8 @@ struct Program {
     /// Set logging verbosity [trace|debug|info|warn|error]
     #[arg(id = "log-level", short, long, default_value = "info")]
     log_level: LevelFilter,
+    #[command(flatten)]
+    connection: http::ConnectionArgs,
     #[command(subcommand)]
     cmd: Command,
 }
@@ -69,7 +76,8 @@ impl Program {
             ColorChoice::Auto,
         )
         .context(error::LoggerSnafu)?;
-        self.cmd.run().await
+        // The subcommand futures are large, so keep them on the heap.
+        Box::pin(self.cmd.run(&self.connection)).await
     }
 }
 
@@ -83,6 +91,13 @@ enum Command {
     Delegation(Delegation),
     /// Download a TUF repository's targets
     Download(download::DownloadArgs),
+    /// Export a TUF repository's metadata and some or all targets to an offline bundle
+    Export(export::ExportArgs),
+    /// Verify an offline bundle and extract its targets
+    Import(import::ImportArgs),
+    /// Inspect a TUF repository's metadata
+    #[command(subcommand)]
+    Inspect(inspect::Command),
     /// Manipulate a root.json metadata file
     #[command(subcommand)]
     Root(root::Command),
@@ -93,15 +108,19 @@ enum Command {
 }
 
 impl Command {
-    async fn run(self) -> Result<()> {
+    async fn run(self, connection: &http::ConnectionArgs) -> Result<()> {
+        // Only subcommands that fetch repositories read the connection settings.
         match self {
             Command::Create(args) => args.run().await,
             Command::Root(root_subcommand) => root_subcommand.run().await,
-            Command::Download(args) => args.run().await,
-            Command::Update(args) => args.run().await,
-            Command::Delegation(cmd) => cmd.run().await,
-            Command::Clone(cmd) => cmd.run().await,
-            Command::TransferMetadata(cmd) => cmd.run().await,
+            Command::Download(args) => args.run(&connection.builder().await?).await,
+            Command::Export(args) => args.run(&connection.builder().await?).await,
+            Command::Import(args) => args.run().await,
+            Command::Inspect(cmd) => cmd.run(&connection.builder().await?).await,
+            Command::Update(args) => args.run(&connection.builder().await?).await,
+            Command::Delegation(cmd) => cmd.run(connection).await,
+            Command::Clone(cmd) => cmd.run(&connection.builder().await?).await,
+            Command::TransferMetadata(cmd) => cmd.run(&connection.builder().await?).await,
         }
     }
 }
@@ -152,12 +171,17 @@ where
     task.await.context(error::JoinTaskSnafu)?
 }
 
-// Walk the directory specified, building a map of filename to Target structs.
-// Hashing of the targets is done in parallel
-async fn build_targets<P>(indir: P, follow_links: bool) -> Result<HashMap<TargetName, Target>>
+// Walk the directory specified, building a map of filename to Target structs listing the digest
+// for each of `hash_algorithms`. Hashing of the targets is done in parallel
+async fn build_targets<P>(
+    indir: P,
+    follow_links: bool,
+    hash_algorithms: &[HashAlgorithm],
+) -> Result<HashMap<TargetName, Target>>
 where
     P: AsRef<Path>,
 {
+    let hash_algorithms = Arc::new(hash_algorithms.to_vec());
     let indir = indir.as_ref().to_owned();
 
     let (tx, rx) = tokio::sync::mpsc::channel(10);
@@ -182,11 +206,13 @@ where
         )
         .filter_map(|entry| {
             let indir = indir.clone();
+            let hash_algorithms = Arc::clone(&hash_algorithms);
             async move {
                 match entry {
                     Ok(entry) => {
                         if entry.file_type().is_file() {
-                            let future = async move { process_target(entry.path()).await };
+                            let future =
+                                async move { process_target(entry.path(), &hash_algorithms).await };
                             Some(Ok(tokio::task::spawn(future)))
                         } else {
                             None
@@ -207,7 +233,10 @@ where
         .collect()
 }
 
-async fn process_target(path: &Path) -> Result<(TargetName, Target)> {
+async fn process_target(
+    path: &Path,
+    hash_algorithms: &[HashAlgorithm],
+) -> Result<(TargetName, Target)> {
     // Get the file name as a TargetName
     let target_name = TargetName::new(
         path.file_name()
@@ -218,7 +247,7 @@ async fn process_target(path: &Path) -> Result<(TargetName, Target)> {
     .context(error::InvalidTargetNameSnafu)?;
 
     // Build a Target from the path given. If it is not a file, this will fail
-    let target = Target::from_path(path)
+    let target = Target::from_path_with_hashes(path, hash_algorithms)
         .await
         .context(error::TargetFromPathSnafu { path })?;
 
@@ -254,8 +283,8 @@ struct Delegation {
 }
 
 impl Delegation {
-    async fn run(self) -> Result<()> {
-        self.cmd.run(&self.role).await
+    async fn run(self, connection: &http::ConnectionArgs) -> Result<()> {
+        self.cmd.run(&self.role, connection).await
     }
 }
 
@@ -276,14 +305,18 @@ enum DelegationCommand {
 }
 
 impl DelegationCommand {
-    async fn run(self, role: &str) -> Result<()> {
+    async fn run(self, role: &str, connection: &http::ConnectionArgs) -> Result<()> {
         match self {
             DelegationCommand::CreateRole(args) => args.run(role).await,
-            DelegationCommand::AddRole(args) => args.run(role).await,
-            DelegationCommand::UpdateDelegatedTargets(args) => args.run(role).await,
-            DelegationCommand::AddKey(args) => args.run(role).await,
-            DelegationCommand::RemoveKey(args) => args.run(role).await,
-            DelegationCommand::Remove(args) => args.run(role).await,
+            DelegationCommand::AddRole(args) => args.run(role, &connection.builder().await?).await,
+            DelegationCommand::UpdateDelegatedTargets(args) => {
+                args.run(role, &connection.builder().await?).await
+            }
+            DelegationCommand::AddKey(args) => args.run(role, &connection.builder().await?).await,
+            DelegationCommand::RemoveKey(args) => {
+                args.run(role, &connection.builder().await?).await
+            }
+            DelegationCommand::Remove(args) => args.run(role, &connection.builder().await?).await,
         }
     }
 }
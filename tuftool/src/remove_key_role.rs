@@ -12,6 +12,7 @@ use std::num::NonZeroU64;
 use std::path::PathBuf;
 use tough::editor::targets::TargetsEditor;
 use tough::schema::decoded::{Decoded, Hex};
+use tough::HttpTransportBuilder;
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -51,11 +52,13 @@ pub(crate) struct RemoveKeyArgs {
 }
 
 impl RemoveKeyArgs {
-    pub(crate) async fn run(&self, role: &str) -> Result<()> {
-        let repository = load_metadata_repo(&self.root, self.metadata_base_url.clone()).await?;
+    pub(crate) async fn run(&self, role: &str, connection: &HttpTransportBuilder) -> Result<()> {
+        let repository =
+            load_metadata_repo(&self.root, self.metadata_base_url.clone(), connection).await?;
         self.remove_key(
             role,
             TargetsEditor::from_repo(repository, role)
+                .await
                 .context(error::EditorFromRepoSnafu { path: &self.root })?,
         )
         .await
@@ -11,6 +11,7 @@ use snafu::ResultExt;
 use std::num::NonZeroU64;
 use std::path::PathBuf;
 use tough::editor::targets::TargetsEditor;
+use tough::HttpTransportBuilder;
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -50,11 +51,13 @@ pub(crate) struct RemoveRoleArgs {
 }
 
 impl RemoveRoleArgs {
-    pub(crate) async fn run(&self, role: &str) -> Result<()> {
-        let repository = load_metadata_repo(&self.root, self.metadata_base_url.clone()).await?;
+    pub(crate) async fn run(&self, role: &str, connection: &HttpTransportBuilder) -> Result<()> {
+        let repository =
+            load_metadata_repo(&self.root, self.metadata_base_url.clone(), connection).await?;
         self.remove_delegated_role(
             role,
             TargetsEditor::from_repo(repository, role)
+                .await
                 .context(error::EditorFromRepoSnafu { path: &self.root })?,
         )
         .await
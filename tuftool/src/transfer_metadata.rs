@@ -10,7 +10,7 @@ use snafu::ResultExt;
 use std::num::NonZeroU64;
 use std::path::{Path, PathBuf};
 use tough::editor::RepositoryEditor;
-use tough::{ExpirationEnforcement, RepositoryLoader};
+use tough::{ExpirationEnforcement, HttpTransportBuilder, RepositoryLoader};
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -80,7 +80,7 @@ WARNING: `--allow-expired-repo` was passed; this is unsafe and will not establis
 }
 
 impl TransferMetadataArgs {
-    pub(crate) async fn run(&self) -> Result<()> {
+    pub(crate) async fn run(&self, connection: &HttpTransportBuilder) -> Result<()> {
         let mut keys = Vec::new();
         for source in &self.keys {
             let key_source = parse_key_source(source)?;
@@ -107,6 +107,7 @@ impl TransferMetadataArgs {
             self.targets_base_url.clone(),
         )
         .expiration_enforcement(expiration_enforcement)
+        .transport(crate::http::transport(connection)?)
         .load()
         .await
         .context(error::RepoLoadSnafu)?;
@@ -13,7 +13,8 @@ use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::{Path, PathBuf};
 use tough::editor::signed::PathExists;
 use tough::editor::RepositoryEditor;
-use tough::{ExpirationEnforcement, RepositoryLoader};
+use tough::schema::HashAlgorithm;
+use tough::{ExpirationEnforcement, HttpTransportBuilder, RepositoryLoader};
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -26,6 +27,11 @@ pub(crate) struct UpdateArgs {
     #[arg(short, long)]
     follow: bool,
 
+    /// Hash algorithm to list for each added target; may be given more than once. Options are
+    /// "sha256" and "sha512"
+    #[arg(long = "hash-algorithm", default_value = "sha256")]
+    hash_algorithms: Vec<HashAlgorithm>,
+
     /// Incoming metadata from delegatee
     #[arg(short, long = "incoming-metadata")]
     indir: Option<Url>,
@@ -107,7 +113,7 @@ WARNING: `--allow-expired-repo` was passed; this is unsafe and will not establis
 }
 
 impl UpdateArgs {
-    pub(crate) async fn run(&self) -> Result<()> {
+    pub(crate) async fn run(&self, connection: &HttpTransportBuilder) -> Result<()> {
         let expiration_enforcement = if self.allow_expired_repo {
             expired_repo_warning(&self.outdir);
             ExpirationEnforcement::Unsafe
@@ -122,6 +128,7 @@ impl UpdateArgs {
             Url::parse(UNUSED_URL).context(error::UrlParseSnafu { url: UNUSED_URL })?,
         )
         .expiration_enforcement(expiration_enforcement)
+        .transport(crate::http::transport(connection)?)
         .load()
         .await
         .context(error::RepoLoadSnafu)?;
@@ -161,7 +168,8 @@ impl UpdateArgs {
                     .context(error::InitializeThreadPoolSnafu)?;
             }
 
-            let new_targets = build_targets(targets_indir, self.follow).await?;
+            let new_targets =
+                build_targets(targets_indir, self.follow, &self.hash_algorithms).await?;
 
             for (target_name, target) in new_targets {
                 editor
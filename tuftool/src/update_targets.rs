@@ -14,6 +14,8 @@ use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use tough::editor::signed::PathExists;
 use tough::editor::targets::TargetsEditor;
+use tough::schema::HashAlgorithm;
+use tough::HttpTransportBuilder;
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -27,6 +29,11 @@ pub(crate) struct UpdateTargetsArgs {
     #[arg(short, long)]
     follow: bool,
 
+    /// Hash algorithm to list for each added target; may be given more than once. Options are
+    /// "sha256" and "sha512"
+    #[arg(long = "hash-algorithm", default_value = "sha256")]
+    hash_algorithms: Vec<HashAlgorithm>,
+
     /// Number of target hashing threads to run when adding targets
     /// (default: number of cores)
     // No default is specified in structopt here. This is because rayon
@@ -67,10 +74,12 @@ pub(crate) struct UpdateTargetsArgs {
 }
 
 impl UpdateTargetsArgs {
-    pub(crate) async fn run(&self, role: &str) -> Result<()> {
-        let repository = load_metadata_repo(&self.root, self.metadata_base_url.clone()).await?;
+    pub(crate) async fn run(&self, role: &str, connection: &HttpTransportBuilder) -> Result<()> {
+        let repository =
+            load_metadata_repo(&self.root, self.metadata_base_url.clone(), connection).await?;
         self.update_targets(
             TargetsEditor::from_repo(repository, role)
+                .await
                 .context(error::EditorFromRepoSnafu { path: &self.root })?,
         )
         .await
@@ -96,7 +105,8 @@ impl UpdateTargetsArgs {
                     .context(error::InitializeThreadPoolSnafu)?;
             }
 
-            let new_targets = build_targets(targets_indir, self.follow).await?;
+            let new_targets =
+                build_targets(targets_indir, self.follow, &self.hash_algorithms).await?;
 
             for (target_name, target) in new_targets {
                 editor
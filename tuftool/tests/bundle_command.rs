@@ -0,0 +1,119 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod test_utils;
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use test_utils::{dir_url, test_data};
+
+fn reference_impl() -> PathBuf {
+    test_data().join("tuf-reference-impl")
+}
+
+fn root_path() -> PathBuf {
+    reference_impl().join("metadata").join("1.root.json")
+}
+
+/// Runs `tuftool export` for the TUF reference implementation repository with `args`.
+fn export(bundle: &Path, args: &[&str]) {
+    Command::cargo_bin("tuftool")
+        .unwrap()
+        .args([
+            "export",
+            "--root",
+            root_path().to_str().unwrap(),
+            "--metadata-url",
+            dir_url(reference_impl().join("metadata")).as_str(),
+            bundle.to_str().unwrap(),
+        ])
+        .args(args)
+        .assert()
+        .success();
+}
+
+/// Runs `tuftool import` for `bundle` into `outdir`.
+fn import(bundle: &Path, outdir: &Path) -> assert_cmd::assert::Assert {
+    Command::cargo_bin("tuftool")
+        .unwrap()
+        .args([
+            "import",
+            "--root",
+            root_path().to_str().unwrap(),
+            bundle.to_str().unwrap(),
+            outdir.to_str().unwrap(),
+        ])
+        .assert()
+}
+
+fn assert_targets(outdir: &Path, names: &[&str]) {
+    let mut found = std::fs::read_dir(outdir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    found.sort_unstable();
+    assert_eq!(found, names);
+    for name in names {
+        assert_eq!(
+            std::fs::read(outdir.join(name)).unwrap(),
+            std::fs::read(reference_impl().join("targets").join(name)).unwrap()
+        );
+    }
+}
+
+#[test]
+// Ensure that every target of an exported bundle is imported
+fn export_import_all_targets() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("repo.tar");
+    let targets_url = dir_url(reference_impl().join("targets"));
+    export(&bundle, &["--targets-url", targets_url.as_str()]);
+
+    let outdir = dir.path().join("targets");
+    import(&bundle, &outdir).success();
+    assert_targets(&outdir, &["file1.txt", "file2.txt", "file3.txt"]);
+}
+
+#[test]
+// Ensure that only the targets in a bundle are imported
+fn export_import_targets_subset() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("repo.tar");
+    let targets_url = dir_url(reference_impl().join("targets"));
+    export(
+        &bundle,
+        &["--targets-url", targets_url.as_str(), "-n", "file2.txt"],
+    );
+
+    let outdir = dir.path().join("targets");
+    import(&bundle, &outdir).success();
+    assert_targets(&outdir, &["file2.txt"]);
+}
+
+#[test]
+// Ensure that a metadata-only bundle can be imported, and that it does not hold any targets
+fn export_import_metadata_only() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("repo.tar");
+    export(&bundle, &["--metadata-only"]);
+
+    let outdir = dir.path().join("targets");
+    import(&bundle, &outdir).success();
+    assert_targets(&outdir, &[]);
+
+    let outdir = dir.path().join("named");
+    Command::cargo_bin("tuftool")
+        .unwrap()
+        .args([
+            "import",
+            "--root",
+            root_path().to_str().unwrap(),
+            "-n",
+            "file1.txt",
+            bundle.to_str().unwrap(),
+            outdir.to_str().unwrap(),
+        ])
+        .assert()
+        .failure();
+}
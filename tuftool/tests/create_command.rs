@@ -180,3 +180,104 @@ fn create_with_no_key() {
         .assert()
         .failure();
 }
+
+#[tokio::test]
+// Ensure the create command lists the hash algorithms it's given for each target
+async fn create_with_hash_algorithms() {
+    let targets_input_dir = test_utils::test_data()
+        .join("tuf-reference-impl")
+        .join("targets");
+    let root_json = test_utils::test_data().join("simple-rsa").join("root.json");
+    let root_key = test_utils::test_data().join("snakeoil.pem");
+    let repo_dir = TempDir::new().unwrap();
+
+    Command::cargo_bin("tuftool")
+        .unwrap()
+        .args([
+            "create",
+            "-t",
+            targets_input_dir.to_str().unwrap(),
+            "-o",
+            repo_dir.path().to_str().unwrap(),
+            "-k",
+            root_key.to_str().unwrap(),
+            "--root",
+            root_json.to_str().unwrap(),
+            "--hash-algorithm",
+            "sha256",
+            "--hash-algorithm",
+            "sha512",
+            "--targets-expires",
+            "in 7 days",
+            "--targets-version",
+            "1",
+            "--snapshot-expires",
+            "in 7 days",
+            "--snapshot-version",
+            "1",
+            "--timestamp-expires",
+            "in 7 days",
+            "--timestamp-version",
+            "1",
+        ])
+        .assert()
+        .success();
+
+    let repo = RepositoryLoader::new(
+        &tokio::fs::read(root_json).await.unwrap(),
+        dir_url(repo_dir.path().join("metadata")),
+        dir_url(repo_dir.path().join("targets")),
+    )
+    .load()
+    .await
+    .unwrap();
+    let file1 = TargetName::new("file1.txt").unwrap();
+    let hashes = &repo.targets().signed.targets[&file1].hashes;
+    assert!(hashes.sha256.is_some());
+    assert!(hashes.sha512.is_some());
+    assert_eq!(
+        test_utils::read_to_end(repo.read_target(&file1).await.unwrap().unwrap()).await,
+        &b"This is an example target file."[..]
+    );
+}
+
+#[test]
+// Ensure the create command, which fetches nothing, doesn't read the HTTP connection settings
+fn create_ignores_connection_settings() {
+    let targets_input_dir = test_utils::test_data()
+        .join("tuf-reference-impl")
+        .join("targets");
+    let root_json = test_utils::test_data().join("simple-rsa").join("root.json");
+    let root_key = test_utils::test_data().join("snakeoil.pem");
+    let repo_dir = TempDir::new().unwrap();
+
+    Command::cargo_bin("tuftool")
+        .unwrap()
+        .args([
+            "--cacert",
+            repo_dir.path().join("missing.pem").to_str().unwrap(),
+            "create",
+            "-t",
+            targets_input_dir.to_str().unwrap(),
+            "-o",
+            repo_dir.path().join("repo").to_str().unwrap(),
+            "-k",
+            root_key.to_str().unwrap(),
+            "--root",
+            root_json.to_str().unwrap(),
+            "--targets-expires",
+            "in 7 days",
+            "--targets-version",
+            "1",
+            "--snapshot-expires",
+            "in 7 days",
+            "--snapshot-version",
+            "1",
+            "--timestamp-expires",
+            "in 7 days",
+            "--timestamp-version",
+            "1",
+        ])
+        .assert()
+        .success();
+}
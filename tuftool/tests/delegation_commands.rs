@@ -202,6 +202,7 @@ async fn create_add_role_command() {
             "1",
             "-v",
             "2",
+            "--terminating",
         ])
         .assert()
         .success();
@@ -244,7 +245,7 @@ async fn create_add_role_command() {
         .success();
 
     // Load the updated repo
-    let repo = RepositoryLoader::new(
+    let mut repo = RepositoryLoader::new(
         &tokio::fs::read(root_json).await.unwrap(),
         dir_url(update_out.path().join("metadata")),
         dir_url(update_out.path().join("targets")),
@@ -252,9 +253,11 @@ async fn create_add_role_command() {
     .load()
     .await
     .unwrap();
+    repo.load_delegated_targets().await.unwrap();
 
-    // Make sure `B` is added as a role
-    assert!(repo.delegated_role("B").is_some());
+    // Make sure `B` is added as a terminating role
+    assert!(repo.delegated_role("B").unwrap().terminating);
+    assert!(!repo.delegated_role("A").unwrap().terminating);
 }
 #[tokio::test]
 // Ensure we can update targets of delegated roles
@@ -671,7 +674,7 @@ async fn add_key_command() {
         .success();
 
     // Load the updated repo
-    let _repo = RepositoryLoader::new(
+    let mut repo = RepositoryLoader::new(
         &tokio::fs::read(root_json).await.unwrap(),
         dir_url(update_out.path().join("metadata")),
         dir_url(update_out.path().join("targets")),
@@ -679,6 +682,7 @@ async fn add_key_command() {
     .load()
     .await
     .unwrap();
+    repo.load_delegated_targets().await.unwrap();
 }
 
 #[test]
@@ -1149,7 +1153,7 @@ async fn remove_role_command() {
         .success();
 
     // Load the updated repo
-    let repo = RepositoryLoader::new(
+    let mut repo = RepositoryLoader::new(
         &tokio::fs::read(root_json).await.unwrap(),
         dir_url(update_out.path().join("metadata")),
         dir_url(update_out.path().join("targets")),
@@ -1157,6 +1161,7 @@ async fn remove_role_command() {
     .load()
     .await
     .unwrap();
+    repo.load_delegated_targets().await.unwrap();
 
     // Make sure `B` is removed
     assert!(repo.delegated_role("B").is_none());
@@ -1647,7 +1652,7 @@ async fn dubious_role_name() {
         .success();
 
     // Load the updated repo
-    let repo = RepositoryLoader::new(
+    let mut repo = RepositoryLoader::new(
         &tokio::fs::read(root_json).await.unwrap(),
         dir_url(update_out.path().join("metadata")),
         dir_url(update_out.path().join("targets")),
@@ -1655,6 +1660,7 @@ async fn dubious_role_name() {
     .load()
     .await
     .unwrap();
+    repo.load_delegated_targets().await.unwrap();
 
     // Make sure `B` is added as a role
     assert!(repo.delegated_role(funny_role_name).is_some());
@@ -0,0 +1,58 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod test_utils;
+
+use assert_cmd::Command;
+use test_utils::{dir_url, test_data};
+
+/// Runs `tuftool inspect expirations` for the TUF reference implementation repository, whose
+/// metadata all expires at 2030-01-01T00:00:00Z.
+fn inspect_expirations(expires_before: &str) -> assert_cmd::assert::Assert {
+    let base = test_data().join("tuf-reference-impl");
+    Command::cargo_bin("tuftool")
+        .unwrap()
+        .args([
+            "inspect",
+            "expirations",
+            "--root",
+            base.join("metadata").join("1.root.json").to_str().unwrap(),
+            "--metadata-url",
+            dir_url(base.join("metadata")).as_str(),
+            "--expires-before",
+            expires_before,
+        ])
+        .assert()
+}
+
+#[test]
+// Ensure that every role is reported, and that nothing expiring succeeds
+fn inspect_expirations_ok() {
+    let output = inspect_expirations("2029-12-01T00:00:00Z").success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    for role in ["root", "timestamp", "snapshot", "targets", "role1", "role2"] {
+        let expected = format!("{role}: version 1, expires 2030-01-01T00:00:00+00:00");
+        assert!(
+            stdout.lines().any(|line| line.starts_with(&expected)),
+            "{}",
+            stdout
+        );
+    }
+    assert!(stdout
+        .contains("role2: version 1, expires 2030-01-01T00:00:00+00:00 (signing role: role1)"));
+    assert!(!stdout.contains("EXPIRING"));
+}
+
+#[test]
+// Ensure that roles expiring before the given time fail the command
+fn inspect_expirations_expiring() {
+    let output = inspect_expirations("2030-02-01T00:00:00Z").failure();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert!(stdout.contains("EXPIRING role1: version 1"), "{}", stdout);
+    let stderr = String::from_utf8(output.get_output().stderr.clone()).unwrap();
+    assert!(
+        stderr.contains("root, timestamp, snapshot, targets, role1, role2"),
+        "{}",
+        stderr
+    );
+}